@@ -1,12 +1,13 @@
+use std::process::ExitCode;
 use track::{ error::AppError, init };
 use error_stack::Result;
 
-fn main() -> Result<(), AppError> {
+fn main() -> Result<ExitCode, AppError> {
     init::error_reporting();
     init::tracing();
-    init
+    let exit_code = init
         ::cli_init()
         .map_err(|e| e.change_context(AppError).attach_printable("failed to initialize cli"))?;
 
-    Ok(())
+    Ok(exit_code)
 }
@@ -1,3 +1,25 @@
 pub mod string_ext;
 pub mod cli;
-pub mod tracker;
\ No newline at end of file
+pub mod tracker;
+pub mod time_expr;
+pub mod duration;
+pub mod prompt;
+pub mod settings;
+pub mod day_boundary;
+pub mod session_guard;
+pub mod heartbeat;
+pub mod report;
+pub mod aggregate;
+pub mod context;
+pub mod git;
+pub mod project;
+pub mod client;
+pub mod billing;
+pub mod rounding;
+pub mod invoice;
+pub mod budget;
+pub mod target;
+pub mod balance;
+pub mod time_off;
+pub mod export;
+pub mod render;
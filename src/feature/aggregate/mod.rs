@@ -0,0 +1,277 @@
+use std::collections::{ BTreeSet, HashMap };
+use chrono::{ Duration, NaiveDate, NaiveTime };
+use clap::ValueEnum;
+
+use super::{
+    client::{ self, Client },
+    day_boundary,
+    report::UNKNOWN,
+    time_off::{ self, DayOff },
+    tracker::entry::Entry,
+};
+
+/// A level of grouped reports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    Day,
+    /// ISO weeks such as 2026-W38, starting on Monday
+    Week,
+    Month,
+    /// The full project path
+    Project,
+    /// Entries with several tags count towards each of them
+    Tag,
+    /// The client of the project
+    Client,
+}
+
+impl GroupBy {
+    /// Whether groups are ordered by time, oldest first, rather than longest first.
+    pub fn is_chronological(self) -> bool {
+        matches!(self, Self::Day | Self::Week | Self::Month)
+    }
+}
+
+/// Time of a group, with the subgroups of the next level.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Group {
+    pub key: String,
+    pub time: Duration,
+    pub children: Vec<Group>,
+}
+
+impl Group {
+    /// The groups below this one depth first, each with its level below this group.
+    pub fn flatten(&self) -> Vec<(usize, &Group)> {
+        let mut rows = Vec::new();
+        for child in &self.children {
+            rows.push((0, child));
+            rows.extend(child.flatten().into_iter().map(|(depth, group)| (depth + 1, group)));
+        }
+        rows
+    }
+}
+
+/// The time of `entries` inside `[from, to)` grouped by each of `levels` in turn, under a
+/// root group holding the grand total. Entries are split at the start of each day, so the
+/// time of an entry running past midnight counts towards both days.
+///
+/// Time is only counted once per group, even when an entry with several tags shows up in the
+/// groups of each of its tags.
+pub fn aggregate(
+    entries: &[Entry],
+    levels: &[GroupBy],
+    clients: &[Client],
+    day_start: NaiveTime,
+    from: u64,
+    to: u64,
+    now: u64
+) -> Group {
+    let mut items: Vec<Item> = Vec::new();
+    let pieces = entries.iter().flat_map(|entry| day_boundary::split(entry, day_start, now));
+    for (piece, entry) in pieces.enumerate() {
+        let time = entry.overlap(from, to, now);
+        if time <= Duration::zero() {
+            continue;
+        }
+        let mut keys: Vec<Vec<String>> = vec![Vec::new()];
+        for level in levels {
+            keys = keys
+                .into_iter()
+                .flat_map(|prefix| {
+                    keys_of(&entry, *level, clients, day_start)
+                        .into_iter()
+                        .map(move |key| {
+                            let mut keys = prefix.clone();
+                            keys.push(key);
+                            keys
+                        })
+                })
+                .collect();
+        }
+        items.extend(keys.into_iter().map(|keys| Item { piece, keys, time }));
+    }
+    let all: Vec<&Item> = items.iter().collect();
+    Group { key: "total".to_owned(), time: total(&all), children: groups(&all, levels, 0) }
+}
+
+/// Marks the day groups that fall on a day off with its kind, e.g. `2026-12-24 (vacation)`.
+/// When days are the first level, days off from `first` to `last` without tracked time get
+/// an empty group too, so that they show up as off rather than as missing time.
+pub fn mark_days_off(
+    total: &mut Group,
+    levels: &[GroupBy],
+    days_off: &[DayOff],
+    first: NaiveDate,
+    last: NaiveDate
+) {
+    if levels.first() == Some(&GroupBy::Day) {
+        for off in days_off.iter().filter(|off| (first..=last).contains(&off.date)) {
+            let key = off.date.format("%Y-%m-%d").to_string();
+            if !total.children.iter().any(|group| group.key == key) {
+                total.children.push(Group { key, time: Duration::zero(), children: Vec::new() });
+            }
+        }
+        total.children.sort_by(|a, b| a.key.cmp(&b.key));
+    }
+    label_days_off(total, levels, days_off);
+}
+
+fn label_days_off(group: &mut Group, levels: &[GroupBy], days_off: &[DayOff]) {
+    let Some((level, rest)) = levels.split_first() else {
+        return;
+    };
+    for child in &mut group.children {
+        if *level == GroupBy::Day {
+            let off = NaiveDate::parse_from_str(&child.key, "%Y-%m-%d")
+                .ok()
+                .and_then(|day| time_off::on(days_off, day));
+            if let Some(off) = off {
+                child.key = format!("{} ({})", child.key, off.kind);
+            }
+        }
+        label_days_off(child, rest, days_off);
+    }
+}
+
+/// A piece of an entry with its key on every level.
+struct Item {
+    piece: usize,
+    keys: Vec<String>,
+    time: Duration,
+}
+
+fn keys_of(entry: &Entry, level: GroupBy, clients: &[Client], day_start: NaiveTime) -> Vec<String> {
+    let day = || day_boundary::day_of(entry.start, day_start);
+    let project = entry.details.project.as_deref();
+    let key = match level {
+        GroupBy::Day => Some(day().format("%Y-%m-%d").to_string()),
+        GroupBy::Week => Some(day().format("%G-W%V").to_string()),
+        GroupBy::Month => Some(day().format("%Y-%m").to_string()),
+        GroupBy::Project => project.map(str::to_owned),
+        GroupBy::Client =>
+            project
+                .and_then(|project| client::client_of(clients, project))
+                .map(|client| client.name.clone()),
+        GroupBy::Tag if !entry.details.tags.is_empty() => {
+            let tags: BTreeSet<String> = entry.details.tags.iter().cloned().collect();
+            return tags.into_iter().collect();
+        }
+        GroupBy::Tag => None,
+    };
+    vec![key.unwrap_or_else(|| UNKNOWN.to_owned())]
+}
+
+/// The time of `items`, counting each piece of an entry once.
+fn total(items: &[&Item]) -> Duration {
+    let mut seen = BTreeSet::new();
+    items
+        .iter()
+        .filter(|item| seen.insert(item.piece))
+        .map(|item| item.time)
+        .sum()
+}
+
+fn groups(items: &[&Item], levels: &[GroupBy], depth: usize) -> Vec<Group> {
+    let Some(level) = levels.get(depth) else {
+        return Vec::new();
+    };
+    let mut by_key: HashMap<&str, Vec<&Item>> = HashMap::new();
+    for item in items {
+        by_key.entry(&item.keys[depth]).or_default().push(item);
+    }
+    let mut groups: Vec<Group> = by_key
+        .into_iter()
+        .map(|(key, items)| Group {
+            key: key.to_owned(),
+            time: total(&items),
+            children: groups(&items, levels, depth + 1),
+        })
+        .collect();
+    if level.is_chronological() {
+        groups.sort_by(|a, b| a.key.cmp(&b.key));
+    } else {
+        groups.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| a.key.cmp(&b.key)));
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::feature::tracker::entry::EntryDetails;
+    use super::*;
+
+    /// Monday 2026-09-14 at midnight UTC.
+    const MONDAY: u64 = 1_789_344_000;
+
+    fn entry(start: u64, hours: u64, project: Option<&str>, tags: &[&str]) -> Entry {
+        Entry {
+            start,
+            stop: Some(start + hours * 3600),
+            details: EntryDetails {
+                project: project.map(str::to_owned),
+                tags: tags
+                    .iter()
+                    .map(|tag| (*tag).to_owned())
+                    .collect(),
+                ..EntryDetails::default()
+            },
+            ..Entry::default()
+        }
+    }
+
+    fn rows(group: &Group) -> Vec<(usize, String, i64)> {
+        group
+            .flatten()
+            .into_iter()
+            .map(|(depth, group)| (depth, group.key.clone(), group.time.num_hours()))
+            .collect()
+    }
+
+    #[test]
+    fn groups_nest_with_subtotals() {
+        let day = 86_400;
+        let entries = [
+            entry(MONDAY + 9 * 3600, 2, Some("acme/api"), &["code"]),
+            entry(MONDAY + 7 * day + 9 * 3600, 4, Some("acme/web"), &["code", "review"]),
+            entry(MONDAY + 7 * day + 13 * 3600, 1, None, &[]),
+            entry(MONDAY + 8 * day + 9 * 3600, 1, Some("acme/api"), &[]),
+        ];
+        let to = MONDAY + 14 * day;
+        let clients = [Client { name: "Acme".to_owned(), projects: vec!["acme".to_owned()], ..Client::default() }];
+        let group = |levels: &[GroupBy]| aggregate(&entries, levels, &clients, NaiveTime::MIN, MONDAY, to, to);
+        // Day keys depend on the local time zone, so weeks are only checked through their totals
+        let weeks = group(&[GroupBy::Week, GroupBy::Project]);
+        assert_eq!(weeks.time.num_hours(), 8);
+        assert_eq!(weeks.children.len(), 2);
+        assert!(weeks.children[0].key < weeks.children[1].key);
+
+        let projects = group(&[GroupBy::Client, GroupBy::Project]);
+        assert_eq!(
+            rows(&projects),
+            vec![
+                (0, "Acme".to_owned(), 7),
+                (1, "acme/web".to_owned(), 4),
+                (1, "acme/api".to_owned(), 3),
+                (0, UNKNOWN.to_owned(), 1),
+                (1, UNKNOWN.to_owned(), 1)
+            ]
+        );
+
+        let tags = group(&[GroupBy::Project, GroupBy::Tag]);
+        assert_eq!(tags.time.num_hours(), 8);
+        assert_eq!(
+            rows(&tags)[..3],
+            [
+                (0, "acme/web".to_owned(), 4),
+                (1, "code".to_owned(), 4),
+                (1, "review".to_owned(), 4)
+            ]
+        );
+        let by_tag = group(&[GroupBy::Tag]);
+        assert_eq!(
+            rows(&by_tag),
+            vec![(0, "code".to_owned(), 6), (0, "review".to_owned(), 4), (0, UNKNOWN.to_owned(), 2)]
+        );
+    }
+}
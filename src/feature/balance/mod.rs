@@ -0,0 +1,251 @@
+use std::{ collections::BTreeMap, fmt };
+use chrono::{ Datelike, Duration, NaiveDate, NaiveTime };
+use clap::ValueEnum;
+use error_stack::Report;
+use serde::{ Deserialize, Serialize };
+use thiserror::Error;
+
+use super::{
+    day_boundary,
+    duration::{ self, DurationFormat },
+    target::{ DailyTarget, WEEKDAYS },
+    time_off::{ self, DayOff, OffKind },
+    tracker::entry::Entry,
+};
+
+#[derive(Error, Debug)]
+pub enum BalanceError {
+    #[error("invalid schedule: {0:?}, expected e.g. \"8h mon-fri\" or \"8h mon-thu; 6h fri\"")]
+    InvalidSchedule(String),
+
+    #[error("no work schedule set, set one with e.g. \"track config schedule '8h mon-fri'\"")]
+    NoSchedule,
+}
+
+/// Hours to work on each weekday, in seconds from Monday to Sunday.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schedule(pub [i64; 7]);
+
+impl Schedule {
+    /// Parses `;` separated daily targets such as `8h mon-thu; 6h fri`. Later parts override
+    /// earlier ones on the same weekday.
+    pub fn parse(input: &str) -> Result<Self, Report<BalanceError>> {
+        let mut schedule = Self::default();
+        for part in input.split(';') {
+            let target = DailyTarget
+                ::parse(part)
+                .map_err(|e| e.change_context(BalanceError::InvalidSchedule(input.to_owned())))?;
+            for weekday in target.weekdays {
+                schedule.0[weekday.num_days_from_monday() as usize] = target.time;
+            }
+        }
+        Ok(schedule)
+    }
+
+    /// Time to work on `day` by the schedule alone.
+    pub fn expected(&self, day: NaiveDate) -> Duration {
+        Duration::seconds(self.0[day.weekday().num_days_from_monday() as usize])
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut times: Vec<i64> = self.0
+            .iter()
+            .copied()
+            .filter(|time| *time > 0)
+            .collect();
+        times.sort_unstable_by(|a, b| b.cmp(a));
+        times.dedup();
+        let parts: Vec<String> = times
+            .into_iter()
+            .map(|time| {
+                let weekdays = WEEKDAYS.into_iter()
+                    .filter(|weekday| self.0[weekday.num_days_from_monday() as usize] == time)
+                    .collect();
+                DailyTarget { time, weekdays }.to_string()
+            })
+            .collect();
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+/// Length of the periods a balance is broken down into.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum BalancePeriod {
+    #[default]
+    Week,
+    Month,
+}
+
+impl BalancePeriod {
+    /// The first day of the period containing `day`.
+    pub fn start(self, day: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Week => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+            Self::Month => day.with_day(1).expect("every month has a first day"),
+        }
+    }
+
+    /// A label such as `2026-W37` or `2026-09` for the period starting on `first`.
+    pub fn label(self, first: NaiveDate) -> String {
+        match self {
+            Self::Week => first.format("%G-W%V").to_string(),
+            Self::Month => first.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// Expected and worked time of one period, with the balance carried over from earlier ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BalanceRow {
+    /// First day of the period
+    pub period: NaiveDate,
+    pub expected: Duration,
+    pub worked: Duration,
+    /// Balance of all periods up to and including this one
+    pub running: Duration,
+    /// Kinds of the days off on scheduled working days, one per day
+    pub days_off: Vec<OffKind>,
+}
+
+impl BalanceRow {
+    pub fn balance(&self) -> Duration {
+        self.worked - self.expected
+    }
+
+    /// The days off counted by kind, such as `2 vacation, 1 holiday`.
+    pub fn describe_days_off(&self) -> String {
+        let mut kinds = self.days_off.clone();
+        kinds.sort_unstable();
+        kinds.dedup();
+        let parts: Vec<String> = kinds
+            .into_iter()
+            .map(|kind| format!("{} {kind}", self.days_off.iter().filter(|off| **off == kind).count()))
+            .collect();
+        parts.join(", ")
+    }
+}
+
+/// Time worked per day, splitting entries at the start of each day.
+pub fn worked_per_day(entries: &[Entry], day_start: NaiveTime, now: u64) -> BTreeMap<NaiveDate, Duration> {
+    let mut days: BTreeMap<NaiveDate, Duration> = BTreeMap::new();
+    for piece in entries.iter().flat_map(|entry| day_boundary::split(entry, day_start, now)) {
+        *days.entry(day_boundary::day_of(piece.start, day_start)).or_insert_with(Duration::zero) +=
+            piece.elapsed(now);
+    }
+    days
+}
+
+/// The balance of every period from `first` to `last`, both inclusive, oldest first.
+/// Periods are cut at `first` and `last`, so partial weeks and months only count their days
+/// inside the range. No time is expected on days off.
+pub fn balance(
+    worked: &BTreeMap<NaiveDate, Duration>,
+    schedule: &Schedule,
+    days_off: &[DayOff],
+    first: NaiveDate,
+    last: NaiveDate,
+    by: BalancePeriod
+) -> Vec<BalanceRow> {
+    let mut rows: Vec<BalanceRow> = Vec::new();
+    let mut running = Duration::zero();
+    for day in first.iter_days().take_while(|day| *day <= last) {
+        let period = by.start(day);
+        if rows.last().is_none_or(|row| row.period != period) {
+            rows.push(BalanceRow {
+                period,
+                expected: Duration::zero(),
+                worked: Duration::zero(),
+                running,
+                days_off: Vec::new(),
+            });
+        }
+        let row = rows.last_mut().expect("a row was just pushed");
+        let mut expected = schedule.expected(day);
+        if let Some(off) = time_off::on(days_off, day).filter(|_| expected > Duration::zero()) {
+            row.days_off.push(off.kind);
+            expected = Duration::zero();
+        }
+        let worked = worked.get(&day).copied().unwrap_or_else(Duration::zero);
+        row.expected += expected;
+        row.worked += worked;
+        running += worked - expected;
+        row.running = running;
+    }
+    rows
+}
+
+/// A duration with an explicit sign such as `+1h 30m`, zero stays unsigned.
+pub fn format_signed(time: Duration, duration_format: DurationFormat) -> String {
+    let formatted = duration::format(time, duration_format);
+    if time > Duration::zero() { format!("+{formatted}") } else { formatted }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, month, day).unwrap()
+    }
+
+    #[test]
+    fn schedules_round_trip_through_text() {
+        let schedule = Schedule::parse("8h mon-fri; 6h fri").unwrap();
+        assert_eq!(schedule.0, [28_800, 28_800, 28_800, 28_800, 21_600, 0, 0]);
+        assert_eq!(schedule.to_string(), "8h 0m mon-thu; 6h 0m fri");
+        assert_eq!(Schedule::parse(&schedule.to_string()).unwrap(), schedule);
+        assert_eq!(schedule.expected(date(9, 18)), Duration::hours(6));
+        assert!(Schedule::parse("8h mon-fri; soon").is_err());
+    }
+
+    #[test]
+    fn balances_carry_over_between_periods() {
+        let schedule = Schedule::parse("8h mon-fri").unwrap();
+        let mut worked = BTreeMap::new();
+        worked.insert(date(9, 29), Duration::hours(10));
+        worked.insert(date(10, 1), Duration::hours(7));
+        worked.insert(date(10, 3), Duration::hours(2));
+        worked.insert(date(10, 5), Duration::hours(8));
+        // Tuesday 2026-09-29 to Monday 2026-10-05
+        let rows = balance(&worked, &schedule, &[], date(9, 29), date(10, 5), BalancePeriod::Week);
+        let summary: Vec<(String, i64, i64, i64, i64)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    BalancePeriod::Week.label(row.period),
+                    row.expected.num_hours(),
+                    row.worked.num_hours(),
+                    row.balance().num_hours(),
+                    row.running.num_hours(),
+                )
+            })
+            .collect();
+        assert_eq!(summary, vec![("2026-W40".to_owned(), 32, 19, -13, -13), ("2026-W41".to_owned(), 8, 8, 0, -13)]);
+
+        let months = balance(&worked, &schedule, &[], date(9, 29), date(10, 5), BalancePeriod::Month);
+        let running: Vec<(String, i64)> = months
+            .iter()
+            .map(|row| (BalancePeriod::Month.label(row.period), row.running.num_hours()))
+            .collect();
+        assert_eq!(running, vec![("2026-09".to_owned(), -6), ("2026-10".to_owned(), -13)]);
+        assert_eq!(format_signed(Duration::minutes(90), DurationFormat::Compact), "+1h 30m");
+        assert_eq!(format_signed(Duration::minutes(-90), DurationFormat::Compact), "-1h 30m");
+    }
+
+    #[test]
+    fn days_off_are_not_missing_time() {
+        let schedule = Schedule::parse("8h mon-fri").unwrap();
+        let mut worked = BTreeMap::new();
+        worked.insert(date(9, 29), Duration::hours(8));
+        // Tuesday and Wednesday off, the holiday falls on a Saturday without scheduled work
+        let mut days_off = time_off::range(date(10, 3), date(10, 3), OffKind::Holiday, None);
+        time_off::merge(&mut days_off, time_off::range(date(9, 29), date(9, 30), OffKind::Vacation, None));
+        let rows = balance(&worked, &schedule, &days_off, date(9, 28), date(10, 4), BalancePeriod::Week);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].expected, Duration::hours(24));
+        assert_eq!(rows[0].balance(), Duration::hours(-16));
+        assert_eq!(rows[0].describe_days_off(), "2 vacation");
+    }
+}
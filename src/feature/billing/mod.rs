@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+use chrono::{ Duration, NaiveDate, NaiveTime };
+use serde::{ Deserialize, Serialize };
+
+use super::{
+    client::{ self, Client },
+    day_boundary,
+    project,
+    rounding::{ Rounding, RoundingScope },
+    tracker::entry::{ Entry, EntryDetails },
+};
+
+/// Hourly rates set on projects and tags. Client rates are stored on the client.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rates {
+    /// Rates per project path, inherited by subprojects
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub projects: BTreeMap<String, f64>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, f64>,
+}
+
+impl Rates {
+    pub fn is_empty(&self) -> bool {
+        self.projects.is_empty() && self.tags.is_empty()
+    }
+}
+
+/// What a rate is set on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RateTarget {
+    Client(String),
+    Project(String),
+    Tag(String),
+}
+
+/// The hourly rate that applies to an entry and the currency it is billed in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rate {
+    pub hourly: f64,
+    pub currency: Option<String>,
+}
+
+/// The rate for time with `details`, or `None` when it is not billable or no rate applies.
+/// The most specific rate wins: a tag rate over a project rate over the client's default
+/// rate. Among tags, the first tag with a rate wins, and among projects the closest ancestor.
+pub fn rate_for(details: &EntryDetails, clients: &[Client], rates: &Rates) -> Option<Rate> {
+    if details.billable == Some(false) {
+        return None;
+    }
+    let project = details.project.as_deref();
+    let client = project.and_then(|project| client::client_of(clients, project));
+
+    let tag_rate = details.tags.iter().find_map(|tag| rates.tags.get(tag).copied());
+    let project_rate = project.and_then(|project| {
+        rates.projects
+            .iter()
+            .filter(|(path, _)| project::is_within(project, path))
+            .max_by_key(|(path, _)| project::depth(path))
+            .map(|(_, rate)| *rate)
+    });
+    let client_rate = client.and_then(|client| client.default_rate);
+
+    let hourly = tag_rate.or(project_rate).or(client_rate)?;
+    Some(Rate { hourly, currency: client.and_then(|client| client.currency.clone()) })
+}
+
+/// The amount in cents for `time` at `hourly`, rounded to the nearest cent.
+pub fn amount(time: Duration, hourly: f64) -> i64 {
+    ((time.num_seconds() as f64) * hourly / 36.0).round() as i64
+}
+
+/// Formats an amount in cents with thousands separators, e.g. `€1,234.50`. Currencies
+/// without a common symbol are prefixed with their code, e.g. `CHF 1,234.50`.
+pub fn format_money(cents: i64, currency: Option<&str>) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    let units = (cents / 100).to_string();
+    let mut grouped = String::new();
+    for (index, digit) in units.chars().enumerate() {
+        if index > 0 && (units.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    let number = format!("{grouped}.{:02}", cents % 100);
+    match currency {
+        Some("USD") => format!("{sign}${number}"),
+        Some("EUR") => format!("{sign}€{number}"),
+        Some("GBP") => format!("{sign}£{number}"),
+        Some(code) => format!("{sign}{code} {number}"),
+        None => format!("{sign}{number}"),
+    }
+}
+
+/// Everything needed to bill tracked time.
+#[derive(Clone, Copy, Debug)]
+pub struct Billing<'a> {
+    pub clients: &'a [Client],
+    pub rates: &'a Rates,
+    /// Rounding for clients without their own rule
+    pub rounding: Rounding,
+    /// When days start, for rounding per day
+    pub day_start: NaiveTime,
+}
+
+/// Raw and rounded time of a group with the amounts billed for it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Billed {
+    pub time: Duration,
+    /// Billable time after rounding plus the unbillable time as is
+    pub rounded: Duration,
+    pub amounts: Amounts,
+}
+
+impl Billing<'_> {
+    pub fn rounding_for(&self, client: Option<&Client>) -> Rounding {
+        client.and_then(|client| client.rounding).unwrap_or(self.rounding)
+    }
+
+    /// Whether any time is rounded, by the setting or by a client's own rule.
+    pub fn is_rounded(&self) -> bool {
+        !self.rounding.is_off() ||
+            self.clients.iter().any(|client| client.rounding.is_some_and(|rounding| !rounding.is_off()))
+    }
+
+    /// Bills the `time` of each entry under its group `key`. Billable time is rounded with the
+    /// rule of the entry's client, per entry or per day and rate; unbillable time is kept as is.
+    /// Groups are returned in the order they first appear.
+    pub fn bill<'e, K, I>(&self, items: I) -> Vec<(K, Billed)>
+        where K: PartialEq, I: IntoIterator<Item = (K, &'e Entry, Duration)>
+    {
+        type DayBucket = (usize, Option<String>, NaiveDate, u64, Option<String>);
+        let mut groups: Vec<(K, Billed)> = Vec::new();
+        let mut days: BTreeMap<DayBucket, (Rounding, f64, Duration)> = BTreeMap::new();
+        for (key, entry, time) in items {
+            if time <= Duration::zero() {
+                continue;
+            }
+            let index = match groups.iter().position(|(existing, _)| *existing == key) {
+                Some(index) => index,
+                None => {
+                    groups.push((key, Billed::default()));
+                    groups.len() - 1
+                }
+            };
+            let billed = &mut groups[index].1;
+            billed.time += time;
+            let Some(rate) = rate_for(&entry.details, self.clients, self.rates) else {
+                billed.rounded += time;
+                continue;
+            };
+            let client = entry.details.project
+                .as_deref()
+                .and_then(|project| client::client_of(self.clients, project));
+            let rounding = self.rounding_for(client);
+            match rounding.scope {
+                RoundingScope::PerEntry => {
+                    let rounded = rounding.round(time);
+                    billed.rounded += rounded;
+                    billed.amounts.add(rate.currency.as_deref(), amount(rounded, rate.hourly));
+                }
+                RoundingScope::PerDay => {
+                    let day = day_boundary::day_of(entry.start, self.day_start);
+                    let client = client.map(|client| client.name.clone());
+                    let bucket = (index, client, day, rate.hourly.to_bits(), rate.currency);
+                    days.entry(bucket).or_insert((rounding, rate.hourly, Duration::zero())).2 += time;
+                }
+            }
+        }
+        for ((index, _, _, _, currency), (rounding, hourly, time)) in days {
+            let rounded = rounding.round(time);
+            let billed = &mut groups[index].1;
+            billed.rounded += rounded;
+            billed.amounts.add(currency.as_deref(), amount(rounded, hourly));
+        }
+        groups
+    }
+}
+
+/// Amounts in cents per currency, in the order the currencies first appear.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Amounts(pub Vec<(Option<String>, i64)>);
+
+impl Amounts {
+    pub fn add(&mut self, currency: Option<&str>, cents: i64) {
+        match self.0.iter_mut().find(|(existing, _)| existing.as_deref() == currency) {
+            Some((_, total)) => {
+                *total += cents;
+            }
+            None => self.0.push((currency.map(str::to_owned), cents)),
+        }
+    }
+
+    pub fn merge(&mut self, other: &Amounts) {
+        for (currency, cents) in &other.0 {
+            self.add(currency.as_deref(), *cents);
+        }
+    }
+
+    /// The amounts joined by ` + `, or `-` when there is nothing to bill.
+    pub fn format(&self) -> String {
+        if self.0.is_empty() {
+            return "-".to_owned();
+        }
+        self.0
+            .iter()
+            .map(|(currency, cents)| format_money(*cents, currency.as_deref()))
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(project: &str, tags: &[&str]) -> EntryDetails {
+        EntryDetails {
+            project: Some(project.to_owned()),
+            tags: tags
+                .iter()
+                .map(|tag| tag.to_string())
+                .collect(),
+            ..EntryDetails::default()
+        }
+    }
+
+    #[test]
+    fn the_most_specific_rate_wins() {
+        let clients = [
+            Client {
+                name: "Acme".to_owned(),
+                currency: Some("EUR".to_owned()),
+                default_rate: Some(80.0),
+                projects: vec!["acme".to_owned()],
+                ..Client::default()
+            },
+        ];
+        let mut rates = Rates::default();
+        rates.projects.insert("acme/website".to_owned(), 100.0);
+        rates.projects.insert("acme/website/frontend".to_owned(), 110.0);
+        rates.tags.insert("urgent".to_owned(), 150.0);
+        let hourly = |details: &EntryDetails| rate_for(details, &clients, &rates).map(|rate| rate.hourly);
+
+        assert_eq!(hourly(&details("acme/api", &[])), Some(80.0));
+        assert_eq!(hourly(&details("acme/website/backend", &[])), Some(100.0));
+        assert_eq!(hourly(&details("acme/website/frontend/css", &[])), Some(110.0));
+        assert_eq!(hourly(&details("acme/website", &["meeting", "urgent"])), Some(150.0));
+        assert_eq!(hourly(&details("globex", &[])), None);
+        let unbillable = EntryDetails { billable: Some(false), ..details("acme/api", &["urgent"]) };
+        assert_eq!(hourly(&unbillable), None);
+        assert_eq!(
+            rate_for(&details("acme/api", &[]), &clients, &rates).and_then(|rate| rate.currency),
+            Some("EUR".to_owned())
+        );
+    }
+
+    #[test]
+    fn rounding_applies_per_entry_or_per_day() {
+        let clients = [
+            Client {
+                name: "Acme".to_owned(),
+                default_rate: Some(100.0),
+                projects: vec!["acme".to_owned()],
+                ..Client::default()
+            },
+        ];
+        let rates = Rates::default();
+        let noon = 1_789_387_200;
+        let entries: Vec<Entry> = [(0, 10), (10, 20), (20, 21)]
+            .iter()
+            .map(|(from, to)| Entry {
+                start: noon + from * 60,
+                stop: Some(noon + to * 60),
+                details: details("acme/api", &[]),
+                ..Entry::default()
+            })
+            .chain([Entry { start: noon, stop: Some(noon + 600), ..Entry::default() }])
+            .collect();
+        let mut billing = Billing {
+            clients: &clients,
+            rates: &rates,
+            rounding: Rounding::parse("15m up").unwrap(),
+            day_start: NaiveTime::MIN,
+        };
+        let bill = |billing: &Billing| {
+            let items = entries.iter().map(|entry| ((), entry, entry.elapsed(0)));
+            billing.bill(items).remove(0).1
+        };
+
+        let per_entry = bill(&billing);
+        assert_eq!(per_entry.time, Duration::minutes(31));
+        assert_eq!(per_entry.rounded, Duration::minutes(55));
+        assert_eq!(per_entry.amounts.format(), "75.00");
+
+        billing.rounding = Rounding::parse("15m up per-day").unwrap();
+        let per_day = bill(&billing);
+        assert_eq!(per_day.rounded, Duration::minutes(40));
+        assert_eq!(per_day.amounts.format(), "50.00");
+    }
+
+    #[test]
+    fn money_is_formatted_per_currency() {
+        assert_eq!(amount(Duration::minutes(90), 100.0), 15_000);
+        assert_eq!(amount(Duration::seconds(1), 100.0), 3);
+        assert_eq!(format_money(123_456_789, Some("USD")), "$1,234,567.89");
+        assert_eq!(format_money(5, Some("EUR")), "€0.05");
+        assert_eq!(format_money(-100_000, Some("CHF")), "-CHF 1,000.00");
+        assert_eq!(format_money(99_900, None), "999.00");
+        let mut amounts = Amounts::default();
+        amounts.add(Some("EUR"), 100);
+        amounts.add(None, 50);
+        amounts.add(Some("EUR"), 200);
+        assert_eq!(amounts.format(), "€3.00 + 0.50");
+    }
+}
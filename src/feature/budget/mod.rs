@@ -0,0 +1,301 @@
+use chrono::{ Datelike, Duration, NaiveDate };
+use error_stack::Report;
+use serde::{ Deserialize, Serialize };
+use thiserror::Error;
+
+use super::{
+    billing::{ self, Billing },
+    client::{ self, Client },
+    day_boundary,
+    duration::{ self, DurationFormat },
+    project,
+    tracker::entry::Entry,
+};
+
+/// Percentages of a budget at which warnings are shown unless configured otherwise.
+pub const DEFAULT_THRESHOLDS: [u32; 2] = [80, 100];
+
+#[derive(Error, Debug)]
+pub enum BudgetError {
+    #[error("invalid thresholds: {0:?}, expected percentages such as 80,100")]
+    InvalidThresholds(String),
+
+    #[error("no budget set for {0}")] UnknownBudget(String),
+
+    #[error("the projects of {0} are billed in different currencies")] MixedCurrencies(String),
+}
+
+/// How much may be spent on a project.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Limit {
+    /// Tracked time in seconds
+    Time(i64),
+    /// Billed amount in cents, in the currency of the project's client. All clients of the
+    /// project and its subprojects must bill in that currency.
+    Money(i64),
+}
+
+/// A budget of a project and its subprojects.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Budget {
+    pub project: String,
+
+    pub limit: Limit,
+
+    /// Percentages of the limit at which warnings are shown, ascending
+    #[serde(default = "default_thresholds")]
+    pub thresholds: Vec<u32>,
+}
+
+fn default_thresholds() -> Vec<u32> {
+    DEFAULT_THRESHOLDS.to_vec()
+}
+
+/// Time or money spent of a budget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Usage {
+    /// Seconds or cents, like the limit
+    pub used: i64,
+    pub limit: i64,
+}
+
+impl Usage {
+    pub fn percent(&self) -> f64 {
+        if self.limit <= 0 {
+            return 100.0;
+        }
+        ((self.used as f64) * 100.0) / (self.limit as f64)
+    }
+
+    pub fn remaining(&self) -> i64 {
+        self.limit - self.used
+    }
+}
+
+/// Spending on a budget in one week.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BurndownRow {
+    /// Monday of the week
+    pub week: NaiveDate,
+    pub used: i64,
+    /// What is left of the budget at the end of the week
+    pub remaining: i64,
+}
+
+impl Budget {
+    /// Whether time spent on `project` counts towards the budget.
+    pub fn covers(&self, project: &str) -> bool {
+        project::is_within(project, &self.project)
+    }
+
+    /// The spending per week, oldest first. Time counts all tracked time, money the billed
+    /// amounts after rounding.
+    pub fn burndown(&self, entries: &[Entry], billing: &Billing, now: u64) -> Vec<BurndownRow> {
+        let items = entries
+            .iter()
+            .filter(|entry| entry.details.project.as_deref().is_some_and(|project| self.covers(project)))
+            .map(|entry| {
+                let day = day_boundary::day_of(entry.start, billing.day_start);
+                let week = day - Duration::days(day.weekday().num_days_from_monday() as i64);
+                (week, entry, entry.elapsed(now))
+            });
+        let mut weeks = billing.bill(items);
+        weeks.sort_by_key(|(week, _)| *week);
+
+        let mut remaining = self.limit_value();
+        weeks
+            .into_iter()
+            .map(|(week, billed)| {
+                let used = match self.limit {
+                    Limit::Time(_) => billed.time.num_seconds(),
+                    Limit::Money(_) =>
+                        billed.amounts.0
+                            .iter()
+                            .filter(|(currency, _)| currency.as_deref() == self.client_currency(billing))
+                            .map(|(_, cents)| cents)
+                            .sum(),
+                };
+                remaining -= used;
+                BurndownRow { week, used, remaining }
+            })
+            .collect()
+    }
+
+    pub fn usage(&self, entries: &[Entry], billing: &Billing, now: u64) -> Usage {
+        let used = self
+            .burndown(entries, billing, now)
+            .iter()
+            .map(|row| row.used)
+            .sum();
+        Usage { used, limit: self.limit_value() }
+    }
+
+    /// The highest threshold `usage` has reached.
+    pub fn reached(&self, usage: Usage) -> Option<u32> {
+        let percent = usage.percent();
+        self.thresholds
+            .iter()
+            .copied()
+            .filter(|threshold| percent >= (*threshold as f64))
+            .max()
+    }
+
+    /// Formats seconds or cents of the budget, money in the currency of the project's client.
+    pub fn format(&self, value: i64, billing: &Billing, duration_format: DurationFormat) -> String {
+        match self.limit {
+            Limit::Time(_) => duration::format(Duration::seconds(value), duration_format),
+            Limit::Money(_) => billing::format_money(value, self.client_currency(billing)),
+        }
+    }
+
+    /// Fails for a money budget whose project and subprojects belong to clients billing in
+    /// different currencies, which cannot be added up.
+    pub fn check_currencies(&self, clients: &[Client]) -> Result<(), Report<BudgetError>> {
+        if let Limit::Time(_) = self.limit {
+            return Ok(());
+        }
+        let mut currencies = clients
+            .iter()
+            .filter(|client| {
+                client.projects.iter().any(|linked| project::is_within(linked, &self.project))
+            })
+            .chain(client::client_of(clients, &self.project))
+            .map(|client| client.currency.as_deref());
+        let first = currencies.next();
+        if currencies.any(|currency| Some(currency) != first) {
+            return Err(Report::new(BudgetError::MixedCurrencies(self.project.clone())));
+        }
+        Ok(())
+    }
+
+    fn client_currency<'a>(&self, billing: &Billing<'a>) -> Option<&'a str> {
+        client
+            ::client_of(billing.clients, &self.project)
+            .and_then(|client| client.currency.as_deref())
+    }
+
+    /// `usage` as e.g. `34:00:00 of 40:00:00 (85%)`.
+    pub fn summary(&self, usage: Usage, billing: &Billing, duration_format: DurationFormat) -> String {
+        format!(
+            "{} of {} ({:.0}%)",
+            self.format(usage.used, billing, duration_format),
+            self.format(usage.limit, billing, duration_format),
+            usage.percent().floor()
+        )
+    }
+
+    /// A warning when `usage` has reached one of the thresholds.
+    pub fn warning(&self, usage: Usage, billing: &Billing, duration_format: DurationFormat) -> Option<String> {
+        self.reached(usage)?;
+        Some(
+            format!(
+                "{} has used {:.0}% of its budget ({} of {})",
+                self.project,
+                usage.percent().floor(),
+                self.format(usage.used, billing, duration_format),
+                self.format(usage.limit, billing, duration_format)
+            )
+        )
+    }
+
+    fn limit_value(&self) -> i64 {
+        match self.limit {
+            Limit::Time(seconds) => seconds,
+            Limit::Money(cents) => cents,
+        }
+    }
+}
+
+/// Parses comma separated percentages such as `50,80,100`.
+pub fn parse_thresholds(input: &str) -> Result<Vec<u32>, Report<BudgetError>> {
+    let mut thresholds = input
+        .split(',')
+        .map(|threshold| threshold.trim().trim_end_matches('%').parse::<u32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| Report::new(BudgetError::InvalidThresholds(input.to_owned())))?;
+    if thresholds.is_empty() || thresholds.contains(&0) {
+        return Err(Report::new(BudgetError::InvalidThresholds(input.to_owned())));
+    }
+    thresholds.sort_unstable();
+    thresholds.dedup();
+    Ok(thresholds)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+    use crate::feature::{
+        billing::Rates,
+        client::Client,
+        rounding::Rounding,
+        tracker::entry::EntryDetails,
+    };
+    use super::*;
+
+    fn entry(start: u64, minutes: u64, project: &str) -> Entry {
+        Entry {
+            start,
+            stop: Some(start + minutes * 60),
+            details: EntryDetails { project: Some(project.to_owned()), ..EntryDetails::default() },
+            ..Entry::default()
+        }
+    }
+
+    #[test]
+    fn budgets_burn_down_per_week() {
+        let clients = [
+            Client {
+                name: "Acme".to_owned(),
+                currency: Some("EUR".to_owned()),
+                default_rate: Some(100.0),
+                projects: vec!["acme".to_owned()],
+                ..Client::default()
+            },
+        ];
+        let rates = Rates::default();
+        let billing = Billing {
+            clients: &clients,
+            rates: &rates,
+            rounding: Rounding::parse("1h up").unwrap(),
+            day_start: NaiveTime::MIN,
+        };
+        // Monday 2026-09-14 at noon UTC, and the same time a week later
+        let monday = 1_789_387_200;
+        let next_monday = monday + 7 * 86_400;
+        let entries = [
+            entry(next_monday, 30, "acme/website/frontend"),
+            entry(monday, 90, "acme/website"),
+            entry(monday, 60, "acme/api"),
+        ];
+        let time = Budget {
+            project: "acme/website".to_owned(),
+            limit: Limit::Time(4 * 3600),
+            thresholds: default_thresholds(),
+        };
+        let weeks: Vec<(String, i64, i64)> = time
+            .burndown(&entries, &billing, next_monday)
+            .into_iter()
+            .map(|row| (row.week.to_string(), row.used / 60, row.remaining / 60))
+            .collect();
+        assert_eq!(weeks, vec![("2026-09-14".to_owned(), 90, 150), ("2026-09-21".to_owned(), 30, 120)]);
+
+        let money = Budget { limit: Limit::Money(30_000), ..time.clone() };
+        let usage = money.usage(&entries, &billing, next_monday);
+        assert_eq!(usage, Usage { used: 30_000, limit: 30_000 });
+        assert_eq!(money.reached(usage), Some(100));
+        assert_eq!(
+            money.warning(usage, &billing, DurationFormat::Clock).as_deref(),
+            Some("acme/website has used 100% of its budget (€300.00 of €300.00)")
+        );
+        let usage = time.usage(&entries, &billing, next_monday);
+        assert_eq!(time.reached(usage), None);
+    }
+
+    #[test]
+    fn thresholds_are_sorted_percentages() {
+        assert_eq!(parse_thresholds("100, 50%,80").unwrap(), vec![50, 80, 100]);
+        assert!(parse_thresholds("0").is_err());
+        assert!(parse_thresholds("eighty").is_err());
+    }
+}
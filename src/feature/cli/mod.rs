@@ -1,7 +1,30 @@
-use clap::{ command, Parser, Subcommand };
+use chrono::{ DateTime, Local };
+use std::{ path::Path, process::ExitCode };
+use clap::{ Args, Parser, Subcommand };
 use error_stack::Report;
 
-use super::tracker::flatfile::FlatFileTracker;
+use super::{
+    aggregate::GroupBy,
+    balance::BalancePeriod,
+    billing::RateTarget,
+    budget::{ self, Limit },
+    client::{ self, ClientChanges },
+    context,
+    duration::{ self, DurationFormat },
+    export::DataFormat,
+    git,
+    heartbeat::Heartbeat,
+    invoice::{ self, InvoiceFormat, InvoiceOptions, Period },
+    project::{ self, Expansion },
+    prompt,
+    rounding::Rounding,
+    report::{ Breakdown, ReportOptions },
+    render::ReportFormat,
+    session_guard::CapPolicy,
+    time_expr,
+    time_off::OffKind,
+    tracker::{ entry::EntryDetails, flatfile::FlatFileTracker },
+};
 
 #[derive(thiserror::Error, Debug)]
 #[error("a cli error occured")]
@@ -31,55 +54,1052 @@ struct Cli {
     #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     quiet: u8,
 
+    /// How to cap a timer that ran longer than max-session, overriding the auto-cap setting
+    #[arg(long, value_enum, global = true)]
+    auto_cap: Option<CapPolicy>,
+
     /// Subcommands for specific actions
     #[command(subcommand)]
     pub command: Commands,
 }
 
+/// Exit code of `track status` when no timer is running
+const EXIT_NOT_RUNNING: u8 = 3;
+
+#[derive(Debug, Args)]
+struct DetailsArgs {
+    /// Project the time is spent on
+    #[arg(short, long)]
+    project: Option<String>,
+
+    /// Tag to attach, may be repeated
+    #[arg(short, long = "tag")]
+    tags: Vec<String>,
+
+    /// Free-form note
+    #[arg(short, long)]
+    note: Option<String>,
+
+    /// Mark the time as billable
+    #[arg(long, conflicts_with = "non_billable")]
+    billable: bool,
+
+    /// Mark the time as not billable, e.g. for internal work
+    #[arg(long)]
+    non_billable: bool,
+}
+
+impl From<DetailsArgs> for EntryDetails {
+    fn from(DetailsArgs { project, tags, note, billable, non_billable }: DetailsArgs) -> Self {
+        let billable = match (billable, non_billable) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        };
+        Self { project, tags, note, billable }
+    }
+}
+
 #[derive(Debug, Subcommand)]
 enum Commands {
-    /// Start tracking time
-    Start,
+    /// Start tracking time, with defaults from the nearest .track file in the current
+    /// directory or its parents and the git repository, branch and commit it is started in
+    Start {
+        /// When the timer started, e.g. "15 minutes ago", "9:30" or "yesterday 9am"
+        #[arg(long)]
+        at: Option<String>,
+
+        #[command(flatten)]
+        details: DetailsArgs,
+    },
 
     /// Stop tracking time
-    Stop,
+    Stop {
+        /// When the timer stopped, e.g. "17:30" or "5 minutes ago"
+        #[arg(long)]
+        at: Option<String>,
+    },
+
+    /// Start a new timer with the project, tags and note of a previous entry
+    Continue {
+        /// Id of the entry to continue, defaults to the most recent one
+        id: Option<u64>,
+
+        /// When the timer started, e.g. "15 minutes ago" or "9:30"
+        #[arg(long)]
+        at: Option<String>,
+    },
+
+    /// Add a finished entry
+    Add {
+        /// When the entry started, e.g. "yesterday 9am"
+        #[arg(long)]
+        from: String,
+
+        /// When the entry stopped, e.g. "yesterday noon"
+        #[arg(long, required_unless_present = "duration", conflicts_with = "duration")]
+        to: Option<String>,
+
+        /// How long the entry lasted, e.g. "1h30m", "90m", "1.5h" or "01:30:00"
+        #[arg(long)]
+        duration: Option<String>,
+
+        #[command(flatten)]
+        details: DetailsArgs,
+
+        /// How to display durations
+        #[arg(long, value_enum, default_value_t)]
+        duration_format: DurationFormat,
+    },
+
+    /// Record activity from an editor plugin or shell hook; sessions are derived from
+    /// heartbeats using the idle-timeout setting
+    Heartbeat {
+        /// Project the activity belongs to
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// File being worked on
+        #[arg(short, long)]
+        file: Option<String>,
+
+        /// Language of the file, detected from its extension when omitted
+        #[arg(long)]
+        language: Option<String>,
+
+        /// When the activity happened, defaults to now
+        #[arg(long)]
+        at: Option<String>,
+    },
+
+    /// Split a finished entry into two at the given time
+    Split {
+        /// Id of the entry to split
+        id: u64,
+
+        /// Where to split the entry, e.g. "12:30" or "yesterday 3pm"
+        #[arg(long)]
+        at: String,
+    },
+
+    /// Merge consecutive finished entries into one, combining their tags and notes
+    Merge {
+        /// Ids of the entries to merge
+        #[arg(required = true, num_args = 2..)]
+        ids: Vec<u64>,
+
+        /// Largest allowed gap between two merged entries, e.g. "5m"
+        #[arg(long, default_value = "5m")]
+        max_gap: String,
+    },
 
     /// Report tracked time for the last 24 hours
-    Report,
+    Report {
+        /// How to display durations
+        #[arg(long, value_enum, default_value_t)]
+        duration_format: DurationFormat,
+
+        /// Break time down by language, directory or file from heartbeats, by git branch or
+        /// commit, or by project or client
+        #[arg(long, value_enum)]
+        by: Option<Breakdown>,
+
+        /// Only list the N largest rows of the breakdown
+        #[arg(long, requires = "by", conflicts_with_all = ["depth", "collapse"])]
+        top: Option<usize>,
+
+        /// Only count entries and commits on this git branch
+        #[arg(long)]
+        branch: Option<String>,
+
+        /// Number of project levels to show with --by project
+        #[arg(
+            long,
+            requires = "by",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        depth: Option<usize>,
+
+        /// Fold the subprojects of this project into it with --by project, may be repeated
+        #[arg(long, requires = "by")]
+        collapse: Vec<String>,
+
+        /// Add billable amounts, grouped by client unless --by project or --by branch is given
+        #[arg(long, conflicts_with_all = ["top", "depth", "collapse"])]
+        billing: bool,
+
+        /// Group entry time with subtotals, nested when several are given, e.g. week,project
+        #[arg(long, value_enum, value_delimiter = ',', conflicts_with_all = ["by", "billing"])]
+        group_by: Vec<GroupBy>,
+
+        /// Start of the report, e.g. "monday" or "2026-09-01", 24 hours before --to by default
+        #[arg(long)]
+        from: Option<String>,
+
+        /// End of the report, now by default
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Print records as JSON, CSV or NDJSON, or a Markdown table or HTML page, instead of text
+        ///
+        /// The records are the entries (id, start, stop, seconds, project, tags, note, billable),
+        /// the rows of --by (key, seconds), the groups of --group-by (path, seconds, with one
+        /// CSV column per level) or the rows of --billing (key, seconds, rounded_seconds,
+        /// amounts). Durations are whole seconds, money is in cents and times are RFC 3339.
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+
+        /// Draw a bar for each row with --format html
+        #[arg(long, requires = "format")]
+        charts: bool,
+    },
+
+    /// Show the running timer and today's total, exiting with 3 when no timer is running
+    Status {
+        /// Print a single line, e.g. for scripts
+        #[arg(short, long)]
+        short: bool,
+
+        /// How to display durations
+        #[arg(long, value_enum, default_value_t)]
+        duration_format: DurationFormat,
+    },
+
+    /// Show or change settings such as day-boundary (none, split, virtual) and day-start (HH:MM)
+    Config {
+        /// Setting to show or change, all settings are listed when omitted
+        key: Option<String>,
+
+        /// New value for the setting
+        value: Option<String>,
+    },
+
+    /// Manage clients and the projects they own
+    Client {
+        #[command(subcommand)]
+        command: ClientCommands,
+    },
+
+    /// Set hourly rates on clients, projects and tags; the most specific rate wins
+    Rate {
+        #[command(subcommand)]
+        command: RateCommands,
+    },
+
+    /// Bill the unbilled time of a client, e.g. "track invoice --client Acme --period 2026-09"
+    Invoice {
+        /// Client to bill
+        #[arg(long)]
+        client: String,
+
+        /// Month (2026-09), day (2026-09-14) or range of days (2026-09-01..2026-09-15) to bill,
+        /// the previous month by default
+        #[arg(long)]
+        period: Option<String>,
+
+        /// Document format
+        #[arg(long, value_enum, default_value_t)]
+        format: InvoiceFormat,
+
+        /// Template file with {{placeholders}} replacing the built-in template
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Write the invoice to this file instead of printing it
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Invoice number, the next number of the year by default, e.g. 2026-004
+        #[arg(long)]
+        number: Option<String>,
+
+        /// Tax rate in percent, overriding the client's tax rate
+        #[arg(long)]
+        tax_rate: Option<String>,
+
+        /// Print the invoice without marking any entries as invoiced
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Show the used and remaining time or money budget of every project
+    Budget {
+        #[command(subcommand)]
+        command: Option<BudgetCommands>,
+
+        /// How to display durations
+        #[arg(long, value_enum, default_value_t, global = true)]
+        duration_format: DurationFormat,
+
+        /// Print the budgets as JSON, CSV or NDJSON instead of text
+        ///
+        /// Every budget has project, kind (time or money), limit, used, remaining, percent and
+        /// currency. With show, every week has project, week, used and remaining. Time is in
+        /// seconds and money in cents.
+        #[arg(long, value_enum, global = true)]
+        format: Option<DataFormat>,
+    },
+
+    /// Show the overtime balance against the work schedule, per week or month
+    Balance {
+        /// Length of the periods to break the balance down into
+        #[arg(long, value_enum, default_value_t)]
+        by: BalancePeriod,
+
+        /// How to display durations
+        #[arg(long, value_enum, default_value_t)]
+        duration_format: DurationFormat,
+
+        /// Print records as JSON, CSV or NDJSON instead of text
+        ///
+        /// Every week or month has period, first_day, expected_seconds, worked_seconds,
+        /// balance_seconds, running_seconds and days_off.
+        #[arg(long, value_enum)]
+        format: Option<DataFormat>,
+    },
+
+    /// Manage vacation, sick and holiday days, which lower the expected time
+    Off {
+        #[command(subcommand)]
+        command: Option<OffCommands>,
+    },
+
+    /// Manage the project hierarchy, e.g. client/project/task
+    Project {
+        #[command(subcommand)]
+        command: ProjectCommands,
+    },
+
+    /// Integrate with git hooks
+    Git {
+        #[command(subcommand)]
+        command: GitCommands,
+    },
+
+    /// Print the running timer for shell prompts and status bars, or nothing when idle
+    Prompt {
+        /// Template with {project}, {tags}, {note}, {start} and {elapsed} placeholders,
+        /// e.g. "{project} {elapsed:short}"
+        #[arg(short, long, default_value = "{project} {elapsed:short}")]
+        format: String,
+    },
 }
 
-pub fn init() -> Result<(), Report<CliError>> {
+#[derive(Debug, Args)]
+struct ClientArgs {
+    /// Postal address, as printed on invoices
+    #[arg(long)]
+    address: Option<String>,
+
+    /// Currency code such as EUR or USD
+    #[arg(long)]
+    currency: Option<String>,
+
+    /// Default hourly rate, e.g. 95 or 120.50
+    #[arg(long)]
+    rate: Option<String>,
+
+    /// Tax or VAT identification number
+    #[arg(long)]
+    tax_id: Option<String>,
+
+    /// Tax charged on invoices in percent, e.g. 19
+    #[arg(long)]
+    tax_rate: Option<String>,
+
+    /// How the client's time is rounded, e.g. "15m up per-day", or "default" to use the
+    /// rounding setting
+    #[arg(long)]
+    rounding: Option<String>,
+
+    /// Project owned by the client, including its subprojects, may be repeated
+    #[arg(short, long = "project")]
+    projects: Vec<String>,
+}
+
+impl ClientArgs {
+    fn into_changes(self) -> Result<ClientChanges, Report<CliError>> {
+        let ClientArgs { address, currency, rate, tax_id, tax_rate, rounding, projects } = self;
+        let default_rate = rate
+            .map(|rate| client::parse_rate(&rate))
+            .transpose()
+            .map_err(|e| e.change_context(CliError).attach_printable("invalid --rate"))?;
+        let tax_rate = tax_rate
+            .map(|rate| invoice::parse_tax_rate(&rate))
+            .transpose()
+            .map_err(|e| e.change_context(CliError).attach_printable("invalid --tax-rate"))?;
+        let rounding = rounding
+            .map(|rule| match rule.as_str() {
+                "default" => Ok(None),
+                rule => Rounding::parse(rule).map(Some),
+            })
+            .transpose()
+            .map_err(|e| e.change_context(CliError).attach_printable("invalid --rounding"))?;
+        Ok(ClientChanges {
+            address,
+            currency,
+            default_rate,
+            tax_id,
+            tax_rate,
+            rounding,
+            link: projects,
+            ..ClientChanges::default()
+        })
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum ClientCommands {
+    /// Add a client
+    Add {
+        name: String,
+
+        #[command(flatten)]
+        details: ClientArgs,
+    },
+
+    /// List clients with their details
+    List {
+        /// Print records as JSON, CSV or NDJSON instead of text
+        ///
+        /// Every client has name, address, currency, rate, tax_id, tax_rate, rounding and
+        /// projects.
+        #[arg(long, value_enum)]
+        format: Option<DataFormat>,
+    },
+
+    /// Change the details of a client
+    Edit {
+        name: String,
+
+        /// New name of the client
+        #[arg(long = "name")]
+        rename: Option<String>,
+
+        #[command(flatten)]
+        details: ClientArgs,
+
+        /// Project no longer owned by the client, may be repeated
+        #[arg(long)]
+        unlink: Vec<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+struct RateTargetArgs {
+    /// Default rate of a client
+    #[arg(long)]
+    client: Option<String>,
+
+    /// Rate of a project and its subprojects
+    #[arg(short, long)]
+    project: Option<String>,
+
+    /// Rate of entries with a tag
+    #[arg(short, long)]
+    tag: Option<String>,
+}
+
+impl From<RateTargetArgs> for RateTarget {
+    fn from(RateTargetArgs { client, project, tag }: RateTargetArgs) -> Self {
+        match (client, project, tag) {
+            (Some(client), _, _) => Self::Client(client),
+            (_, Some(project), _) => Self::Project(project),
+            (_, _, Some(tag)) => Self::Tag(tag),
+            (None, None, None) => unreachable!("clap requires one rate target"),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum RateCommands {
+    /// Set an hourly rate, e.g. "track rate set 95 --project acme"
+    Set {
+        rate: String,
+
+        #[command(flatten)]
+        target: RateTargetArgs,
+    },
+
+    /// Remove a rate
+    Unset {
+        #[command(flatten)]
+        target: RateTargetArgs,
+    },
+
+    /// List all rates
+    List {
+        /// Print records as JSON, CSV or NDJSON instead of text
+        ///
+        /// Every rate has target (client, project or tag), name and rate.
+        #[arg(long, value_enum)]
+        format: Option<DataFormat>,
+    },
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+struct BudgetLimitArgs {
+    /// Time budget such as 40h or 1h30m
+    #[arg(long)]
+    time: Option<String>,
+
+    /// Money budget in the currency of the project's client, e.g. 5000
+    #[arg(long)]
+    money: Option<String>,
+}
+
+impl BudgetLimitArgs {
+    fn into_limit(self) -> Result<Limit, Report<CliError>> {
+        match (self.time, self.money) {
+            (Some(time), _) => {
+                let time = duration
+                    ::parse(&time)
+                    .map_err(|e| e.change_context(CliError).attach_printable("invalid --time"))?;
+                Ok(Limit::Time(time.num_seconds()))
+            }
+            (_, Some(money)) => {
+                let amount = client
+                    ::parse_rate(&money)
+                    .map_err(|e| e.change_context(CliError).attach_printable("invalid --money"))?;
+                Ok(Limit::Money((amount * 100.0).round() as i64))
+            }
+            (None, None) => unreachable!("clap requires one budget limit"),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum BudgetCommands {
+    /// Set the budget of a project and its subprojects, e.g.
+    /// "track budget set acme/website --time 40h --thresholds 50,80,100"
+    Set {
+        project: String,
+
+        #[command(flatten)]
+        limit: BudgetLimitArgs,
+
+        /// Percentages of the budget at which to warn, 80,100 by default
+        #[arg(long)]
+        thresholds: Option<String>,
+    },
+
+    /// Remove the budget of a project
+    Unset {
+        project: String,
+    },
+
+    /// Show the weekly burn-down of a project's budget
+    Show {
+        project: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum OffCommands {
+    /// Take a day off, e.g. "track off add --date 2026-12-24 --until 2026-12-31 --kind vacation"
+    Add {
+        /// The day off, or the first of several
+        #[arg(long)]
+        date: String,
+
+        /// The last of several days off
+        #[arg(long)]
+        until: Option<String>,
+
+        #[arg(long, value_enum, default_value_t)]
+        kind: OffKind,
+
+        /// A name such as "Christmas Day"
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// Remove a day off, or several with --until
+    Remove {
+        #[arg(long)]
+        date: String,
+
+        #[arg(long)]
+        until: Option<String>,
+    },
+
+    /// List the days off
+    List {
+        /// Only list days off from this day on
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Print records as JSON, CSV or NDJSON instead of text
+        ///
+        /// Every day off has date, kind and name.
+        #[arg(long, value_enum)]
+        format: Option<DataFormat>,
+    },
+
+    /// Import holidays from an iCalendar (.ics) or CSV file with lines of "date,name"
+    Import {
+        file: String,
+
+        #[arg(long, value_enum, default_value_t = OffKind::Holiday)]
+        kind: OffKind,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ProjectCommands {
+    /// Register a project such as "acme/website/frontend"
+    Add {
+        path: String,
+    },
+
+    /// List registered and used projects as a tree
+    List {
+        /// Print records as JSON, CSV or NDJSON instead of text
+        ///
+        /// Every project has its full path.
+        #[arg(long, value_enum)]
+        format: Option<DataFormat>,
+    },
+
+    /// Move a project and its subprojects, updating all entries
+    Move {
+        from: String,
+        to: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum GitCommands {
+    /// Install a post-commit hook that records the time spent on each commit
+    InstallHooks {
+        /// Also attach the time spent as a git note under refs/notes/track
+        #[arg(long)]
+        note: bool,
+
+        /// Also add a Time-Spent trailer to commit messages
+        #[arg(long)]
+        trailer: bool,
+
+        /// Replace existing hooks that were not installed by track
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print the time tracked in this repository since its last recorded commit
+    Spent,
+
+    /// Record the HEAD commit, run by the post-commit hook
+    #[command(hide = true)]
+    PostCommit,
+}
+
+fn parse_time(input: &str) -> Result<DateTime<Local>, Report<CliError>> {
+    time_expr
+        ::parse_at(input, &Local::now())
+        .map_err(|e| e.change_context(CliError).attach_printable(format!("invalid time: {input}")))
+}
+
+/// Fills in the details left open on the command line from the nearest `.track` file.
+fn with_context(details: DetailsArgs, verbosity: i8) -> Result<EntryDetails, Report<CliError>> {
+    let details = details.into();
+    let Ok(dir) = std::env::current_dir() else {
+        return Ok(details);
+    };
+    let loaded = context
+        ::load(&dir)
+        .map_err(|e| e.change_context(CliError).attach_printable("invalid directory context"))?;
+    let Some((path, context)) = loaded else {
+        return Ok(details);
+    };
+    if verbosity > 0 {
+        println!("using context from {}", path.display());
+    }
+    Ok(context.apply(details))
+}
+
+/// Repository, branch and commit of the current directory, if it is inside a git work tree.
+fn current_git_info() -> Option<git::GitInfo> {
+    std::env::current_dir().ok().and_then(|dir| git::info(&dir))
+}
+
+fn parse_optional_time(input: Option<&str>) -> Result<DateTime<Local>, Report<CliError>> {
+    input.map_or_else(|| Ok(Local::now()), parse_time)
+}
+
+pub fn init() -> Result<ExitCode, Report<CliError>> {
     let cli = Cli::parse();
-    let Cli { db_dir: db_dir_option, lockfile: lock_file_option, verbose, quiet, .. } = cli;
+    let Cli { db_dir: db_dir_option, lockfile: lock_file_option, verbose, quiet, auto_cap, .. } = cli;
     let db_dir = db_dir_option.unwrap_or("db.json".to_owned());
     let db_dir = if db_dir.ends_with(".json") { db_dir } else { format!("{}.json", db_dir) };
     let lockfile = lock_file_option.unwrap_or("lockfile".to_owned());
     let tracker = FlatFileTracker::new(&db_dir, &lockfile);
-    let verbosity = if quiet > 0 { -1 as i8 } else { verbose as i8 };
-    let handle_start = || -> Result<(), Report<CliError>> {
+    let verbosity = if quiet > 0 { -1_i8 } else { verbose as i8 };
+    let handle_start = |at: Option<String>, details: DetailsArgs| -> Result<(), Report<CliError>> {
+        let at = parse_optional_time(at.as_deref())?;
+        let details = with_context(details, verbosity)?;
         tracker
-            .start(verbosity)
+            .start_at(at, details, current_git_info(), verbosity)
             .map_err(|e| e.change_context(CliError).attach_printable("tracker failed to start"))?;
 
         Ok(())
     };
-    let handle_stop = || -> Result<(), Report<CliError>> {
+    let handle_stop = |at: Option<String>| -> Result<(), Report<CliError>> {
+        let at = parse_optional_time(at.as_deref())?;
         tracker
-            .stop(verbosity)
+            .stop_at(at, verbosity)
             .map_err(|e| e.change_context(CliError).attach_printable("tracker failed to stop"))?;
         Ok(())
     };
-    let handle_report = || -> Result<(), Report<CliError>> {
+    let handle_continue = |id: Option<u64>, at: Option<String>| -> Result<(), Report<CliError>> {
+        let at = parse_optional_time(at.as_deref())?;
+        tracker
+            .continue_entry(id, at, current_git_info(), verbosity)
+            .map_err(|e| e.change_context(CliError).attach_printable("tracker failed to continue"))?;
+        Ok(())
+    };
+    let handle_add = |
+        from: String,
+        to: Option<String>,
+        duration: Option<String>,
+        details: DetailsArgs,
+        duration_format: DurationFormat
+    | -> Result<(), Report<CliError>> {
+        let from = parse_time(&from)?;
+        let to = match (to, duration) {
+            (Some(to), _) => parse_time(&to)?,
+            (None, Some(length)) => {
+                let length = duration
+                    ::parse(&length)
+                    .map_err(|e| e.change_context(CliError).attach_printable("invalid duration"))?;
+                from + length
+            }
+            (None, None) => {
+                return Err(
+                    Report::new(CliError).attach_printable("either --to or --duration is required")
+                );
+            }
+        };
+        let details = with_context(details, verbosity)?;
+        tracker
+            .add(from, to, details, duration_format, verbosity)
+            .map_err(|e| e.change_context(CliError).attach_printable("tracker failed to add entry"))?;
+        Ok(())
+    };
+    let handle_heartbeat = |
+        project: Option<String>,
+        file: Option<String>,
+        language: Option<String>,
+        at: Option<String>
+    | -> Result<(), Report<CliError>> {
+        let time = parse_optional_time(at.as_deref())?.timestamp() as u64;
+        tracker
+            .heartbeat(&(Heartbeat { time, project, file, language }), verbosity)
+            .map_err(|e| e.change_context(CliError).attach_printable("tracker failed to record heartbeat"))?;
+        Ok(())
+    };
+    let handle_split = |id: u64, at: String| -> Result<(), Report<CliError>> {
+        let at = parse_time(&at)?;
+        tracker
+            .split(id, at, verbosity)
+            .map_err(|e| e.change_context(CliError).attach_printable("tracker failed to split entry"))?;
+        Ok(())
+    };
+    let handle_merge = |ids: Vec<u64>, max_gap: String| -> Result<(), Report<CliError>> {
+        let max_gap = duration
+            ::parse(&max_gap)
+            .map_err(|e| e.change_context(CliError).attach_printable("invalid --max-gap"))?;
+        tracker
+            .merge(&ids, max_gap, verbosity)
+            .map_err(|e| e.change_context(CliError).attach_printable("tracker failed to merge entries"))?;
+        Ok(())
+    };
+    let handle_report = |options: ReportOptions| -> Result<(), Report<CliError>> {
+        if options.branch.is_some() && options.breakdown.is_some_and(Breakdown::uses_heartbeats) {
+            return Err(
+                Report::new(CliError).attach_printable("heartbeats cannot be filtered by --branch")
+            );
+        }
+        let billable_groups = [Breakdown::Client, Breakdown::Project, Breakdown::Branch];
+        if options.billing && options.breakdown.is_some_and(|by| !billable_groups.contains(&by)) {
+            return Err(
+                Report::new(CliError).attach_printable("--billing groups by client, project or branch only")
+            );
+        }
+        if options.charts && options.format != Some(ReportFormat::Html) {
+            return Err(Report::new(CliError).attach_printable("--charts needs --format html"));
+        }
+        if options.breakdown == Some(Breakdown::Project) && options.top.is_some() {
+            return Err(Report::new(CliError).attach_printable("--top does not apply to the project tree"));
+        }
+        if options.breakdown != Some(Breakdown::Project) && options.expansion != Expansion::default() {
+            return Err(
+                Report::new(CliError).attach_printable("--depth and --collapse only apply to --by project")
+            );
+        }
         tracker
-            .report(verbosity)
+            .report(&options, verbosity)
             .map_err(|e| e.change_context(CliError).attach_printable("tracker failed to report"))?;
         Ok(())
     };
+    let handle_status = |
+        short: bool,
+        duration_format: DurationFormat
+    | -> Result<ExitCode, Report<CliError>> {
+        let running = tracker
+            .status(short, duration_format, verbosity)
+            .map_err(|e| e.change_context(CliError).attach_printable("tracker failed to get status"))?;
+        Ok(if running { ExitCode::SUCCESS } else { ExitCode::from(EXIT_NOT_RUNNING) })
+    };
+    let handle_prompt = |format: String| -> Result<(), Report<CliError>> {
+        let entry = tracker
+            .running_entry()
+            .map_err(|e| e.change_context(CliError).attach_printable("failed to read the running timer"))?;
+        if let Some(entry) = entry {
+            let line = prompt
+                ::render(&format, &entry, Local::now().timestamp() as u64)
+                .map_err(|e| e.change_context(CliError).attach_printable("invalid prompt format"))?;
+            println!("{line}");
+        }
+        Ok(())
+    };
+    let handle_config = |key: Option<String>, value: Option<String>| -> Result<(), Report<CliError>> {
+        tracker
+            .config(key.as_deref(), value.as_deref(), verbosity)
+            .map_err(|e| e.change_context(CliError).attach_printable("tracker failed to update settings"))?;
+        Ok(())
+    };
+    let handle_client = |command: ClientCommands| -> Result<(), Report<CliError>> {
+        match command {
+            ClientCommands::Add { name, details } => tracker.add_client(&name, details.into_changes()?, verbosity),
+            ClientCommands::List { format } => tracker.list_clients(format, verbosity),
+            ClientCommands::Edit { name, rename, details, unlink } => {
+                let changes = ClientChanges { name: rename, unlink, ..details.into_changes()? };
+                tracker.edit_client(&name, changes, verbosity)
+            }
+        }.map_err(|e| e.change_context(CliError).attach_printable("tracker failed to update clients"))
+    };
+    let handle_rate = |command: RateCommands| -> Result<(), Report<CliError>> {
+        match command {
+            RateCommands::Set { rate, target } => {
+                let rate = client
+                    ::parse_rate(&rate)
+                    .map_err(|e| e.change_context(CliError).attach_printable("invalid rate"))?;
+                tracker.set_rate(target.into(), Some(rate), verbosity)
+            }
+            RateCommands::Unset { target } => tracker.set_rate(target.into(), None, verbosity),
+            RateCommands::List { format } => tracker.list_rates(format, verbosity),
+        }.map_err(|e| e.change_context(CliError).attach_printable("tracker failed to update rates"))
+    };
+    let handle_invoice = |
+        mut options: InvoiceOptions,
+        period: Option<String>,
+        tax_rate: Option<String>
+    | -> Result<(), Report<CliError>> {
+        options.period = period
+            .map(|period| Period::parse(&period))
+            .transpose()
+            .map_err(|e| e.change_context(CliError).attach_printable("invalid --period"))?;
+        options.tax_rate = tax_rate
+            .map(|rate| invoice::parse_tax_rate(&rate))
+            .transpose()
+            .map_err(|e| e.change_context(CliError).attach_printable("invalid --tax-rate"))?;
+        tracker
+            .invoice(&options, verbosity)
+            .map_err(|e| e.change_context(CliError).attach_printable("tracker failed to create the invoice"))?;
+        Ok(())
+    };
+    let handle_budget = |
+        command: Option<BudgetCommands>,
+        duration_format: DurationFormat,
+        format: Option<DataFormat>
+    | -> Result<(), Report<CliError>> {
+        match command {
+            None => tracker.budgets(None, duration_format, format, verbosity),
+            Some(BudgetCommands::Set { project, limit, thresholds }) => {
+                let thresholds = thresholds
+                    .map(|thresholds| budget::parse_thresholds(&thresholds))
+                    .transpose()
+                    .map_err(|e| e.change_context(CliError).attach_printable("invalid --thresholds"))?;
+                tracker.set_budget(&project, limit.into_limit()?, thresholds, verbosity)
+            }
+            Some(BudgetCommands::Unset { project }) => tracker.remove_budget(&project, verbosity),
+            Some(BudgetCommands::Show { project }) => tracker.budgets(Some(&project), duration_format, format, verbosity),
+        }.map_err(|e| e.change_context(CliError).attach_printable("tracker failed to update budgets"))
+    };
+    let handle_balance = |
+        by: BalancePeriod,
+        duration_format: DurationFormat,
+        format: Option<DataFormat>
+    | -> Result<(), Report<CliError>> {
+        tracker
+            .balance(by, duration_format, format, verbosity)
+            .map_err(|e| e.change_context(CliError).attach_printable("tracker failed to compute the balance"))?;
+        Ok(())
+    };
+    let handle_off = |command: Option<OffCommands>| -> Result<(), Report<CliError>> {
+        let day = |input: &str| parse_time(input).map(|time| time.date_naive());
+        let days = |date: &str, until: Option<&str>| -> Result<_, Report<CliError>> {
+            let first = day(date)?;
+            Ok((first, until.map(day).transpose()?.unwrap_or(first)))
+        };
+        match command {
+            Some(OffCommands::Add { date, until, kind, name }) => {
+                let (first, last) = days(&date, until.as_deref())?;
+                tracker.add_days_off(first, last, kind, name.as_deref(), verbosity)
+            }
+            Some(OffCommands::Remove { date, until }) => {
+                let (first, last) = days(&date, until.as_deref())?;
+                tracker.remove_days_off(first, last, verbosity)
+            }
+            Some(OffCommands::List { from, format }) => {
+                tracker.list_days_off(from.as_deref().map(day).transpose()?, format, verbosity)
+            }
+            Some(OffCommands::Import { file, kind }) => tracker.import_days_off(Path::new(&file), kind, verbosity),
+            None => tracker.list_days_off(None, None, verbosity),
+        }.map_err(|e| e.change_context(CliError).attach_printable("tracker failed to update days off"))
+    };
+    let handle_project = |command: ProjectCommands| -> Result<(), Report<CliError>> {
+        match command {
+            ProjectCommands::Add { path } => tracker.add_project(&path, verbosity),
+            ProjectCommands::List { format } => tracker.list_projects(format, verbosity),
+            ProjectCommands::Move { from, to } => tracker.move_project(&from, &to, verbosity),
+        }.map_err(|e| e.change_context(CliError).attach_printable("tracker failed to update projects"))
+    };
+    let handle_git = |command: GitCommands| -> Result<(), Report<CliError>> {
+        let dir = std::env
+            ::current_dir()
+            .map_err(|_| Report::new(CliError).attach_printable("failed to get the current directory"))?;
+        let not_a_repository = || Report::new(CliError).attach_printable("not inside a git work tree");
+        match command {
+            GitCommands::InstallHooks { note, trailer, force } => {
+                let absolute = |path: &str| -> Result<String, Report<CliError>> {
+                    let path = std::path
+                        ::absolute(path)
+                        .map_err(|_| Report::new(CliError).attach_printable(format!("invalid path: {path}")))?;
+                    Ok(git::shell_quote(&path.to_string_lossy()))
+                };
+                let executable = std::env
+                    ::current_exe()
+                    .map_err(|_| Report::new(CliError).attach_printable("failed to locate the track executable"))?;
+                let command = format!(
+                    "{} --db-dir {} --lockfile {}",
+                    git::shell_quote(&executable.to_string_lossy()),
+                    absolute(&db_dir)?,
+                    absolute(&lockfile)?
+                );
+                let hooks = git
+                    ::install_hooks(&dir, &command, git::HookOptions { note, trailer }, force)
+                    .map_err(|e| e.change_context(CliError).attach_printable("failed to install git hooks"))?;
+                if verbosity > -1 {
+                    for hook in hooks {
+                        println!("installed {}", hook.display());
+                    }
+                }
+            }
+            GitCommands::Spent => {
+                let info = git::info(&dir).ok_or_else(not_a_repository)?;
+                tracker
+                    .commit_spent(&info, Local::now(), verbosity)
+                    .map_err(|e| e.change_context(CliError).attach_printable("failed to compute the time spent"))?;
+            }
+            GitCommands::PostCommit => {
+                let info = git::info(&dir).ok_or_else(not_a_repository)?;
+                tracker
+                    .record_commit(info, Local::now(), verbosity)
+                    .map_err(|e| e.change_context(CliError).attach_printable("failed to record the commit"))?;
+            }
+        }
+        Ok(())
+    };
+    // Prompts, heartbeats and git hooks run very often and must stay fast. Hooks also
+    // capture the output, which must not contain warnings.
+    let quick = matches!(
+        cli.command,
+        Commands::Prompt { .. } |
+            Commands::Heartbeat { .. } |
+            Commands::Git { command: GitCommands::Spent | GitCommands::PostCommit }
+    );
+    // An explicit stop time ends an overdue session where the user says it ended.
+    let explicit_stop = matches!(cli.command, Commands::Stop { at: Some(_) });
+    let capped = if quick || explicit_stop {
+        false
+    } else {
+        tracker
+            .guard_session(auto_cap, verbosity)
+            .map_err(|e| e.change_context(CliError).attach_printable("failed to check the running session"))?
+    };
     match cli.command {
-        Commands::Start => handle_start()?,
-        Commands::Stop => handle_stop()?,
-        Commands::Report => handle_report()?,
+        Commands::Start { at, details } => handle_start(at, details)?,
+        // An overdue timer that was just capped is already stopped, as reported on stderr.
+        Commands::Stop { .. } if capped => {}
+        Commands::Stop { at } => handle_stop(at)?,
+        Commands::Continue { id, at } => handle_continue(id, at)?,
+        Commands::Add { from, to, duration, details, duration_format } =>
+            handle_add(from, to, duration, details, duration_format)?,
+        Commands::Heartbeat { project, file, language, at } =>
+            handle_heartbeat(project, file, language, at)?,
+        Commands::Split { id, at } => handle_split(id, at)?,
+        Commands::Merge { ids, max_gap } => handle_merge(ids, max_gap)?,
+        Commands::Report {
+            duration_format,
+            by,
+            top,
+            branch,
+            depth,
+            collapse,
+            billing,
+            group_by,
+            from,
+            to,
+            format,
+            charts,
+        } => {
+            let collapsed = collapse
+                .iter()
+                .map(|path| project::normalize(path))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.change_context(CliError).attach_printable("invalid --collapse project"))?;
+            let expansion = Expansion { depth, collapsed };
+            let timestamp = |time: Option<String>| -> Result<Option<u64>, Report<CliError>> {
+                Ok(time.as_deref().map(parse_time).transpose()?.map(|time| time.timestamp() as u64))
+            };
+            let (from, to) = (timestamp(from)?, timestamp(to)?);
+            let breakdown = by;
+            handle_report(
+                ReportOptions {
+                    duration_format,
+                    breakdown,
+                    top,
+                    branch,
+                    expansion,
+                    billing,
+                    group_by,
+                    from,
+                    to,
+                    format,
+                    charts,
+                }
+            )?
+        }
+        Commands::Status { short, duration_format } => {
+            return handle_status(short, duration_format);
+        }
+        Commands::Prompt { format } => handle_prompt(format)?,
+        Commands::Config { key, value } => handle_config(key, value)?,
+        Commands::Client { command } => handle_client(command)?,
+        Commands::Rate { command } => handle_rate(command)?,
+        Commands::Invoice { client, period, format, template, output, number, tax_rate, dry_run } => {
+            let options = InvoiceOptions { client, format, template, output, number, dry_run, ..InvoiceOptions::default() };
+            handle_invoice(options, period, tax_rate)?
+        }
+        Commands::Budget { command, duration_format, format } => handle_budget(command, duration_format, format)?,
+        Commands::Balance { by, duration_format, format } => handle_balance(by, duration_format, format)?,
+        Commands::Off { command } => handle_off(command)?,
+        Commands::Project { command } => handle_project(command)?,
+        Commands::Git { command } => handle_git(command)?,
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,170 @@
+use error_stack::Report;
+use serde::{ Deserialize, Serialize };
+use thiserror::Error;
+
+use super::{ project, rounding::Rounding };
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("unknown client: {0}")] UnknownClient(String),
+
+    #[error("client already exists: {0}")] DuplicateClient(String),
+
+    #[error("invalid rate: {0}")] InvalidRate(String),
+}
+
+/// Someone the time is spent for, with the details needed to bill them.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Client {
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+
+    /// ISO 4217 code such as `EUR`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+
+    /// Hourly rate used when neither the project nor a tag has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_rate: Option<f64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_id: Option<String>,
+
+    /// Tax charged on invoices in percent, e.g. `19`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_rate: Option<f64>,
+
+    /// How the client's time is rounded, overriding the rounding setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rounding: Option<Rounding>,
+
+    /// Projects of the client, including their subprojects
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub projects: Vec<String>,
+}
+
+/// Changes to a client. Fields left as `None` are kept.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClientChanges {
+    pub name: Option<String>,
+    pub address: Option<String>,
+    pub currency: Option<String>,
+    pub default_rate: Option<f64>,
+    pub tax_id: Option<String>,
+    pub tax_rate: Option<f64>,
+    /// `Some(None)` removes the client's own rule
+    pub rounding: Option<Option<Rounding>>,
+    pub link: Vec<String>,
+    pub unlink: Vec<String>,
+}
+
+impl Client {
+    pub fn apply(&mut self, changes: ClientChanges) {
+        let ClientChanges { name, address, currency, default_rate, tax_id, tax_rate, rounding, link, unlink } =
+            changes;
+        if let Some(name) = name {
+            self.name = name;
+        }
+        if address.is_some() {
+            self.address = address;
+        }
+        if currency.is_some() {
+            self.currency = currency.map(|currency| currency.to_uppercase());
+        }
+        if default_rate.is_some() {
+            self.default_rate = default_rate;
+        }
+        if tax_id.is_some() {
+            self.tax_id = tax_id;
+        }
+        if tax_rate.is_some() {
+            self.tax_rate = tax_rate;
+        }
+        if let Some(rounding) = rounding {
+            self.rounding = rounding;
+        }
+        self.projects.retain(|project| !unlink.contains(project));
+        for project in link {
+            if !self.projects.contains(&project) {
+                self.projects.push(project);
+            }
+        }
+    }
+
+    /// How specifically the client is linked to `project`: the depth of the closest linked
+    /// ancestor, or `None` when the project does not belong to the client.
+    fn link_depth(&self, project: &str) -> Option<usize> {
+        self.projects
+            .iter()
+            .filter(|linked| project::is_within(project, linked))
+            .map(|linked| project::depth(linked))
+            .max()
+    }
+}
+
+/// The client `project` belongs to. When several clients are linked to ancestors of the
+/// project, the one linked most specifically wins.
+pub fn client_of<'a>(clients: &'a [Client], project: &str) -> Option<&'a Client> {
+    clients
+        .iter()
+        .filter_map(|client| client.link_depth(project).map(|depth| (depth, client)))
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, client)| client)
+}
+
+pub fn find<'a>(clients: &'a mut [Client], name: &str) -> Result<&'a mut Client, Report<ClientError>> {
+    clients
+        .iter_mut()
+        .find(|client| client.name == name)
+        .ok_or_else(|| Report::new(ClientError::UnknownClient(name.to_owned())))
+}
+
+pub fn parse_rate(input: &str) -> Result<f64, Report<ClientError>> {
+    match input.trim().parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate >= 0.0 => Ok(rate),
+        _ => Err(Report::new(ClientError::InvalidRate(input.to_owned()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(name: &str, projects: &[&str]) -> Client {
+        Client {
+            name: name.to_owned(),
+            projects: projects
+                .iter()
+                .map(|project| project.to_string())
+                .collect(),
+            ..Client::default()
+        }
+    }
+
+    #[test]
+    fn the_most_specific_link_wins() {
+        let clients = [client("acme", &["acme"]), client("agency", &["acme/website"])];
+        let name = |project| client_of(&clients, project).map(|client| client.name.as_str());
+        assert_eq!(name("acme/api"), Some("acme"));
+        assert_eq!(name("acme/website/frontend"), Some("agency"));
+        assert_eq!(name("acmecorp"), None);
+    }
+
+    #[test]
+    fn changes_keep_unset_fields() {
+        let mut acme = Client { currency: Some("EUR".to_owned()), ..client("acme", &["acme", "old"]) };
+        acme.apply(ClientChanges {
+            default_rate: Some(95.0),
+            link: vec!["globex".to_owned()],
+            unlink: vec!["old".to_owned()],
+            ..ClientChanges::default()
+        });
+        assert_eq!(acme.currency.as_deref(), Some("EUR"));
+        assert_eq!(acme.default_rate, Some(95.0));
+        assert_eq!(acme.projects, vec!["acme".to_owned(), "globex".to_owned()]);
+        assert!(parse_rate("-1").is_err());
+        assert!(parse_rate("abc").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use std::{ fs, path::{ Path, PathBuf } };
+use error_stack::{ Report, ResultExt };
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::tracker::entry::EntryDetails;
+
+/// Name of the file that sets the context of a directory tree.
+pub const FILE_NAME: &str = ".track";
+
+#[derive(Error, Debug)]
+pub enum ContextError {
+    #[error("failed to read the context file: {0}")] ReadError(#[source] std::io::Error),
+
+    #[error("failed to parse the context file: {0}")] ParseError(#[source] toml::de::Error),
+}
+
+/// Defaults for entries started inside a directory tree, read from a `.track` file such as
+///
+/// ```toml
+/// project = "website"
+/// tags = ["frontend"]
+/// billable = true
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Context {
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+    pub billable: Option<bool>,
+}
+
+impl Context {
+    /// Fills in what `details` leaves open. Tags given on the command line replace the
+    /// context's tags instead of adding to them.
+    pub fn apply(self, details: EntryDetails) -> EntryDetails {
+        EntryDetails {
+            project: details.project.or(self.project),
+            tags: if details.tags.is_empty() { self.tags } else { details.tags },
+            note: details.note.or(self.note),
+            billable: details.billable.or(self.billable),
+        }
+    }
+}
+
+/// The nearest `.track` file in `dir` or one of its ancestors.
+pub fn find(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|ancestor| ancestor.join(FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+pub fn parse(content: &str) -> Result<Context, Report<ContextError>> {
+    toml::from_str(content).map_err(|e| Report::new(ContextError::ParseError(e)))
+}
+
+/// Loads the context that applies to `dir` together with the file it came from.
+pub fn load(dir: &Path) -> Result<Option<(PathBuf, Context)>, Report<ContextError>> {
+    let Some(path) = find(dir) else {
+        return Ok(None);
+    };
+    let content = fs
+        ::read_to_string(&path)
+        .map_err(|e| Report::new(ContextError::ReadError(e)))
+        .attach_printable_lazy(|| format!("{}", path.display()))?;
+    let context = parse(&content).attach_printable_lazy(|| format!("{}", path.display()))?;
+    Ok(Some((path, context)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_line_details_override_the_context() {
+        let context = parse("project = \"website\"\ntags = [\"frontend\"]\nbillable = true\n").unwrap();
+        let details = context.clone().apply(EntryDetails {
+            tags: vec!["meeting".to_owned()],
+            ..EntryDetails::default()
+        });
+        assert_eq!(details, EntryDetails {
+            project: Some("website".to_owned()),
+            tags: vec!["meeting".to_owned()],
+            note: None,
+            billable: Some(true),
+        });
+        let details = context.apply(EntryDetails {
+            project: Some("api".to_owned()),
+            ..EntryDetails::default()
+        });
+        assert_eq!(details.project.as_deref(), Some("api"));
+        assert_eq!(details.tags, vec!["frontend".to_owned()]);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(parse("projcet = \"typo\"").is_err());
+        assert_eq!(parse("").unwrap(), Context::default());
+    }
+}
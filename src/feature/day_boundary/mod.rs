@@ -0,0 +1,143 @@
+use chrono::{ Duration, Local, NaiveDate, NaiveTime, TimeZone };
+use clap::ValueEnum;
+use serde::{ Deserialize, Serialize };
+
+use super::tracker::entry::Entry;
+
+/// What to do with entries that cross the start of a day.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DayBoundary {
+    /// Keep entries whole everywhere
+    None,
+    /// Split entries when they are stored
+    Split,
+    /// Keep stored entries whole but split them when reporting
+    #[default]
+    Virtual,
+}
+
+/// The day an instant belongs to when days start at `day_start` instead of midnight.
+pub fn day_of(timestamp: u64, day_start: NaiveTime) -> NaiveDate {
+    day_of_in(&Local, timestamp, day_start)
+}
+
+pub fn day_of_in<Tz: TimeZone>(tz: &Tz, timestamp: u64, day_start: NaiveTime) -> NaiveDate {
+    let local = tz
+        .timestamp_opt(timestamp as i64, 0)
+        .earliest()
+        .expect("Invalid timestamp")
+        .naive_local();
+    if local.time() < day_start {
+        local.date() - Duration::days(1)
+    } else {
+        local.date()
+    }
+}
+
+/// The instant at which `day` starts.
+pub fn start_of_day(day: NaiveDate, day_start: NaiveTime) -> u64 {
+    start_of_day_in(&Local, day, day_start)
+}
+
+pub fn start_of_day_in<Tz: TimeZone>(tz: &Tz, day: NaiveDate, day_start: NaiveTime) -> u64 {
+    // A day start inside a DST gap falls back to the next valid minute.
+    let mut naive = day.and_time(day_start);
+    loop {
+        if let Some(instant) = tz.from_local_datetime(&naive).earliest() {
+            return instant.timestamp() as u64;
+        }
+        naive += Duration::minutes(1);
+    }
+}
+
+/// Splits `entry` into one piece per day it touches. The first piece keeps the entry's id,
+/// and a running entry is split up to `now` with its last piece left running.
+pub fn split(entry: &Entry, day_start: NaiveTime, now: u64) -> Vec<Entry> {
+    split_in(&Local, entry, day_start, now)
+}
+
+pub fn split_in<Tz: TimeZone>(
+    tz: &Tz,
+    entry: &Entry,
+    day_start: NaiveTime,
+    now: u64
+) -> Vec<Entry> {
+    let stop = entry.stop.unwrap_or(now);
+    let mut pieces = Vec::new();
+    let mut piece = entry.clone();
+    loop {
+        let next_day = day_of_in(tz, piece.start, day_start) + Duration::days(1);
+        let boundary = start_of_day_in(tz, next_day, day_start);
+        if boundary >= stop {
+            break;
+        }
+        let mut rest = piece.clone();
+        rest.start = boundary;
+        piece.stop = Some(boundary);
+        piece.active = false;
+        pieces.push(piece);
+        piece = rest;
+    }
+    pieces.push(piece);
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use super::*;
+
+    fn at(d: u32, h: u32, m: u32) -> u64 {
+        Utc.with_ymd_and_hms(2026, 9, d, h, m, 0).unwrap().timestamp() as u64
+    }
+
+    fn spans(pieces: &[Entry]) -> Vec<(u64, Option<u64>)> {
+        pieces
+            .iter()
+            .map(|piece| (piece.start, piece.stop))
+            .collect()
+    }
+
+    fn entry(start: u64, stop: Option<u64>) -> Entry {
+        Entry { id: 7, start, stop, active: stop.is_none(), ..Entry::default() }
+    }
+
+    #[test]
+    fn entries_within_a_day_are_kept_whole() {
+        let entry = entry(at(14, 9, 0), Some(at(14, 17, 0)));
+        assert_eq!(split_in(&Utc, &entry, NaiveTime::MIN, 0), vec![entry]);
+    }
+
+    #[test]
+    fn entries_are_split_at_midnight() {
+        let pieces = split_in(&Utc, &entry(at(14, 22, 0), Some(at(16, 1, 0))), NaiveTime::MIN, 0);
+        assert_eq!(
+            spans(&pieces),
+            vec![
+                (at(14, 22, 0), Some(at(15, 0, 0))),
+                (at(15, 0, 0), Some(at(16, 0, 0))),
+                (at(16, 0, 0), Some(at(16, 1, 0)))
+            ]
+        );
+        assert!(pieces.iter().all(|piece| piece.id == 7));
+    }
+
+    #[test]
+    fn entries_are_split_at_a_custom_day_start() {
+        let four = NaiveTime::from_hms_opt(4, 0, 0).unwrap();
+        let pieces = split_in(&Utc, &entry(at(14, 22, 0), Some(at(15, 5, 0))), four, 0);
+        assert_eq!(
+            spans(&pieces),
+            vec![(at(14, 22, 0), Some(at(15, 4, 0))), (at(15, 4, 0), Some(at(15, 5, 0)))]
+        );
+        assert_eq!(day_of_in(&Utc, at(15, 3, 59), four), NaiveDate::from_ymd_opt(2026, 9, 14).unwrap());
+    }
+
+    #[test]
+    fn running_entries_keep_their_last_piece_running() {
+        let pieces = split_in(&Utc, &entry(at(14, 23, 0), None), NaiveTime::MIN, at(15, 2, 0));
+        assert_eq!(spans(&pieces), vec![(at(14, 23, 0), Some(at(15, 0, 0))), (at(15, 0, 0), None)]);
+        assert!(!pieces[0].active && pieces[1].active);
+    }
+}
@@ -0,0 +1,178 @@
+use chrono::Duration;
+use clap::ValueEnum;
+use error_stack::Report;
+use serde::{ Deserialize, Serialize };
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DurationError {
+    #[error("duration is empty")]
+    Empty,
+
+    #[error("invalid duration: {0}")] Invalid(String),
+
+    #[error("unknown duration unit: {0}")] UnknownUnit(String),
+
+    #[error("duration is missing a unit: {0}")] MissingUnit(String),
+
+    #[error("duration is too long: {0}")] OutOfRange(String),
+}
+
+/// How durations are rendered in command output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DurationFormat {
+    /// `01:30:00`
+    #[default]
+    Clock,
+    /// `1h 30m`
+    Compact,
+    /// `1.50` hours
+    Decimal,
+}
+
+/// Parses `1h30m`, `1h 30m`, `90m`, `45s`, `1.5h`, `01:30:00`, `1:30` and plain decimal hours such as `1.5`.
+pub fn parse(input: &str) -> Result<Duration, Report<DurationError>> {
+    let input = input.trim().to_lowercase();
+    if input.is_empty() {
+        return Err(Report::new(DurationError::Empty));
+    }
+    let invalid = || Report::new(DurationError::Invalid(input.clone()));
+
+    if input.contains(':') {
+        let parts = input
+            .split(':')
+            .map(|part| part.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        let (hours, minutes, seconds) = match parts[..] {
+            [hours, minutes] => (hours, minutes, 0),
+            [hours, minutes, seconds] => (hours, minutes, seconds),
+            _ => {
+                return Err(invalid());
+            }
+        };
+        if minutes >= 60 || seconds >= 60 {
+            return Err(invalid());
+        }
+        return Ok(
+            Duration::hours(hours as i64) +
+                Duration::minutes(minutes as i64) +
+                Duration::seconds(seconds as i64)
+        );
+    }
+
+    if let Ok(hours) = input.parse::<f64>() {
+        return from_hours(hours, &input);
+    }
+
+    let compact: String = input.split_whitespace().collect();
+    let mut total = Duration::zero();
+    let mut chars = compact.chars().peekable();
+    while chars.peek().is_some() {
+        let mut amount = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+            amount.push(c);
+        }
+        let mut unit = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+            unit.push(c);
+        }
+        let amount: f64 = amount.parse().map_err(|_| invalid())?;
+        if unit.is_empty() {
+            return Err(Report::new(DurationError::MissingUnit(input.clone())));
+        }
+        let seconds_per_unit = match unit.as_str() {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            "d" | "day" | "days" => 86400.0,
+            _ => {
+                return Err(Report::new(DurationError::UnknownUnit(unit)));
+            }
+        };
+        total = total
+            .checked_add(&from_hours((amount * seconds_per_unit) / 3600.0, &input)?)
+            .ok_or_else(|| Report::new(DurationError::OutOfRange(input.clone())))?;
+    }
+
+    Ok(total)
+}
+
+fn from_hours(hours: f64, input: &str) -> Result<Duration, Report<DurationError>> {
+    if !hours.is_finite() || hours < 0.0 {
+        return Err(Report::new(DurationError::Invalid(input.to_owned())));
+    }
+    // The cast saturates, which is then out of range as well
+    Duration
+        ::try_seconds((hours * 3600.0).round() as i64)
+        .ok_or_else(|| Report::new(DurationError::OutOfRange(input.to_owned())))
+}
+
+pub fn format(duration: Duration, format: DurationFormat) -> String {
+    let sign = if duration < Duration::zero() { "-" } else { "" };
+    let total_seconds = duration.num_seconds().abs();
+    let (hours, minutes, seconds) = (
+        total_seconds / 3600,
+        (total_seconds % 3600) / 60,
+        total_seconds % 60,
+    );
+
+    match format {
+        DurationFormat::Clock => format!("{sign}{hours:02}:{minutes:02}:{seconds:02}"),
+        DurationFormat::Compact if hours > 0 => format!("{sign}{hours}h {minutes}m"),
+        DurationFormat::Compact => format!("{sign}{minutes}m"),
+        DurationFormat::Decimal => format!("{sign}{:.2}", (total_seconds as f64) / 3600.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(value: i64) -> Duration {
+        Duration::minutes(value)
+    }
+
+    #[test]
+    fn parses_unit_expressions() {
+        assert_eq!(parse("1h30m").unwrap(), minutes(90));
+        assert_eq!(parse("1h 30m").unwrap(), minutes(90));
+        assert_eq!(parse("90m").unwrap(), minutes(90));
+        assert_eq!(parse("1.5h").unwrap(), minutes(90));
+        assert_eq!(parse("45s").unwrap(), Duration::seconds(45));
+        assert_eq!(parse("2 hours 5 minutes").unwrap(), minutes(125));
+        let error = parse("1h30").unwrap_err();
+        assert!(matches!(error.current_context(), DurationError::MissingUnit(input) if input == "1h30"));
+    }
+
+    #[test]
+    fn parses_clock_and_decimal_expressions() {
+        assert_eq!(parse("01:30:00").unwrap(), minutes(90));
+        assert_eq!(parse("1:30").unwrap(), minutes(90));
+        assert_eq!(parse("100:00:01").unwrap(), Duration::hours(100) + Duration::seconds(1));
+        assert_eq!(parse("1.5").unwrap(), minutes(90));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for input in ["", "abc", "1x", "1:60", "1:2:3:4", "-1h", "h"] {
+            assert!(parse(input).is_err(), "expected {input:?} to be rejected");
+        }
+        for input in ["1e300", "99999999999999999999h", "9000000000000000s 9000000000000000s"] {
+            let error = parse(input).expect_err(input);
+            assert!(matches!(error.current_context(), DurationError::OutOfRange(_)), "{input}: {error:?}");
+        }
+    }
+
+    #[test]
+    fn formats_durations() {
+        let duration = minutes(90) + Duration::seconds(5);
+        assert_eq!(format(duration, DurationFormat::Clock), "01:30:05");
+        assert_eq!(format(duration, DurationFormat::Compact), "1h 30m");
+        assert_eq!(format(minutes(30), DurationFormat::Compact), "30m");
+        assert_eq!(format(minutes(90), DurationFormat::Decimal), "1.50");
+        assert_eq!(format(Duration::hours(60), DurationFormat::Clock), "60:00:00");
+        assert_eq!(format(-minutes(90), DurationFormat::Compact), "-1h 30m");
+    }
+}
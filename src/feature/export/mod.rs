@@ -0,0 +1,560 @@
+use chrono::{ Duration, NaiveDate, SecondsFormat };
+use clap::ValueEnum;
+use error_stack::Report;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::{
+    aggregate::{ Group, GroupBy },
+    balance::BalanceRow,
+    billing::Amounts,
+    budget::BurndownRow,
+    client::Client,
+    report::BillingRow,
+    time_off::DayOff,
+    tracker::entry::{ local_time, Entry },
+};
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("failed to serialize the output: {0}")] SerializeError(#[source] serde_json::Error),
+}
+
+/// Machine-readable output of reports and lists.
+///
+/// Every format holds the same records: `json` a pretty-printed array of them, `ndjson` one
+/// compact object per line and `csv` a header line followed by one line per record. Fields
+/// are only ever added to a record, never renamed or removed. Durations are whole seconds,
+/// money is in cents, times are RFC 3339 in the local time zone and days are `YYYY-MM-DD`.
+/// Missing values are `null` in JSON and empty in CSV, and lists are joined by `;` in CSV.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DataFormat {
+    Json,
+    Csv,
+    Ndjson,
+}
+
+/// A record of machine-readable output.
+pub trait Record: Serialize {
+    /// Names of the CSV columns, the same as the JSON fields
+    const COLUMNS: &'static [&'static str];
+
+    /// Values of the CSV columns
+    fn cells(&self) -> Vec<String>;
+}
+
+/// Renders `records` in `format`.
+pub fn render<R: Record>(records: &[R], format: DataFormat) -> Result<String, Report<ExportError>> {
+    render_with_columns(records, R::COLUMNS, format)
+}
+
+/// Renders `records` in `format`, with `columns` as the CSV header.
+pub fn render_with_columns<R: Record>(
+    records: &[R],
+    columns: &[&str],
+    format: DataFormat
+) -> Result<String, Report<ExportError>> {
+    let serialize_error = |e| Report::new(ExportError::SerializeError(e));
+    match format {
+        DataFormat::Json => Ok(serde_json::to_string_pretty(records).map_err(serialize_error)? + "\n"),
+        DataFormat::Ndjson =>
+            records
+                .iter()
+                .map(|record| serde_json::to_string(record).map(|line| line + "\n"))
+                .collect::<Result<String, _>>()
+                .map_err(serialize_error),
+        DataFormat::Csv => {
+            let mut csv = csv_line(columns.iter().map(|column| (*column).to_owned()));
+            for record in records {
+                csv.push_str(&csv_line(record.cells()));
+            }
+            Ok(csv)
+        }
+    }
+}
+
+/// A line of CSV, quoting the cells that contain separators, quotes or line breaks.
+fn csv_line(cells: impl IntoIterator<Item = String>) -> String {
+    let cells: Vec<String> = cells
+        .into_iter()
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell
+            }
+        })
+        .collect();
+    cells.join(",") + "\n"
+}
+
+fn optional<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(T::to_string).unwrap_or_default()
+}
+
+/// A finished entry of `track report`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EntryRecord {
+    /// `None` for sessions derived from heartbeats
+    pub id: Option<u64>,
+    pub start: String,
+    pub stop: Option<String>,
+    pub seconds: i64,
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+    pub billable: Option<bool>,
+}
+
+impl EntryRecord {
+    pub fn new(entry: &Entry, now: u64) -> Self {
+        let time = |timestamp| local_time(timestamp).to_rfc3339_opts(SecondsFormat::Secs, false);
+        Self {
+            id: (!entry.is_session()).then_some(entry.id),
+            start: time(entry.start),
+            stop: entry.stop.map(time),
+            seconds: entry.elapsed(now).num_seconds(),
+            project: entry.details.project.clone(),
+            tags: entry.details.tags.clone(),
+            note: entry.details.note.clone(),
+            billable: entry.details.billable,
+        }
+    }
+}
+
+impl Record for EntryRecord {
+    const COLUMNS: &'static [&'static str] = &["id", "start", "stop", "seconds", "project", "tags", "note", "billable"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            optional(&self.id),
+            self.start.clone(),
+            optional(&self.stop),
+            self.seconds.to_string(),
+            optional(&self.project),
+            self.tags.join(";"),
+            optional(&self.note),
+            optional(&self.billable)
+        ]
+    }
+}
+
+/// A row of `track report --by`, such as a branch or a project by its full path.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RowRecord {
+    pub key: String,
+    pub seconds: i64,
+}
+
+impl RowRecord {
+    pub fn new(key: &str, time: Duration) -> Self {
+        Self { key: key.to_owned(), seconds: time.num_seconds() }
+    }
+}
+
+impl Record for RowRecord {
+    const COLUMNS: &'static [&'static str] = &["key", "seconds"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.key.clone(), self.seconds.to_string()]
+    }
+}
+
+/// A group of `track report --group-by` with the keys of the group and its parents in
+/// `path`. The grand total comes last with an empty path. In CSV the path is spread over one
+/// column per level, named after the level, and subtotals leave the deeper levels empty.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct GroupRecord {
+    pub path: Vec<String>,
+    pub seconds: i64,
+    #[serde(skip)]
+    levels: usize,
+}
+
+impl GroupRecord {
+    /// The groups below `total` depth first, followed by `total` itself.
+    pub fn from_group(total: &Group, levels: usize) -> Vec<Self> {
+        let mut records = Vec::new();
+        let mut path: Vec<String> = Vec::new();
+        for (depth, group) in total.flatten() {
+            path.truncate(depth);
+            path.push(group.key.clone());
+            records.push(Self { path: path.clone(), seconds: group.time.num_seconds(), levels });
+        }
+        records.push(Self { path: Vec::new(), seconds: total.time.num_seconds(), levels });
+        records
+    }
+
+    /// The CSV header for groups by `levels`.
+    pub fn columns(levels: &[GroupBy]) -> Vec<&'static str> {
+        levels
+            .iter()
+            .map(|level| match level {
+                GroupBy::Day => "day",
+                GroupBy::Week => "week",
+                GroupBy::Month => "month",
+                GroupBy::Project => "project",
+                GroupBy::Tag => "tag",
+                GroupBy::Client => "client",
+            })
+            .chain(["seconds"])
+            .collect()
+    }
+}
+
+impl Record for GroupRecord {
+    const COLUMNS: &'static [&'static str] = &["path", "seconds"];
+
+    fn cells(&self) -> Vec<String> {
+        let mut cells = self.path.clone();
+        cells.resize(self.levels, String::new());
+        cells.push(self.seconds.to_string());
+        cells
+    }
+}
+
+/// An amount of money in one currency, `null` when no currency is configured.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AmountRecord {
+    pub currency: Option<String>,
+    pub cents: i64,
+}
+
+/// A row of `track report --billing`, where `rounded_seconds` is the time after rounding its
+/// billable part. In CSV the amounts are written as e.g. `1250 EUR;300 USD`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BillingRecord {
+    pub key: String,
+    pub seconds: i64,
+    pub rounded_seconds: i64,
+    pub amounts: Vec<AmountRecord>,
+}
+
+impl From<&BillingRow> for BillingRecord {
+    fn from(row: &BillingRow) -> Self {
+        let Amounts(amounts) = &row.amounts;
+        Self {
+            key: row.key.clone(),
+            seconds: row.time.num_seconds(),
+            rounded_seconds: row.rounded.num_seconds(),
+            amounts: amounts
+                .iter()
+                .map(|(currency, cents)| AmountRecord { currency: currency.clone(), cents: *cents })
+                .collect(),
+        }
+    }
+}
+
+impl Record for BillingRecord {
+    const COLUMNS: &'static [&'static str] = &["key", "seconds", "rounded_seconds", "amounts"];
+
+    fn cells(&self) -> Vec<String> {
+        let amounts: Vec<String> = self.amounts
+            .iter()
+            .map(|amount| match &amount.currency {
+                Some(currency) => format!("{} {currency}", amount.cents),
+                None => amount.cents.to_string(),
+            })
+            .collect();
+        vec![self.key.clone(), self.seconds.to_string(), self.rounded_seconds.to_string(), amounts.join(";")]
+    }
+}
+
+/// A project of `track project list`, including the parents of every project.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ProjectRecord {
+    pub path: String,
+}
+
+impl Record for ProjectRecord {
+    const COLUMNS: &'static [&'static str] = &["path"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.path.clone()]
+    }
+}
+
+/// A client of `track client list`, with its hourly rate and tax rate in percent.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ClientRecord {
+    pub name: String,
+    pub address: Option<String>,
+    pub currency: Option<String>,
+    pub rate: Option<f64>,
+    pub tax_id: Option<String>,
+    pub tax_rate: Option<f64>,
+    pub rounding: Option<String>,
+    pub projects: Vec<String>,
+}
+
+impl From<&Client> for ClientRecord {
+    fn from(client: &Client) -> Self {
+        Self {
+            name: client.name.clone(),
+            address: client.address.clone(),
+            currency: client.currency.clone(),
+            rate: client.default_rate,
+            tax_id: client.tax_id.clone(),
+            tax_rate: client.tax_rate,
+            rounding: client.rounding.as_ref().map(ToString::to_string),
+            projects: client.projects.clone(),
+        }
+    }
+}
+
+impl Record for ClientRecord {
+    const COLUMNS: &'static [&'static str] = &[
+        "name",
+        "address",
+        "currency",
+        "rate",
+        "tax_id",
+        "tax_rate",
+        "rounding",
+        "projects",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            optional(&self.address),
+            optional(&self.currency),
+            optional(&self.rate),
+            optional(&self.tax_id),
+            optional(&self.tax_rate),
+            optional(&self.rounding),
+            self.projects.join(";")
+        ]
+    }
+}
+
+/// An hourly rate of `track rate list`, where `target` is `client`, `project` or `tag`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RateRecord {
+    pub target: &'static str,
+    pub name: String,
+    pub rate: f64,
+}
+
+impl Record for RateRecord {
+    const COLUMNS: &'static [&'static str] = &["target", "name", "rate"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.target.to_owned(), self.name.clone(), self.rate.to_string()]
+    }
+}
+
+/// A budget of `track budget`, where `kind` is `time` with `limit`, `used` and `remaining` in
+/// seconds, or `money` with them in cents of `currency`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BudgetRecord {
+    pub project: String,
+    pub kind: &'static str,
+    pub limit: i64,
+    pub used: i64,
+    pub remaining: i64,
+    pub percent: f64,
+    pub currency: Option<String>,
+}
+
+impl Record for BudgetRecord {
+    const COLUMNS: &'static [&'static str] = &["project", "kind", "limit", "used", "remaining", "percent", "currency"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.project.clone(),
+            self.kind.to_owned(),
+            self.limit.to_string(),
+            self.used.to_string(),
+            self.remaining.to_string(),
+            self.percent.to_string(),
+            optional(&self.currency)
+        ]
+    }
+}
+
+/// A week of `track budget show`, such as `2026-W38`, with `used` and `remaining` in
+/// seconds or cents like the limit of the budget.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BurndownRecord {
+    pub project: String,
+    pub week: String,
+    pub used: i64,
+    pub remaining: i64,
+}
+
+impl BurndownRecord {
+    pub fn new(project: &str, row: &BurndownRow) -> Self {
+        Self {
+            project: project.to_owned(),
+            week: row.week.format("%G-W%V").to_string(),
+            used: row.used,
+            remaining: row.remaining,
+        }
+    }
+}
+
+impl Record for BurndownRecord {
+    const COLUMNS: &'static [&'static str] = &["project", "week", "used", "remaining"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.project.clone(), self.week.clone(), self.used.to_string(), self.remaining.to_string()]
+    }
+}
+
+/// A week or month of `track balance`, where `running_seconds` is the balance up to and
+/// including it, and `days_off` holds the kind of every day off on a scheduled working day.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BalanceRecord {
+    pub period: String,
+    pub first_day: NaiveDate,
+    pub expected_seconds: i64,
+    pub worked_seconds: i64,
+    pub balance_seconds: i64,
+    pub running_seconds: i64,
+    pub days_off: Vec<String>,
+}
+
+impl BalanceRecord {
+    pub fn new(row: &BalanceRow, period: String) -> Self {
+        Self {
+            period,
+            first_day: row.period,
+            expected_seconds: row.expected.num_seconds(),
+            worked_seconds: row.worked.num_seconds(),
+            balance_seconds: row.balance().num_seconds(),
+            running_seconds: row.running.num_seconds(),
+            days_off: row.days_off
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+impl Record for BalanceRecord {
+    const COLUMNS: &'static [&'static str] = &[
+        "period",
+        "first_day",
+        "expected_seconds",
+        "worked_seconds",
+        "balance_seconds",
+        "running_seconds",
+        "days_off",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.period.clone(),
+            self.first_day.to_string(),
+            self.expected_seconds.to_string(),
+            self.worked_seconds.to_string(),
+            self.balance_seconds.to_string(),
+            self.running_seconds.to_string(),
+            self.days_off.join(";")
+        ]
+    }
+}
+
+/// A day off of `track off list`, where `kind` is `vacation`, `sick` or `holiday`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DayOffRecord {
+    pub date: NaiveDate,
+    pub kind: String,
+    pub name: Option<String>,
+}
+
+impl From<&DayOff> for DayOffRecord {
+    fn from(off: &DayOff) -> Self {
+        Self { date: off.date, kind: off.kind.to_string(), name: off.name.clone() }
+    }
+}
+
+impl Record for DayOffRecord {
+    const COLUMNS: &'static [&'static str] = &["date", "kind", "name"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.date.to_string(), self.kind.clone(), optional(&self.name)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use super::*;
+
+    fn entries() -> Vec<EntryRecord> {
+        vec![
+            EntryRecord {
+                id: Some(1),
+                start: "2026-09-14T09:00:00+02:00".to_owned(),
+                stop: Some("2026-09-14T10:30:00+02:00".to_owned()),
+                seconds: 5400,
+                project: Some("acme/website".to_owned()),
+                tags: vec!["code".to_owned(), "review".to_owned()],
+                note: Some("fix \"login\", again".to_owned()),
+                billable: Some(true),
+            },
+            EntryRecord {
+                id: Some(2),
+                start: "2026-09-14T11:00:00+02:00".to_owned(),
+                stop: Some("2026-09-14T11:15:00+02:00".to_owned()),
+                seconds: 900,
+                project: None,
+                tags: Vec::new(),
+                note: None,
+                billable: None,
+            }
+        ]
+    }
+
+    #[test]
+    fn entries_match_their_snapshots() {
+        assert_eq!(render(&entries(), DataFormat::Json).unwrap(), include_str!("snapshots/entries.json"));
+        assert_eq!(render(&entries(), DataFormat::Ndjson).unwrap(), include_str!("snapshots/entries.ndjson"));
+        assert_eq!(render(&entries(), DataFormat::Csv).unwrap(), include_str!("snapshots/entries.csv"));
+    }
+
+    #[test]
+    fn groups_match_their_snapshots() {
+        let group = |key: &str, hours: i64, children: Vec<Group>| Group {
+            key: key.to_owned(),
+            time: Duration::hours(hours),
+            children,
+        };
+        let total = group(
+            "total",
+            5,
+            vec![
+                group("2026-W38", 3, vec![group("acme", 2, Vec::new()), group("blog", 1, Vec::new())]),
+                group("2026-W39", 2, vec![group("acme", 2, Vec::new())])
+            ]
+        );
+        let levels = [GroupBy::Week, GroupBy::Project];
+        let records = GroupRecord::from_group(&total, levels.len());
+        assert_eq!(render(&records, DataFormat::Ndjson).unwrap(), include_str!("snapshots/groups.ndjson"));
+        let csv = render_with_columns(&records, &GroupRecord::columns(&levels), DataFormat::Csv).unwrap();
+        assert_eq!(csv, include_str!("snapshots/groups.csv"));
+    }
+
+    #[test]
+    fn billing_rows_match_their_snapshots() {
+        let row = BillingRow {
+            key: "Acme".to_owned(),
+            time: Duration::minutes(100),
+            rounded: Duration::hours(2),
+            amounts: Amounts(vec![(Some("EUR".to_owned()), 19_000), (None, 500)]),
+        };
+        let records = [BillingRecord::from(&row)];
+        assert_eq!(render(&records, DataFormat::Json).unwrap(), include_str!("snapshots/billing.json"));
+        assert_eq!(render(&records, DataFormat::Csv).unwrap(), include_str!("snapshots/billing.csv"));
+    }
+
+    #[test]
+    fn empty_lists_keep_their_shape() {
+        let records: [RowRecord; 0] = [];
+        assert_eq!(render(&records, DataFormat::Json).unwrap(), "[]\n");
+        assert_eq!(render(&records, DataFormat::Ndjson).unwrap(), "");
+        assert_eq!(render(&records, DataFormat::Csv).unwrap(), "key,seconds\n");
+    }
+}
@@ -0,0 +1,343 @@
+use std::{ fs, path::{ Path, PathBuf }, process::Command };
+use error_stack::{ Report, ResultExt };
+use serde::{ Deserialize, Serialize };
+use thiserror::Error;
+
+/// First line after the shebang of every hook written by `track git install-hooks`.
+pub const HOOK_MARKER: &str = "# installed by track";
+
+/// Trailer and note key holding the time spent on a commit.
+pub const TRAILER: &str = "Time-Spent";
+
+#[derive(Error, Debug)]
+pub enum GitError {
+    #[error("not inside a git work tree")]
+    NotARepository,
+
+    #[error("a {0} hook that was not installed by track already exists")]
+    HookExists(String),
+
+    #[error("failed to write the hook: {0}")] WriteError(#[source] std::io::Error),
+}
+
+/// Where in a git repository an entry was started.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitInfo {
+    /// Canonical path of the work tree, which tells apart repositories with the same name
+    pub repository: String,
+
+    /// Checked out branch, `None` on a detached HEAD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+
+    /// HEAD commit, `None` before the first commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+/// A commit recorded by the post-commit hook, with the time tracked in its repository since
+/// the previous recorded commit.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitRecord {
+    pub commit: String,
+
+    pub repository: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+
+    /// When the commit was recorded
+    pub time: u64,
+
+    /// Time spent on the commit in seconds
+    pub spent: i64,
+}
+
+impl GitInfo {
+    /// Name of the work tree directory, for display.
+    pub fn name(&self) -> &str {
+        Path::new(&self.repository)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&self.repository)
+    }
+}
+
+impl CommitRecord {
+    pub fn short_commit(&self) -> &str {
+        &self.commit[..self.commit.len().min(7)]
+    }
+}
+
+/// Which hooks `track git install-hooks` writes besides the post-commit hook.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HookOptions {
+    /// Attach the time spent as a git note under `refs/notes/track`
+    pub note: bool,
+    /// Add a `Time-Spent` trailer to commit messages
+    pub trailer: bool,
+}
+
+/// A git work tree and the directory holding its metadata.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Repository {
+    pub work_tree: PathBuf,
+    pub git_dir: PathBuf,
+}
+
+impl Repository {
+    /// The repository whose work tree contains `dir`, found by looking for `.git` in `dir`
+    /// and its ancestors. A `.git` file, as used by linked work trees and submodules,
+    /// points to the actual git directory.
+    pub fn discover(dir: &Path) -> Option<Self> {
+        dir.ancestors().find_map(|work_tree| {
+            let dot_git = work_tree.join(".git");
+            let git_dir = if dot_git.is_dir() {
+                dot_git
+            } else if dot_git.is_file() {
+                let content = fs::read_to_string(&dot_git).ok()?;
+                let target = content.trim().strip_prefix("gitdir:")?.trim();
+                work_tree.join(target)
+            } else {
+                return None;
+            };
+            Some(Self { work_tree: work_tree.to_path_buf(), git_dir })
+        })
+    }
+
+    /// Directory shared by all work trees, holding refs and hooks.
+    pub fn common_dir(&self) -> PathBuf {
+        match fs::read_to_string(self.git_dir.join("commondir")) {
+            Ok(common) => self.git_dir.join(common.trim()),
+            Err(_) => self.git_dir.clone(),
+        }
+    }
+
+    /// The canonical path of the work tree, which identifies the repository.
+    pub fn path(&self) -> String {
+        fs::canonicalize(&self.work_tree)
+            .unwrap_or_else(|_| self.work_tree.clone())
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Directory git runs the hooks from. Git itself is asked so that `core.hooksPath` is
+    /// respected, falling back to the default location when git is not available.
+    pub fn hooks_dir(&self) -> PathBuf {
+        Command::new("git")
+            .args(["rev-parse", "--git-path", "hooks"])
+            .current_dir(&self.work_tree)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|path| self.work_tree.join(path.trim()))
+            .unwrap_or_else(|| self.common_dir().join("hooks"))
+    }
+
+    /// The checked out branch, or `None` on a detached HEAD.
+    pub fn branch(&self) -> Option<String> {
+        let head = fs::read_to_string(self.git_dir.join("HEAD")).ok()?;
+        head.trim().strip_prefix("ref:")?.trim().strip_prefix("refs/heads/").map(str::to_owned)
+    }
+
+    /// The commit HEAD points to, following symbolic refs into loose and packed refs.
+    pub fn head(&self) -> Option<String> {
+        let head = fs::read_to_string(self.git_dir.join("HEAD")).ok()?;
+        match head.trim().strip_prefix("ref:") {
+            Some(name) => self.resolve(name.trim()),
+            None => Some(head.trim().to_owned()),
+        }
+    }
+
+    /// The commit a full ref name such as `refs/heads/main` points to.
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        for dir in [self.git_dir.clone(), self.common_dir()] {
+            if let Ok(commit) = fs::read_to_string(dir.join(name)) {
+                return Some(commit.trim().to_owned());
+            }
+        }
+        let packed = fs::read_to_string(self.common_dir().join("packed-refs")).ok()?;
+        packed
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+            .find_map(|line| {
+                let (commit, reference) = line.split_once(' ')?;
+                (reference.trim() == name).then(|| commit.to_owned())
+            })
+    }
+
+    pub fn info(&self) -> GitInfo {
+        GitInfo { repository: self.path(), branch: self.branch(), commit: self.head() }
+    }
+}
+
+/// Writes the hooks into the repository containing `dir`. `command` is the shell command
+/// that runs track against the current database. Hooks written by track are replaced,
+/// others only when `force` is set. Returns the paths of the written hooks.
+pub fn install_hooks(
+    dir: &Path,
+    command: &str,
+    options: HookOptions,
+    force: bool
+) -> Result<Vec<PathBuf>, Report<GitError>> {
+    let repository = Repository::discover(dir).ok_or_else(|| Report::new(GitError::NotARepository))?;
+    let hooks_dir = repository.hooks_dir();
+    fs::create_dir_all(&hooks_dir).map_err(|e| Report::new(GitError::WriteError(e)))?;
+
+    let mut hooks = vec![("post-commit", post_commit_hook(command, options.note))];
+    if options.trailer {
+        hooks.push(("prepare-commit-msg", prepare_commit_msg_hook(command)));
+    }
+    let mut written = Vec::new();
+    for (name, script) in hooks {
+        let path = hooks_dir.join(name);
+        if let Ok(existing) = fs::read_to_string(&path) {
+            if !force && !existing.contains(HOOK_MARKER) {
+                return Err(
+                    Report::new(GitError::HookExists(name.to_owned())).attach_printable(
+                        "use --force to replace it"
+                    )
+                );
+            }
+        }
+        fs::write(&path, script).map_err(|e| Report::new(GitError::WriteError(e)))?;
+        make_executable(&path).attach_printable_lazy(|| format!("{}", path.display()))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), Report<GitError>> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).map_err(|e|
+        Report::new(GitError::WriteError(e))
+    )
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<(), Report<GitError>> {
+    Ok(())
+}
+
+fn post_commit_hook(command: &str, note: bool) -> String {
+    let mut script = format!(
+        "#!/bin/sh\n{HOOK_MARKER}: records the time spent on each commit\n\
+         spent=$({command} git post-commit) || exit 0\n"
+    );
+    if note {
+        script.push_str(
+            &format!("git notes --ref=track add -f -m \"{TRAILER}: $spent\" HEAD >/dev/null 2>&1\n")
+        );
+    }
+    script
+}
+
+fn prepare_commit_msg_hook(command: &str) -> String {
+    format!(
+        "#!/bin/sh\n{HOOK_MARKER}: adds the time spent to commit messages\n\
+         case \"$2\" in merge|squash|commit) exit 0 ;; esac\n\
+         spent=$({command} git spent) || exit 0\n\
+         git interpret-trailers --in-place --if-exists replace --trailer \"{TRAILER}: $spent\" \"$1\"\n"
+    )
+}
+
+/// Quotes `value` for a POSIX shell.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Git details for entries started in `dir`, or `None` outside a work tree.
+pub fn info(dir: &Path) -> Option<GitInfo> {
+    Repository::discover(dir).map(|repository| repository.info())
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::TempDir;
+    use super::*;
+
+    const COMMIT: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    /// A repository named "website" inside a temporary directory, removed when it is dropped.
+    fn repository(head: &str) -> (TempDir, Repository) {
+        let root = TempDir::new().unwrap();
+        let git_dir = root.path().join("website").join(".git");
+        fs::create_dir_all(git_dir.join("refs").join("heads")).unwrap();
+        fs::write(git_dir.join("HEAD"), head).unwrap();
+        let repository = Repository::discover(&root.path().join("website")).unwrap();
+        (root, repository)
+    }
+
+    #[test]
+    fn branches_are_resolved_through_loose_and_packed_refs() {
+        let (_root, repository) = repository("ref: refs/heads/feature/login\n");
+        let info = repository.info();
+        assert_eq!(info.name(), "website");
+        assert_eq!(info.branch.as_deref(), Some("feature/login"));
+        assert_eq!(info.commit, None);
+        fs::write(
+            repository.git_dir.join("packed-refs"),
+            format!("# pack-refs with: peeled\n{COMMIT} refs/heads/feature/login\n")
+        ).unwrap();
+        assert_eq!(repository.head().as_deref(), Some(COMMIT));
+        fs::create_dir_all(repository.git_dir.join("refs/heads/feature")).unwrap();
+        fs::write(repository.git_dir.join("refs/heads/feature/login"), "f00\n").unwrap();
+        assert_eq!(repository.head().as_deref(), Some("f00"));
+    }
+
+    #[test]
+    fn hooks_do_not_replace_foreign_hooks() {
+        let (_root, repository) = repository("ref: refs/heads/main\n");
+        let hooks_dir = repository.git_dir.join("hooks");
+        let options = HookOptions { note: true, trailer: true };
+        let written = install_hooks(&repository.work_tree, "'track'", options, false).unwrap();
+        assert_eq!(written, vec![hooks_dir.join("post-commit"), hooks_dir.join("prepare-commit-msg")]);
+        let hook = fs::read_to_string(hooks_dir.join("post-commit")).unwrap();
+        assert!(hook.contains("spent=$('track' git post-commit) || exit 0"));
+        assert!(hook.contains("git notes --ref=track add"));
+        install_hooks(&repository.work_tree, "'track'", HookOptions::default(), false).unwrap();
+
+        fs::write(hooks_dir.join("post-commit"), "#!/bin/sh\necho mine\n").unwrap();
+        assert!(install_hooks(&repository.work_tree, "'track'", options, false).is_err());
+        install_hooks(&repository.work_tree, "'track'", options, true).unwrap();
+    }
+
+    #[test]
+    fn repositories_with_the_same_name_are_told_apart() {
+        let (_first, first) = repository("ref: refs/heads/main\n");
+        let (_second, second) = repository("ref: refs/heads/main\n");
+        assert_eq!(first.info().name(), second.info().name());
+        assert_ne!(first.info().repository, second.info().repository);
+    }
+
+    #[test]
+    fn hooks_go_where_core_hooks_path_points() {
+        let root = TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(root.path()).output().unwrap().status.success()
+        };
+        if !git(&["init", "-q"]) {
+            return;
+        }
+        assert!(git(&["config", "core.hooksPath", "githooks"]));
+        let written = install_hooks(root.path(), "'track'", HookOptions::default(), false).unwrap();
+        assert_eq!(
+            fs::canonicalize(&written[0]).unwrap(),
+            fs::canonicalize(root.path()).unwrap().join("githooks").join("post-commit")
+        );
+    }
+
+    #[test]
+    fn shell_arguments_are_quoted() {
+        assert_eq!(shell_quote("/tmp/it's here"), "'/tmp/it'\\''s here'");
+    }
+
+    #[test]
+    fn detached_heads_have_no_branch() {
+        let (_root, repository) = repository(&format!("{COMMIT}\n"));
+        assert_eq!(repository.branch(), None);
+        assert_eq!(repository.head().as_deref(), Some(COMMIT));
+    }
+}
@@ -0,0 +1,194 @@
+use chrono::Duration;
+use serde::{ Deserialize, Serialize };
+
+use super::tracker::entry::{ Entry, EntryDetails };
+
+/// A sign of life from an editor plugin or shell hook.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Heartbeat {
+    pub time: u64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+
+    /// Language reported by the editor, detected from the file extension when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+impl Heartbeat {
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref().or_else(|| self.file.as_deref().and_then(language_of))
+    }
+}
+
+/// The time between a heartbeat and the next one, when that one follows within the idle timeout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span<'a> {
+    pub heartbeat: &'a Heartbeat,
+    pub start: u64,
+    pub stop: u64,
+}
+
+impl Span<'_> {
+    pub fn overlap(&self, from: u64, to: u64) -> Duration {
+        Duration::seconds(self.stop.min(to).saturating_sub(self.start.max(from)) as i64)
+    }
+}
+
+/// Attributes the time between consecutive heartbeats to the earlier one.
+/// Heartbeats followed by a gap longer than `idle_timeout` end their session and get no span.
+pub fn spans(heartbeats: &[Heartbeat], idle_timeout: Duration) -> Vec<Span<'_>> {
+    let mut sorted: Vec<&Heartbeat> = heartbeats.iter().collect();
+    sorted.sort_by_key(|heartbeat| heartbeat.time);
+    let idle_timeout = idle_timeout.num_seconds().max(0) as u64;
+
+    sorted
+        .windows(2)
+        .filter(|pair| pair[1].time - pair[0].time <= idle_timeout)
+        .map(|pair| Span { heartbeat: pair[0], start: pair[0].time, stop: pair[1].time })
+        .filter(|span| span.stop > span.start)
+        .collect()
+}
+
+/// Derives sessions from heartbeats. Consecutive heartbeats of the same project belong to
+/// one session while they are at most `idle_timeout` apart. When the project changes within
+/// the timeout, the previous session lasts until the first heartbeat of the next project.
+/// Sessions are returned as finished entries without an id.
+pub fn sessions(heartbeats: &[Heartbeat], idle_timeout: Duration) -> Vec<Entry> {
+    let mut sessions: Vec<Entry> = Vec::new();
+    for span in spans(heartbeats, idle_timeout) {
+        if let Some(session) = sessions.last_mut() {
+            if session.stop == Some(span.start) && session.details.project == span.heartbeat.project {
+                session.stop = Some(span.stop);
+                continue;
+            }
+        }
+        let mut session = Entry::new(span.start, false, EntryDetails {
+            project: span.heartbeat.project.clone(),
+            ..EntryDetails::default()
+        });
+        session.stop = Some(span.stop);
+        sessions.push(session);
+    }
+    sessions
+}
+
+/// Detects the language of a file from its name or extension.
+pub fn language_of(file: &str) -> Option<&'static str> {
+    let name = file.rsplit(['/', '\\']).next().unwrap_or(file);
+    match name {
+        "Makefile" | "makefile" => {
+            return Some("Makefile");
+        }
+        "Dockerfile" => {
+            return Some("Docker");
+        }
+        _ => {}
+    }
+    let (_, extension) = name.rsplit_once('.')?;
+    let language = match extension.to_lowercase().as_str() {
+        "rs" => "Rust",
+        "py" => "Python",
+        "js" | "mjs" | "cjs" | "jsx" => "JavaScript",
+        "ts" | "tsx" => "TypeScript",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "C++",
+        "cs" => "C#",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "swift" => "Swift",
+        "scala" => "Scala",
+        "dart" => "Dart",
+        "ex" | "exs" => "Elixir",
+        "erl" => "Erlang",
+        "hs" => "Haskell",
+        "lua" => "Lua",
+        "sh" | "bash" | "zsh" | "fish" => "Shell",
+        "sql" => "SQL",
+        "html" | "htm" => "HTML",
+        "css" => "CSS",
+        "scss" | "sass" => "Sass",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "md" | "markdown" => "Markdown",
+        "json" => "JSON",
+        "toml" => "TOML",
+        "yaml" | "yml" => "YAML",
+        _ => {
+            return None;
+        }
+    };
+    Some(language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beat(time: u64, project: &str) -> Heartbeat {
+        Heartbeat { time, project: Some(project.to_owned()), ..Heartbeat::default() }
+    }
+
+    fn session_spans(sessions: &[Entry]) -> Vec<(u64, Option<u64>, Option<&str>)> {
+        sessions
+            .iter()
+            .map(|session| (session.start, session.stop, session.details.project.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn heartbeats_within_the_timeout_form_one_session() {
+        let heartbeats = [beat(0, "a"), beat(120, "a"), beat(60, "a"), beat(1000, "a"), beat(1100, "a")];
+        assert_eq!(
+            session_spans(&sessions(&heartbeats, Duration::minutes(5))),
+            vec![(0, Some(120), Some("a")), (1000, Some(1100), Some("a"))]
+        );
+    }
+
+    #[test]
+    fn switching_projects_ends_the_previous_session() {
+        let heartbeats = [beat(0, "a"), beat(100, "a"), beat(200, "b"), beat(300, "b")];
+        assert_eq!(
+            session_spans(&sessions(&heartbeats, Duration::minutes(5))),
+            vec![(0, Some(200), Some("a")), (200, Some(300), Some("b"))]
+        );
+    }
+
+    #[test]
+    fn spans_attribute_time_to_the_earlier_heartbeat() {
+        let heartbeats = [beat(0, "a"), beat(100, "a"), beat(10_000, "a"), beat(10_050, "b")];
+        let spans: Vec<_> = spans(&heartbeats, Duration::minutes(5))
+            .iter()
+            .map(|span| (span.start, span.stop))
+            .collect();
+        assert_eq!(spans, vec![(0, 100), (10_000, 10_050)]);
+    }
+
+    #[test]
+    fn languages_are_detected_from_file_names() {
+        assert_eq!(language_of("src/main.rs"), Some("Rust"));
+        assert_eq!(language_of("web/App.TSX"), Some("TypeScript"));
+        assert_eq!(language_of("build/Makefile"), Some("Makefile"));
+        assert_eq!(language_of("LICENSE"), None);
+        assert_eq!(language_of("archive.xyz"), None);
+        let heartbeat = Heartbeat {
+            file: Some("page.html".to_owned()),
+            language: Some("Jinja".to_owned()),
+            ..Heartbeat::default()
+        };
+        assert_eq!(heartbeat.language(), Some("Jinja"));
+    }
+
+    #[test]
+    fn lone_heartbeats_do_not_count() {
+        let heartbeats = [beat(0, "a"), beat(10_000, "b")];
+        assert!(sessions(&heartbeats, Duration::minutes(5)).is_empty());
+    }
+}
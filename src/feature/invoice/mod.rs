@@ -0,0 +1,529 @@
+use std::{ fmt, fs, path::Path };
+use chrono::{ Datelike, Duration, Months, NaiveDate };
+use clap::ValueEnum;
+use error_stack::Report;
+use serde::{ Deserialize, Serialize };
+use thiserror::Error;
+
+use super::{
+    billing::{ self, Billed, Billing },
+    client::{ self, Client },
+    day_boundary,
+    tracker::entry::Entry,
+};
+
+/// Label for invoiced time without a project.
+const NO_PROJECT: &str = "(no project)";
+
+#[derive(Error, Debug)]
+pub enum InvoiceError {
+    #[error("invalid period: {0:?}, expected e.g. 2026-09, 2026-09-14 or 2026-09-01..2026-09-15")]
+    InvalidPeriod(String),
+
+    #[error("invalid tax rate: {0}")] InvalidTaxRate(String),
+
+    #[error("no unbilled time for {0} in {1}")] NothingToInvoice(String, Period),
+
+    #[error("invoice {0} already exists")] DuplicateNumber(String),
+
+    #[error("failed to read the template: {0}")] ReadError(#[source] std::io::Error),
+
+    #[error("unknown template placeholder: {0}")] UnknownPlaceholder(String),
+
+    #[error("unclosed template section: {0}")] UnclosedSection(String),
+
+    #[error("unclosed template placeholder: {{{{{0}")] UnclosedPlaceholder(String),
+}
+
+/// Document format of an invoice.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum InvoiceFormat {
+    #[default]
+    Text,
+    Markdown,
+    Html,
+}
+
+impl InvoiceFormat {
+    fn default_template(self) -> &'static str {
+        match self {
+            Self::Text => include_str!("templates/invoice.txt"),
+            Self::Markdown => include_str!("templates/invoice.md"),
+            Self::Html => include_str!("templates/invoice.html"),
+        }
+    }
+}
+
+/// The days an invoice covers, both inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Period {
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+}
+
+impl Period {
+    /// Parses a month such as `2026-09`, a day such as `2026-09-14`, or a range of days such
+    /// as `2026-09-01..2026-09-15`.
+    pub fn parse(input: &str) -> Result<Self, Report<InvoiceError>> {
+        let invalid = || Report::new(InvoiceError::InvalidPeriod(input.to_owned()));
+        let day = |input: &str| NaiveDate::parse_from_str(input.trim(), "%Y-%m-%d").map_err(|_| invalid());
+        let input = input.trim();
+        if let Some((first, last)) = input.split_once("..") {
+            let period = Self { first: day(first)?, last: day(last)? };
+            return if period.first <= period.last { Ok(period) } else { Err(invalid()) };
+        }
+        if let Ok(first) = NaiveDate::parse_from_str(&format!("{input}-01"), "%Y-%m-%d") {
+            if input.len() <= "2026-09".len() {
+                let last = first + Months::new(1) - Duration::days(1);
+                return Ok(Self { first, last });
+            }
+        }
+        let day = day(input)?;
+        Ok(Self { first: day, last: day })
+    }
+
+    pub fn contains(&self, day: NaiveDate) -> bool {
+        self.first <= day && day <= self.last
+    }
+
+    /// The month before the one `today` lies in.
+    pub fn previous_month(today: NaiveDate) -> Self {
+        let first = today.with_day(1).expect("every month has a first day") - Months::new(1);
+        Self { first, last: first + Months::new(1) - Duration::days(1) }
+    }
+
+    fn is_month(&self) -> bool {
+        self.first.day() == 1 && self.last == self.first + Months::new(1) - Duration::days(1)
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_month() {
+            write!(f, "{}", self.first.format("%Y-%m"))
+        } else if self.first == self.last {
+            write!(f, "{}", self.first)
+        } else {
+            write!(f, "{}..{}", self.first, self.last)
+        }
+    }
+}
+
+/// An issued invoice, kept so that its number is not reused.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvoiceRecord {
+    pub number: String,
+
+    pub client: String,
+
+    pub period: String,
+
+    /// Day the invoice was issued
+    pub date: NaiveDate,
+
+    /// Ids of the invoiced entries
+    pub entries: Vec<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+
+    /// Total including tax, in cents
+    pub total: i64,
+}
+
+/// What `track invoice` bills and how the document is written.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InvoiceOptions {
+    pub client: String,
+    /// Days billed, the previous month when `None`
+    pub period: Option<Period>,
+    pub format: InvoiceFormat,
+    /// Template file replacing the built-in template of the format
+    pub template: Option<String>,
+    /// File the document is written to instead of standard output
+    pub output: Option<String>,
+    /// Invoice number, the next free number of the year when `None`
+    pub number: Option<String>,
+    /// Tax rate in percent, overriding the client's
+    pub tax_rate: Option<f64>,
+    /// Render the invoice without marking any entries as invoiced
+    pub dry_run: bool,
+}
+
+/// One line of an invoice: the time of a project at one hourly rate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineItem {
+    pub description: String,
+    /// Time before rounding
+    pub raw: Duration,
+    /// Time billed after rounding
+    pub time: Duration,
+    pub hourly: f64,
+    /// Amount in cents
+    pub amount: i64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Invoice {
+    pub number: String,
+    pub client: Client,
+    pub period: Period,
+    pub date: NaiveDate,
+    pub currency: Option<String>,
+    pub items: Vec<LineItem>,
+    /// Tax rate in percent, `None` when no tax is charged
+    pub tax_rate: Option<f64>,
+    /// Ids of the invoiced entries
+    pub entries: Vec<u64>,
+}
+
+impl Invoice {
+    pub fn subtotal(&self) -> i64 {
+        self.items
+            .iter()
+            .map(|item| item.amount)
+            .sum()
+    }
+
+    pub fn tax(&self) -> i64 {
+        self.tax_rate.map_or(0, |rate| ((self.subtotal() as f64) * rate / 100.0).round() as i64)
+    }
+
+    pub fn total(&self) -> i64 {
+        self.subtotal() + self.tax()
+    }
+
+    pub fn record(&self) -> InvoiceRecord {
+        InvoiceRecord {
+            number: self.number.clone(),
+            client: self.client.name.clone(),
+            period: self.period.to_string(),
+            date: self.date,
+            entries: self.entries.clone(),
+            currency: self.currency.clone(),
+            total: self.total(),
+        }
+    }
+}
+
+/// The next free number of `year` such as `2026-007`, counting up from the highest number
+/// issued that year.
+pub fn next_number(records: &[InvoiceRecord], year: i32) -> String {
+    let prefix = format!("{year}-");
+    let last = records
+        .iter()
+        .filter_map(|record| record.number.strip_prefix(&prefix)?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0);
+    format!("{prefix}{:03}", last + 1)
+}
+
+pub fn parse_tax_rate(input: &str) -> Result<f64, Report<InvoiceError>> {
+    match input.trim().trim_end_matches('%').parse::<f64>() {
+        Ok(rate) if rate.is_finite() && (0.0..=100.0).contains(&rate) => Ok(rate),
+        _ => Err(Report::new(InvoiceError::InvalidTaxRate(input.to_owned()))),
+    }
+}
+
+/// Bills the finished entries of `client` that started inside `period` and were not
+/// invoiced yet. Line items are grouped by project and hourly rate and sorted by project;
+/// entries without a rate, such as non-billable ones, are left out.
+pub fn build(
+    number: String,
+    client: &Client,
+    entries: &[Entry],
+    billing: &Billing,
+    period: Period,
+    date: NaiveDate
+) -> Result<Invoice, Report<InvoiceError>> {
+    let mut invoiced = Vec::new();
+    let mut items = Vec::new();
+    for entry in entries {
+        let Some(stop) = entry.stop else {
+            continue;
+        };
+        let belongs_to_client = entry.details.project
+            .as_deref()
+            .and_then(|project| client::client_of(billing.clients, project))
+            .is_some_and(|owner| owner.name == client.name);
+        if
+            entry.invoice.is_some() ||
+            !belongs_to_client ||
+            !period.contains(day_boundary::day_of(entry.start, billing.day_start))
+        {
+            continue;
+        }
+        let Some(rate) = billing::rate_for(&entry.details, billing.clients, billing.rates) else {
+            continue;
+        };
+        invoiced.push(entry.id);
+        let project = entry.details.project.clone().unwrap_or_else(|| NO_PROJECT.to_owned());
+        items.push(((project, rate.hourly.to_bits()), entry, entry.elapsed(stop)));
+    }
+    if invoiced.is_empty() {
+        return Err(Report::new(InvoiceError::NothingToInvoice(client.name.clone(), period)));
+    }
+
+    let mut items: Vec<LineItem> = billing
+        .bill(items)
+        .into_iter()
+        .map(|((description, hourly), Billed { time, rounded, amounts })| LineItem {
+            description,
+            raw: time,
+            time: rounded,
+            hourly: f64::from_bits(hourly),
+            amount: amounts.0
+                .iter()
+                .map(|(_, cents)| cents)
+                .sum(),
+        })
+        .collect();
+    items.sort_by(|a, b| a.description.cmp(&b.description).then_with(|| b.hourly.total_cmp(&a.hourly)));
+    Ok(Invoice {
+        number,
+        client: client.clone(),
+        period,
+        date,
+        currency: client.currency.clone(),
+        items,
+        tax_rate: client.tax_rate,
+        entries: invoiced,
+    })
+}
+
+/// The template at `path`, or the built-in template of `format`.
+pub fn template(format: InvoiceFormat, path: Option<&Path>) -> Result<String, Report<InvoiceError>> {
+    match path {
+        Some(path) => fs::read_to_string(path).map_err(|e| Report::new(InvoiceError::ReadError(e))),
+        None => Ok(format.default_template().to_owned()),
+    }
+}
+
+/// Renders `invoice` with a template.
+///
+/// Placeholders are written as `{{name}}`:
+/// - `{{number}}`, `{{date}}`, `{{period}}`, `{{currency}}`
+/// - `{{client}}`, `{{address}}`, `{{tax_id}}`
+/// - `{{subtotal}}`, `{{tax_rate}}`, `{{tax}}`, `{{total}}` and `{{total_hours}}`
+///
+/// `{{#items}}...{{/items}}` is repeated for every line item, with `{{description}}`,
+/// `{{hours}}`, `{{rate}}` and `{{amount}}`. Any other section such as
+/// `{{#address}}...{{/address}}` is only kept when its placeholder is not empty. Values are
+/// HTML-escaped for the HTML format.
+pub fn render(template: &str, invoice: &Invoice, format: InvoiceFormat) -> Result<String, Report<InvoiceError>> {
+    render_with(template, &|name| invoice_value(invoice, name), Some(invoice), format)
+}
+
+fn render_with(
+    template: &str,
+    value: &dyn Fn(&str) -> Option<String>,
+    invoice: Option<&Invoice>,
+    format: InvoiceFormat
+) -> Result<String, Report<InvoiceError>> {
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        output.push_str(&rest[..open]);
+        let after = &rest[open + 2..];
+        let close = after
+            .find("}}")
+            .ok_or_else(|| Report::new(InvoiceError::UnclosedPlaceholder(after.to_owned())))?;
+        let tag = after[..close].trim();
+        rest = &after[close + 2..];
+
+        let Some(section) = tag.strip_prefix('#') else {
+            let rendered = value(tag).ok_or_else(|| Report::new(InvoiceError::UnknownPlaceholder(tag.to_owned())))?;
+            output.push_str(&escape(&rendered, format));
+            continue;
+        };
+        let end = format!("{{{{/{section}}}}}");
+        let body_end = rest
+            .find(&end)
+            .ok_or_else(|| Report::new(InvoiceError::UnclosedSection(section.to_owned())))?;
+        let body = &rest[..body_end];
+        rest = &rest[body_end + end.len()..];
+        match (section, invoice) {
+            ("items", Some(invoice)) => {
+                for item in &invoice.items {
+                    let item_value = |name: &str| item_value(invoice, item, name).or_else(|| value(name));
+                    output.push_str(&render_with(body, &item_value, None, format)?);
+                }
+            }
+            (section, _) => {
+                let shown = value(section)
+                    .ok_or_else(|| Report::new(InvoiceError::UnknownPlaceholder(section.to_owned())))?;
+                if !shown.is_empty() {
+                    output.push_str(&render_with(body, value, invoice, format)?);
+                }
+            }
+        }
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn invoice_value(invoice: &Invoice, name: &str) -> Option<String> {
+    let money = |cents| billing::format_money(cents, invoice.currency.as_deref());
+    let value = match name {
+        "number" => invoice.number.clone(),
+        "date" => invoice.date.to_string(),
+        "period" => invoice.period.to_string(),
+        "currency" => invoice.currency.clone().unwrap_or_default(),
+        "client" => invoice.client.name.clone(),
+        "address" => invoice.client.address.clone().unwrap_or_default(),
+        "tax_id" => invoice.client.tax_id.clone().unwrap_or_default(),
+        "subtotal" => money(invoice.subtotal()),
+        "tax_rate" => invoice.tax_rate.map(|rate| format!("{rate}%")).unwrap_or_default(),
+        "tax" => money(invoice.tax()),
+        "total" => money(invoice.total()),
+        "total_hours" => hours(invoice.items.iter().map(|item| item.time).sum()),
+        _ => {
+            return None;
+        }
+    };
+    Some(value)
+}
+
+fn item_value(invoice: &Invoice, item: &LineItem, name: &str) -> Option<String> {
+    let value = match name {
+        "description" => item.description.clone(),
+        "hours" => hours(item.time),
+        "rate" => billing::format_money((item.hourly * 100.0).round() as i64, invoice.currency.as_deref()),
+        "amount" => billing::format_money(item.amount, invoice.currency.as_deref()),
+        _ => {
+            return None;
+        }
+    };
+    Some(value)
+}
+
+fn hours(time: Duration) -> String {
+    format!("{:.2}", (time.num_seconds() as f64) / 3600.0)
+}
+
+fn escape(value: &str, format: InvoiceFormat) -> String {
+    match format {
+        InvoiceFormat::Html =>
+            value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;"),
+        InvoiceFormat::Markdown => value.replace('|', "\\|"),
+        InvoiceFormat::Text => value.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+    use crate::feature::{ billing::Rates, rounding::Rounding, tracker::entry::EntryDetails };
+    use super::*;
+
+    fn acme() -> Client {
+        Client {
+            name: "Acme".to_owned(),
+            currency: Some("EUR".to_owned()),
+            default_rate: Some(100.0),
+            address: Some("1 Main St".to_owned()),
+            projects: vec!["acme".to_owned()],
+            ..Client::default()
+        }
+    }
+
+    fn entry(id: u64, start: u64, minutes: u64, project: &str) -> Entry {
+        Entry {
+            id,
+            start,
+            stop: Some(start + minutes * 60),
+            details: EntryDetails { project: Some(project.to_owned()), ..EntryDetails::default() },
+            ..Entry::default()
+        }
+    }
+
+    #[test]
+    fn periods_cover_months_days_and_ranges() {
+        let date = |day| NaiveDate::from_ymd_opt(2026, 2, day).unwrap();
+        let february = Period::parse("2026-02").unwrap();
+        assert_eq!(february, Period { first: date(1), last: date(28) });
+        assert_eq!(february.to_string(), "2026-02");
+        assert_eq!(Period::parse("2026-02-14").unwrap().to_string(), "2026-02-14");
+        assert_eq!(Period::previous_month(NaiveDate::from_ymd_opt(2026, 3, 31).unwrap()), february);
+        assert_eq!(Period::parse("2026-02-01..2026-02-14").unwrap().to_string(), "2026-02-01..2026-02-14");
+        for invalid in ["2026-13", "2026-02-14..2026-02-01", "february", ""] {
+            assert!(Period::parse(invalid).is_err(), "expected {invalid:?} to fail");
+        }
+    }
+
+    #[test]
+    fn numbers_count_up_per_year() {
+        let record = |number: &str| InvoiceRecord { number: number.to_owned(), ..InvoiceRecord::default() };
+        let records = [record("2025-041"), record("2026-002"), record("custom")];
+        assert_eq!(next_number(&records, 2026), "2026-003");
+        assert_eq!(next_number(&records, 2027), "2027-001");
+    }
+
+    #[test]
+    fn only_unbilled_entries_of_the_client_are_invoiced() {
+        let clients = [acme()];
+        let mut rates = Rates::default();
+        rates.projects.insert("acme/api".to_owned(), 120.0);
+        let billing = Billing {
+            clients: &clients,
+            rates: &rates,
+            rounding: Rounding::parse("15m up").unwrap(),
+            day_start: NaiveTime::MIN,
+        };
+        let noon = 1_789_387_200;
+        let mut invoiced = entry(4, noon, 60, "acme/website");
+        invoiced.invoice = Some("2026-001".to_owned());
+        let mut running = entry(6, noon, 60, "acme/website");
+        running.stop = None;
+        let entries = [
+            entry(1, noon, 50, "acme/website"),
+            entry(2, noon, 20, "acme/api"),
+            entry(3, noon, 10, "acme/website"),
+            invoiced,
+            entry(5, noon, 60, "globex"),
+            running,
+            entry(7, noon + 40 * 86_400, 60, "acme/website"),
+        ];
+        let period = Period::parse("2026-09").unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        let mut invoice = build("2026-002".to_owned(), &acme(), &entries, &billing, period, date).unwrap();
+        assert_eq!(invoice.entries, vec![1, 2, 3]);
+        let items: Vec<(&str, i64, i64)> = invoice.items
+            .iter()
+            .map(|item| (item.description.as_str(), item.time.num_minutes(), item.amount))
+            .collect();
+        assert_eq!(items, vec![("acme/api", 30, 6_000), ("acme/website", 75, 12_500)]);
+        invoice.tax_rate = Some(19.0);
+        assert_eq!((invoice.subtotal(), invoice.tax(), invoice.total()), (18_500, 3_515, 22_015));
+
+        let none = build("2026-002".to_owned(), &acme(), &entries, &billing, Period::parse("2026-08").unwrap(), date);
+        assert!(none.is_err());
+    }
+
+    #[test]
+    fn templates_repeat_items_and_skip_empty_sections() {
+        let invoice = Invoice {
+            number: "2026-001".to_owned(),
+            client: Client { address: None, ..acme() },
+            period: Period::parse("2026-09").unwrap(),
+            date: NaiveDate::from_ymd_opt(2026, 10, 1).unwrap(),
+            currency: Some("EUR".to_owned()),
+            items: vec![LineItem {
+                description: "<api>".to_owned(),
+                raw: Duration::minutes(80),
+                time: Duration::minutes(90),
+                hourly: 100.0,
+                amount: 15_000,
+            }],
+            tax_rate: None,
+            entries: vec![1],
+        };
+        let template = "{{number}}:{{#address}} at {{address}}{{/address}}{{#items}} {{description}} {{hours}}h {{amount}} of {{total}}{{/items}}";
+        assert_eq!(render(template, &invoice, InvoiceFormat::Text).unwrap(), "2026-001: <api> 1.50h €150.00 of €150.00");
+        assert_eq!(render("{{#items}}{{description}}{{/items}}", &invoice, InvoiceFormat::Html).unwrap(), "&lt;api&gt;");
+        assert!(render("{{unknown}}", &invoice, InvoiceFormat::Text).is_err());
+        assert!(render("{{#items}}", &invoice, InvoiceFormat::Text).is_err());
+        let unclosed = render("{{number", &invoice, InvoiceFormat::Text).unwrap_err();
+        assert!(matches!(unclosed.current_context(), InvoiceError::UnclosedPlaceholder(_)));
+    }
+}
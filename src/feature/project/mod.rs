@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use chrono::Duration;
+use error_stack::Report;
+use thiserror::Error;
+
+/// Separator between the levels of a project path such as `acme/website/frontend`.
+pub const SEPARATOR: char = '/';
+
+#[derive(Error, Debug)]
+pub enum ProjectError {
+    #[error("invalid project path: {0:?}")] InvalidPath(String),
+
+    #[error("unknown project: {0}")] UnknownProject(String),
+
+    #[error("cannot move {0} into itself")] MoveIntoItself(String),
+
+    #[error("cannot move a rate onto {0}, which already has one")] RateExists(String),
+
+    #[error("cannot move a budget onto {0}, which already has one")] BudgetExists(String),
+}
+
+/// Trims every level of `path` and rejects empty levels.
+pub fn normalize(path: &str) -> Result<String, Report<ProjectError>> {
+    let segments: Vec<&str> = path.trim().split(SEPARATOR).map(str::trim).collect();
+    if segments.iter().any(|segment| segment.is_empty()) {
+        return Err(Report::new(ProjectError::InvalidPath(path.to_owned())));
+    }
+    Ok(segments.join(&SEPARATOR.to_string()))
+}
+
+/// Whether `path` is `ancestor` or lies below it.
+pub fn is_within(path: &str, ancestor: &str) -> bool {
+    path.strip_prefix(ancestor).is_some_and(|rest| rest.is_empty() || rest.starts_with(SEPARATOR))
+}
+
+pub fn parent(path: &str) -> Option<&str> {
+    path.rsplit_once(SEPARATOR).map(|(parent, _)| parent)
+}
+
+pub fn name(path: &str) -> &str {
+    path.rsplit_once(SEPARATOR).map_or(path, |(_, name)| name)
+}
+
+pub fn depth(path: &str) -> usize {
+    path.matches(SEPARATOR).count()
+}
+
+/// `path` with the subtree `from` moved to `to`, or `None` when it lies outside of `from`.
+pub fn moved(path: &str, from: &str, to: &str) -> Option<String> {
+    is_within(path, from).then(|| format!("{to}{}", &path[from.len()..]))
+}
+
+/// How far the project tree of a report is expanded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Expansion {
+    /// Number of levels shown, all when `None`
+    pub depth: Option<usize>,
+    /// Projects whose subprojects are folded into them
+    pub collapsed: Vec<String>,
+}
+
+impl Expansion {
+    /// The visible project that time spent on `path` is counted under.
+    pub fn visible<'a>(&self, path: &'a str) -> &'a str {
+        let mut visible = path;
+        if let Some(depth) = self.depth {
+            let end = path
+                .match_indices(SEPARATOR)
+                .nth(depth.saturating_sub(1))
+                .map_or(path.len(), |(index, _)| index);
+            visible = &path[..end];
+        }
+        for collapsed in &self.collapsed {
+            if is_within(visible, collapsed) {
+                visible = &visible[..collapsed.len()];
+            }
+        }
+        visible
+    }
+}
+
+/// A project in a report tree with the time of the project and all its subprojects.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeRow {
+    pub path: String,
+    pub depth: usize,
+    pub total: Duration,
+}
+
+/// Rolls the time per project up through the parents. Rows are ordered depth first with
+/// siblings sorted by time, longest first.
+pub fn tree<I>(times: I, expansion: &Expansion) -> Vec<TreeRow>
+    where I: IntoIterator<Item = (String, Duration)>
+{
+    let mut totals: HashMap<String, Duration> = HashMap::new();
+    for (path, time) in times {
+        let mut current = Some(expansion.visible(&path));
+        while let Some(path) = current {
+            *totals.entry(path.to_owned()).or_insert_with(Duration::zero) += time;
+            current = parent(path);
+        }
+    }
+
+    let mut children: HashMap<Option<&str>, Vec<(&str, Duration)>> = HashMap::new();
+    for (path, total) in &totals {
+        children.entry(parent(path)).or_default().push((path, *total));
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by(|(a_path, a_time), (b_path, b_time)| {
+            b_time.cmp(a_time).then_with(|| a_path.cmp(b_path))
+        });
+    }
+
+    let mut rows = Vec::new();
+    let mut pending: Vec<&str> = children
+        .get(&None)
+        .map(|roots| roots.iter().rev().map(|(path, _)| *path).collect())
+        .unwrap_or_default();
+    while let Some(path) = pending.pop() {
+        rows.push(TreeRow { path: path.to_owned(), depth: depth(path), total: totals[path] });
+        if let Some(siblings) = children.get(&Some(path)) {
+            pending.extend(siblings.iter().rev().map(|(path, _)| *path));
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(times: &[(&str, i64)]) -> Vec<(String, Duration)> {
+        times
+            .iter()
+            .map(|(path, minutes)| (path.to_string(), Duration::minutes(*minutes)))
+            .collect()
+    }
+
+    fn rows(rows: Vec<TreeRow>) -> Vec<(String, i64)> {
+        rows.into_iter()
+            .map(|row| (row.path, row.total.num_minutes()))
+            .collect()
+    }
+
+    #[test]
+    fn paths_are_normalized_and_moved() {
+        assert_eq!(normalize(" acme / website ").unwrap(), "acme/website");
+        assert!(normalize("acme//website").is_err());
+        assert!(normalize("").is_err());
+        assert!(is_within("acme/website", "acme"));
+        assert!(!is_within("acmecorp", "acme"));
+        assert_eq!(
+            moved("acme/website/frontend", "acme/website", "globex/web").as_deref(),
+            Some("globex/web/frontend")
+        );
+        assert_eq!(moved("acmecorp", "acme", "globex"), None);
+    }
+
+    #[test]
+    fn subtotals_roll_up_through_parents() {
+        let times = times(&[
+            ("acme/website/frontend", 40),
+            ("acme/website", 20),
+            ("acme/api", 30),
+            ("globex", 100),
+        ]);
+        assert_eq!(
+            rows(tree(times.clone(), &Expansion::default())),
+            vec![
+                ("globex".to_owned(), 100),
+                ("acme".to_owned(), 90),
+                ("acme/website".to_owned(), 60),
+                ("acme/website/frontend".to_owned(), 40),
+                ("acme/api".to_owned(), 30)
+            ]
+        );
+        let collapsed = Expansion { depth: None, collapsed: vec!["acme/website".to_owned()] };
+        assert_eq!(
+            rows(tree(times.clone(), &collapsed)),
+            vec![
+                ("globex".to_owned(), 100),
+                ("acme".to_owned(), 90),
+                ("acme/website".to_owned(), 60),
+                ("acme/api".to_owned(), 30)
+            ]
+        );
+        let top_level = Expansion { depth: Some(1), ..Expansion::default() };
+        assert_eq!(rows(tree(times, &top_level)), vec![("globex".to_owned(), 100), ("acme".to_owned(), 90)]);
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::format::{ Item, StrftimeItems };
+use error_stack::Report;
+use thiserror::Error;
+
+use super::{ duration::{ self, DurationFormat }, tracker::entry::{ local_time, Entry } };
+
+#[derive(Error, Debug)]
+pub enum PromptError {
+    #[error("unknown placeholder: {0}")] UnknownPlaceholder(String),
+
+    #[error("unknown format for {0}: {1}")] UnknownSpec(String, String),
+
+    #[error("unclosed placeholder starting at byte {0}")] UnclosedPlaceholder(usize),
+
+    #[error("unmatched '}}' at byte {0}")] UnmatchedBrace(usize),
+}
+
+/// Renders a prompt template for the running `entry`.
+///
+/// Placeholders are written as `{name}` or `{name:spec}`, and `{{`/`}}` produce literal braces:
+/// - `{project}`, `{note}`
+/// - `{tags}` joined by `,`, or by the given separator as in `{tags: }`
+/// - `{start}` as `%H:%M`, or any strftime format as in `{start:%a %H:%M}`
+/// - `{elapsed}` as `HH:MM:SS`, or `{elapsed:short}` and `{elapsed:decimal}`
+///
+/// Surrounding whitespace is trimmed so that empty placeholders do not leave gaps at the edges.
+pub fn render(template: &str, entry: &Entry, now: u64) -> Result<String, Report<PromptError>> {
+    let mut output = String::new();
+    let mut chars = template.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '{' if chars.next_if(|(_, next)| *next == '{').is_some() => output.push('{'),
+            '}' if chars.next_if(|(_, next)| *next == '}').is_some() => output.push('}'),
+            '}' => {
+                return Err(Report::new(PromptError::UnmatchedBrace(index)));
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                let mut closed = false;
+                for (_, c) in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    placeholder.push(c);
+                }
+                if !closed {
+                    return Err(Report::new(PromptError::UnclosedPlaceholder(index)));
+                }
+                output.push_str(&render_placeholder(&placeholder, entry, now)?);
+            }
+            c => output.push(c),
+        }
+    }
+
+    Ok(output.trim().to_owned())
+}
+
+fn render_placeholder(
+    placeholder: &str,
+    entry: &Entry,
+    now: u64
+) -> Result<String, Report<PromptError>> {
+    let (name, spec) = match placeholder.split_once(':') {
+        Some((name, spec)) => (name.trim(), Some(spec)),
+        None => (placeholder.trim(), None),
+    };
+    let unknown_spec = |spec: &str| {
+        Report::new(PromptError::UnknownSpec(name.to_owned(), spec.to_owned()))
+    };
+    let details = &entry.details;
+
+    let rendered = match (name, spec) {
+        ("project", None) => details.project.clone().unwrap_or_default(),
+        ("note", None) => details.note.clone().unwrap_or_default(),
+        ("tags", separator) => details.tags.join(separator.unwrap_or(",")),
+        ("start", format) => {
+            let format = format.unwrap_or("%H:%M");
+            // Formatting with an invalid specifier panics, so they are rejected up front
+            let items: Vec<Item> = StrftimeItems::new(format).collect();
+            if items.contains(&Item::Error) {
+                return Err(unknown_spec(format));
+            }
+            local_time(entry.start).format_with_items(items.into_iter()).to_string()
+        }
+        ("elapsed", spec) => {
+            let format = match spec {
+                None | Some("clock") => DurationFormat::Clock,
+                Some("short" | "compact") => DurationFormat::Compact,
+                Some("decimal") => DurationFormat::Decimal,
+                Some(spec) => {
+                    return Err(unknown_spec(spec));
+                }
+            };
+            duration::format(entry.elapsed(now), format)
+        }
+        ("project" | "note", Some(spec)) => {
+            return Err(unknown_spec(spec));
+        }
+        _ => {
+            return Err(Report::new(PromptError::UnknownPlaceholder(name.to_owned())));
+        }
+    };
+
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::feature::tracker::entry::EntryDetails;
+    use super::*;
+
+    fn entry() -> Entry {
+        Entry::new(1_000, true, EntryDetails {
+            project: Some("website".to_owned()),
+            tags: vec!["frontend".to_owned(), "css".to_owned()],
+            ..EntryDetails::default()
+        })
+    }
+
+    #[test]
+    fn renders_placeholders_and_literals() {
+        let now = 1_000 + 90 * 60;
+        assert_eq!(render("{project} {elapsed:short}", &entry(), now).unwrap(), "website 1h 30m");
+        assert_eq!(render("[{elapsed}]", &entry(), now).unwrap(), "[01:30:00]");
+        assert_eq!(render("{elapsed:decimal}h", &entry(), now).unwrap(), "1.50h");
+        assert_eq!(render("{tags} / {tags: }", &entry(), now).unwrap(), "frontend,css / frontend css");
+        assert_eq!(render("{{{project}}}", &entry(), now).unwrap(), "{website}");
+    }
+
+    #[test]
+    fn missing_values_render_empty_and_are_trimmed() {
+        assert_eq!(render("{note} {project}", &entry(), 1_000).unwrap(), "website");
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        for template in ["{unknown}", "{elapsed:weeks}", "{project", "project}", "{note:upper}"] {
+            assert!(render(template, &entry(), 1_000).is_err(), "expected {template:?} to fail");
+        }
+        let error = render("{start:%Q}", &entry(), 1_000).unwrap_err();
+        assert!(matches!(error.current_context(), PromptError::UnknownSpec(name, spec) if name == "start" && spec == "%Q"));
+    }
+}
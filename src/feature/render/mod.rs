@@ -0,0 +1,324 @@
+use chrono::Duration;
+use clap::ValueEnum;
+
+use super::{ export::DataFormat, string_ext::StringExt };
+
+/// Output formats of `track report`, either records for other programs or a rendered table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    Ndjson,
+    /// A heading and a GitHub flavored Markdown table
+    Markdown,
+    /// A standalone page with inline styles
+    Html,
+}
+
+impl ReportFormat {
+    /// The format of the records, or `None` when the report is rendered as a table.
+    pub fn data_format(self) -> Option<DataFormat> {
+        match self {
+            Self::Json => Some(DataFormat::Json),
+            Self::Csv => Some(DataFormat::Csv),
+            Self::Ndjson => Some(DataFormat::Ndjson),
+            Self::Markdown | Self::Html => None,
+        }
+    }
+
+    /// The renderer of the table, or `None` when records are printed instead.
+    pub fn renderer(self, charts: bool) -> Option<Box<dyn Renderer>> {
+        match self {
+            Self::Json | Self::Csv | Self::Ndjson => None,
+            Self::Markdown => Some(Box::new(Markdown)),
+            Self::Html => Some(Box::new(Html { charts })),
+        }
+    }
+}
+
+/// A report ready to be rendered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Table {
+    pub title: String,
+    /// The reported time span, e.g. "2026-10-12 00:00 to 2026-10-19 00:00"
+    pub period: String,
+    /// Column names, the first one naming the row keys
+    pub columns: Vec<String>,
+    /// Whether the terminal shows the column names, which the other formats always show
+    pub header: bool,
+    pub rows: Vec<TableRow>,
+    /// The totals, starting with the "total" label
+    pub total: TableRow,
+    /// Whether the terminal shows only the total time on a line of its own, like the plain
+    /// report does, instead of a total row
+    pub separate_total: bool,
+}
+
+/// A row of a [`Table`] with a cell per column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableRow {
+    /// Nesting below the top level rows, shown as indentation of the first cell
+    pub depth: usize,
+    pub cells: Vec<String>,
+    /// Time of the row, the length of its bar in charts
+    pub time: Duration,
+}
+
+/// Turns a [`Table`] into text in some format.
+pub trait Renderer {
+    fn render(&self, table: &Table) -> String;
+}
+
+/// Aligned columns for the terminal. Keys are padded on the right and values on the left,
+/// except for the last column. When there is a single value column, the total is printed
+/// on its own on the last line.
+pub struct Terminal;
+
+impl Renderer for Terminal {
+    fn render(&self, table: &Table) -> String {
+        let mut lines: Vec<Vec<String>> = table.rows
+            .iter()
+            .map(|row| {
+                let mut cells = row.cells.clone();
+                if let Some(key) = cells.first_mut() {
+                    *key = format!("{}{key}", "  ".repeat(row.depth));
+                }
+                cells
+            })
+            .collect();
+        if table.header {
+            let names = table.columns.iter().skip(1).cloned();
+            lines.insert(0, std::iter::once(String::new()).chain(names).collect());
+        }
+        if !table.separate_total {
+            lines.push(table.total.cells.clone());
+        }
+
+        let columns = table.columns.len();
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                lines
+                    .iter()
+                    .map(|line| line.get(column).map_or(0, |cell| cell.width()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let mut output = String::new();
+        for line in lines {
+            let cells: Vec<String> = line
+                .into_iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    match column {
+                        0 => cell.pad_end_to_length(widths[0], None),
+                        column if column == columns - 1 => cell,
+                        column => cell.pad_start_to_length(widths[column], None),
+                    }
+                })
+                .collect();
+            output.push_str(cells.join(" ").trim_end());
+            output.push('\n');
+        }
+        if table.separate_total {
+            output.push_str(table.total.cells.last().map_or("", String::as_str));
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// A heading, the period and a GitHub flavored Markdown table with the values aligned right.
+pub struct Markdown;
+
+impl Renderer for Markdown {
+    fn render(&self, table: &Table) -> String {
+        let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+        let mut output = format!("# {}\n\n{}\n\n", table.title, table.period);
+        output.push_str(&line(table.columns.iter().map(|column| escape_markdown(column)).collect()));
+        output.push_str(
+            &line(
+                (0..table.columns.len())
+                    .map(|column| if column == 0 { ":--" } else { "--:" }.to_owned())
+                    .collect()
+            )
+        );
+        for row in &table.rows {
+            let cells = row.cells
+                .iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    let indent = if column == 0 { "&nbsp;".repeat(2 * row.depth) } else { String::new() };
+                    format!("{indent}{}", escape_markdown(cell))
+                })
+                .collect();
+            output.push_str(&line(cells));
+        }
+        let total = table.total.cells
+            .iter()
+            .map(|cell| if cell.is_empty() { String::new() } else { format!("**{}**", escape_markdown(cell)) })
+            .collect();
+        output.push_str(&line(total));
+        output
+    }
+}
+
+/// A standalone HTML page with inline styles, so it can be mailed or archived as a single
+/// file. With `charts`, each row gets a bar showing its share of the total.
+pub struct Html {
+    pub charts: bool,
+}
+
+const STYLE: &str = "body { font-family: system-ui, sans-serif; margin: 2rem; color: #222; }
+h1 { font-size: 1.4rem; margin-bottom: 0.25rem; }
+p { color: #666; margin-top: 0; }
+table { border-collapse: collapse; }
+th, td { padding: 0.3rem 0.75rem; border-bottom: 1px solid #ddd; text-align: left; white-space: nowrap; }
+.value { text-align: right; font-variant-numeric: tabular-nums; }
+tfoot td { font-weight: bold; border-top: 2px solid #222; border-bottom: none; }
+.chart { width: 12rem; }
+.bar { height: 0.8rem; background: #4c78a8; border-radius: 2px; }";
+
+impl Renderer for Html {
+    fn render(&self, table: &Table) -> String {
+        let class = |column: usize| if column == 0 { "" } else { " class=\"value\"" };
+        let cells = |row: &TableRow| -> String {
+            row.cells
+                .iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    let indent = if column == 0 && row.depth > 0 {
+                        format!(" style=\"padding-left: {}rem\"", 0.75 + 1.25 * (row.depth as f64))
+                    } else {
+                        String::new()
+                    };
+                    format!("<td{}{indent}>{}</td>", class(column), escape_html(cell))
+                })
+                .collect()
+        };
+
+        let mut output = String::new();
+        output.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        output.push_str(&format!("<title>{}</title>\n<style>\n{STYLE}\n</style>\n", escape_html(&table.title)));
+        output.push_str("</head>\n<body>\n");
+        output.push_str(&format!("<h1>{}</h1>\n<p>{}</p>\n", escape_html(&table.title), escape_html(&table.period)));
+        output.push_str("<table>\n<thead>\n<tr>");
+        for (column, name) in table.columns.iter().enumerate() {
+            output.push_str(&format!("<th{}>{}</th>", class(column), escape_html(name)));
+        }
+        if self.charts {
+            output.push_str("<th class=\"chart\"></th>");
+        }
+        output.push_str("</tr>\n</thead>\n<tbody>\n");
+        for row in &table.rows {
+            output.push_str(&format!("<tr>{}", cells(row)));
+            if self.charts {
+                let share = if table.total.time > Duration::zero() {
+                    (row.time.num_seconds() as f64) / (table.total.time.num_seconds() as f64)
+                } else {
+                    0.0
+                };
+                let percent = (share * 100.0).clamp(0.0, 100.0);
+                output.push_str(
+                    &format!(
+                        "<td class=\"chart\"><div class=\"bar\" style=\"width: {percent:.1}%\" title=\"{percent:.0}%\"></div></td>"
+                    )
+                );
+            }
+            output.push_str("</tr>\n");
+        }
+        output.push_str(&format!("</tbody>\n<tfoot>\n<tr>{}", cells(&table.total)));
+        if self.charts {
+            output.push_str("<td></td>");
+        }
+        output.push_str("</tr>\n</tfoot>\n</table>\n</body>\n</html>\n");
+        output
+    }
+}
+
+/// The command line name of `value`, e.g. to name a column after a breakdown.
+pub fn value_name<T: ValueEnum>(value: T) -> String {
+    value
+        .to_possible_value()
+        .map(|possible| possible.get_name().to_owned())
+        .unwrap_or_default()
+}
+
+fn escape_markdown(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+fn escape_html(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use super::*;
+
+    fn row(depth: usize, cells: &[&str], hours: i64) -> TableRow {
+        TableRow {
+            depth,
+            cells: cells
+                .iter()
+                .map(|cell| (*cell).to_owned())
+                .collect(),
+            time: Duration::hours(hours),
+        }
+    }
+
+    fn table() -> Table {
+        Table {
+            title: "Time by client, project".to_owned(),
+            period: "2026-10-12 00:00 to 2026-10-19 00:00".to_owned(),
+            columns: vec!["client / project".to_owned(), "time".to_owned()],
+            header: false,
+            rows: vec![
+                row(0, &["Acme & Sons", "6h 0m"], 6),
+                row(1, &["acme/web|app", "4h 0m"], 4),
+                row(1, &["acme/api", "2h 0m"], 2),
+                row(0, &["(unknown)", "2h 0m"], 2)
+            ],
+            total: row(0, &["total", "8h 0m"], 8),
+            separate_total: true,
+        }
+    }
+
+    #[test]
+    fn terminal_tables_are_aligned() {
+        assert_eq!(
+            Terminal.render(&table()),
+            "Acme & Sons    6h 0m\n  acme/web|app 4h 0m\n  acme/api     2h 0m\n(unknown)      2h 0m\n8h 0m\n"
+        );
+        let billing = Table {
+            columns: ["client", "raw", "rounded", "amount"].map(str::to_owned).to_vec(),
+            header: true,
+            rows: vec![row(0, &["Acme", "1h 50m", "2h 0m", "€200.00"], 2)],
+            total: row(0, &["total", "1h 50m", "2h 0m", "€200.00"], 2),
+            separate_total: false,
+            ..table()
+        };
+        assert_eq!(
+            Terminal.render(&billing),
+            "         raw rounded amount\nAcme  1h 50m   2h 0m €200.00\ntotal 1h 50m   2h 0m €200.00\n"
+        );
+        let with_total_row = Table { separate_total: false, ..table() };
+        assert!(Terminal.render(&with_total_row).ends_with("\n(unknown)      2h 0m\ntotal          8h 0m\n"));
+    }
+
+    #[test]
+    fn documents_match_their_snapshots() {
+        assert_eq!(Markdown.render(&table()), include_str!("snapshots/report.md"));
+        assert_eq!(Html { charts: true }.render(&table()), include_str!("snapshots/report.html"));
+        assert!(!Html { charts: false }.render(&table()).contains("class=\"bar\""));
+    }
+
+    #[test]
+    fn formats_pick_records_or_renderers() {
+        assert_eq!(ReportFormat::Csv.data_format(), Some(DataFormat::Csv));
+        assert!(ReportFormat::Csv.renderer(false).is_none());
+        assert_eq!(ReportFormat::Markdown.data_format(), None);
+        assert!(ReportFormat::Html.renderer(true).is_some());
+    }
+}
@@ -0,0 +1,302 @@
+use std::{ collections::HashMap, path::Path };
+use chrono::Duration;
+use clap::ValueEnum;
+
+use super::{
+    aggregate::GroupBy,
+    billing::{ Amounts, Billed, Billing },
+    client::{ self, Client },
+    duration::DurationFormat,
+    git::CommitRecord,
+    heartbeat::Span,
+    project::{ self, Expansion },
+    render::ReportFormat,
+    tracker::entry::Entry,
+};
+
+/// Label for heartbeats whose breakdown key is unknown.
+pub const UNKNOWN: &str = "(unknown)";
+
+/// Label for the rows cut off by a top-N limit.
+pub const OTHER: &str = "(other)";
+
+/// How time is broken down in reports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Breakdown {
+    /// Heartbeat time by language, detected from the file extension
+    Language,
+    /// Heartbeat time by the directory containing the file
+    Directory,
+    /// Heartbeat time by file
+    File,
+    /// Entry time by the git branch the entry was started on
+    Branch,
+    /// Time spent per commit, as recorded by the git post-commit hook
+    Commit,
+    /// Entry time by project, with subtotals rolled up through parent projects
+    Project,
+    /// Entry time by the client of the project
+    Client,
+}
+
+impl Breakdown {
+    pub fn uses_heartbeats(self) -> bool {
+        matches!(self, Self::Language | Self::Directory | Self::File)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ReportOptions {
+    pub duration_format: DurationFormat,
+    pub breakdown: Option<Breakdown>,
+    pub top: Option<usize>,
+    /// Only count entries and commits on this git branch
+    pub branch: Option<String>,
+    /// Levels of the project tree to show
+    pub expansion: Expansion,
+    /// Add billable amounts to the rows
+    pub billing: bool,
+    /// Nested levels to group entry time by, flat when empty
+    pub group_by: Vec<GroupBy>,
+    /// Start of the reported time, 24 hours before `to` by default
+    pub from: Option<u64>,
+    /// End of the reported time, now by default
+    pub to: Option<u64>,
+    /// Print records in a machine-readable format or render a document instead of text
+    pub format: Option<ReportFormat>,
+    /// Draw a bar for each row in HTML reports
+    pub charts: bool,
+}
+
+/// A report row with the billable amount of its time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BillingRow {
+    pub key: String,
+    pub time: Duration,
+    /// Time after rounding the billable part
+    pub rounded: Duration,
+    pub amounts: Amounts,
+}
+
+/// Sums the time of `spans` inside `[from, to)` per breakdown key, longest first.
+pub fn breakdown(spans: &[Span], by: Breakdown, from: u64, to: u64) -> Vec<(String, Duration)> {
+    totals(
+        spans.iter().map(|span| {
+            let file = span.heartbeat.file.as_deref();
+            let key = match by {
+                Breakdown::Language => span.heartbeat.language().map(str::to_owned),
+                Breakdown::File => file.map(str::to_owned),
+                Breakdown::Directory =>
+                    file.map(|file| {
+                        match Path::new(file).parent() {
+                            Some(parent) if !parent.as_os_str().is_empty() => parent.display().to_string(),
+                            _ => ".".to_owned(),
+                        }
+                    }),
+                // Heartbeats only carry a flat project and no git details.
+                Breakdown::Branch | Breakdown::Commit | Breakdown::Project | Breakdown::Client => None,
+            };
+            (key, span.overlap(from, to))
+        })
+    )
+}
+
+/// Sums the time of `entries` inside `[from, to)` per git branch, longest first.
+pub fn branches(entries: &[Entry], from: u64, to: u64, now: u64) -> Vec<(String, Duration)> {
+    totals(
+        entries
+            .iter()
+            .map(|entry| (entry.branch().map(str::to_owned), entry.overlap(from, to, now)))
+    )
+}
+
+/// Sums the time and billable amounts of `entries` inside `[from, to)` per client, project
+/// or branch, longest first. Projects are grouped by their full path.
+pub fn billing(
+    entries: &[Entry],
+    by: Breakdown,
+    billing: &Billing,
+    from: u64,
+    to: u64,
+    now: u64
+) -> Vec<BillingRow> {
+    let items = entries.iter().map(|entry| {
+        let project = entry.details.project.as_deref();
+        let key = match by {
+            Breakdown::Client =>
+                project
+                    .and_then(|project| client::client_of(billing.clients, project))
+                    .map(|client| client.name.clone()),
+            Breakdown::Project => project.map(str::to_owned),
+            Breakdown::Branch => entry.branch().map(str::to_owned),
+            _ => None,
+        }.unwrap_or_else(|| UNKNOWN.to_owned());
+        (key, entry, entry.overlap(from, to, now))
+    });
+    let mut rows: Vec<BillingRow> = billing
+        .bill(items)
+        .into_iter()
+        .map(|(key, Billed { time, rounded, amounts })| BillingRow { key, time, rounded, amounts })
+        .collect();
+    rows.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| a.key.cmp(&b.key)));
+    rows
+}
+
+/// The project tree of `entries` inside `[from, to)`, parents before their children and each
+/// keyed by its full path. Time without a project is listed last as [`UNKNOWN`].
+pub fn project_paths(
+    entries: &[Entry],
+    from: u64,
+    to: u64,
+    now: u64,
+    expansion: &Expansion
+) -> Vec<(String, Duration)> {
+    let times: Vec<(Option<&str>, Duration)> = entries
+        .iter()
+        .map(|entry| (entry.details.project.as_deref(), entry.overlap(from, to, now)))
+        .filter(|(_, time)| *time > Duration::zero())
+        .collect();
+    let mut rows: Vec<(String, Duration)> = project
+        ::tree(
+            times.iter().filter_map(|(path, time)| path.map(|path| (path.to_owned(), *time))),
+            expansion
+        )
+        .into_iter()
+        .map(|row| (row.path, row.total))
+        .collect();
+    let unknown: Duration = times
+        .iter()
+        .filter(|(path, _)| path.is_none())
+        .map(|(_, time)| *time)
+        .sum();
+    if unknown > Duration::zero() {
+        rows.push((UNKNOWN.to_owned(), unknown));
+    }
+    rows
+}
+
+/// Sums the time of `entries` inside `[from, to)` per client of their project, longest first.
+pub fn clients(
+    entries: &[Entry],
+    clients: &[Client],
+    from: u64,
+    to: u64,
+    now: u64
+) -> Vec<(String, Duration)> {
+    totals(
+        entries.iter().map(|entry| {
+            let client = entry.details.project
+                .as_deref()
+                .and_then(|project| client::client_of(clients, project));
+            (client.map(|client| client.name.clone()), entry.overlap(from, to, now))
+        })
+    )
+}
+
+/// The time spent per commit recorded inside `[from, to]`, in the order of the commits.
+pub fn commits(records: &[CommitRecord], from: u64, to: u64) -> Vec<(String, Duration)> {
+    records
+        .iter()
+        .filter(|record| (from..=to).contains(&record.time))
+        .map(|record| {
+            let key = match &record.branch {
+                Some(branch) => format!("{} {branch}", record.short_commit()),
+                None => record.short_commit().to_owned(),
+            };
+            (key, Duration::seconds(record.spent))
+        })
+        .collect()
+}
+
+/// Sums durations per key, longest first, counting missing keys as [`UNKNOWN`].
+fn totals<I>(items: I) -> Vec<(String, Duration)> where I: IntoIterator<Item = (Option<String>, Duration)> {
+    let mut totals: HashMap<String, Duration> = HashMap::new();
+    for (key, time) in items {
+        if time <= Duration::zero() {
+            continue;
+        }
+        *totals.entry(key.unwrap_or_else(|| UNKNOWN.to_owned())).or_insert_with(Duration::zero) += time;
+    }
+
+    let mut rows: Vec<(String, Duration)> = totals.into_iter().collect();
+    rows.sort_by(|(a_key, a_time), (b_key, b_time)| b_time.cmp(a_time).then_with(|| a_key.cmp(b_key)));
+    rows
+}
+
+/// Keeps the `n` longest rows, longest first, and sums the rest into an [`OTHER`] row.
+pub fn top(mut rows: Vec<(String, Duration)>, n: usize) -> Vec<(String, Duration)> {
+    rows.sort_by(|(a_key, a_time), (b_key, b_time)| b_time.cmp(a_time).then_with(|| a_key.cmp(b_key)));
+    if rows.len() <= n {
+        return rows;
+    }
+    let other: Duration = rows
+        .drain(n..)
+        .map(|(_, time)| time)
+        .sum();
+    rows.push((OTHER.to_owned(), other));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::feature::heartbeat::Heartbeat;
+    use super::*;
+
+    fn beat(file: Option<&str>) -> Heartbeat {
+        Heartbeat { file: file.map(str::to_owned), ..Heartbeat::default() }
+    }
+
+    #[test]
+    fn spans_are_grouped_and_clipped_to_the_window() {
+        let heartbeats = [
+            beat(Some("src/main.rs")),
+            beat(Some("src/lib.rs")),
+            beat(Some("README.md")),
+            beat(None),
+        ];
+        let spans = [
+            Span { heartbeat: &heartbeats[0], start: 0, stop: 100 },
+            Span { heartbeat: &heartbeats[1], start: 100, stop: 150 },
+            Span { heartbeat: &heartbeats[2], start: 150, stop: 300 },
+            Span { heartbeat: &heartbeats[3], start: 300, stop: 310 },
+        ];
+        let secs = Duration::seconds;
+        assert_eq!(
+            breakdown(&spans, Breakdown::Language, 50, 260),
+            vec![("Markdown".to_owned(), secs(110)), ("Rust".to_owned(), secs(100))]
+        );
+        assert_eq!(
+            breakdown(&spans, Breakdown::Directory, 0, 1000),
+            vec![(".".to_owned(), secs(150)), ("src".to_owned(), secs(150)), (UNKNOWN.to_owned(), secs(10))]
+        );
+        assert_eq!(
+            top(breakdown(&spans, Breakdown::File, 0, 1000), 2),
+            vec![
+                ("README.md".to_owned(), secs(150)),
+                ("src/main.rs".to_owned(), secs(100)),
+                (OTHER.to_owned(), secs(60))
+            ]
+        );
+    }
+
+    #[test]
+    fn the_longest_commits_are_kept() {
+        let record = |commit: &str, time: u64, spent: i64| CommitRecord {
+            commit: commit.to_owned(),
+            repository: "/src/website".to_owned(),
+            branch: None,
+            time,
+            spent,
+        };
+        let records = [record("aaaaaaa", 10, 60), record("bbbbbbb", 20, 600), record("ccccccc", 30, 120)];
+        let secs = Duration::seconds;
+        assert_eq!(
+            top(commits(&records, 0, 100), 2),
+            vec![
+                ("bbbbbbb".to_owned(), secs(600)),
+                ("ccccccc".to_owned(), secs(120)),
+                (OTHER.to_owned(), secs(60))
+            ]
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::fmt;
+use chrono::Duration;
+use error_stack::Report;
+use serde::{ Deserialize, Serialize };
+use thiserror::Error;
+
+use super::duration;
+
+#[derive(Error, Debug)]
+pub enum RoundingError {
+    #[error("invalid rounding rule: {0:?}, expected e.g. \"off\", \"15m up\" or \"6m nearest per-day\"")]
+    InvalidRule(String),
+
+    #[error("rounding increment is longer than a week: {0:?}")] IncrementTooLarge(String),
+}
+
+/// Longest increment a rule may round to, in seconds.
+pub const MAX_INCREMENT: i64 = 7 * 24 * 3600;
+
+/// Which way durations are rounded to the increment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingMode {
+    #[default]
+    Up,
+    /// Half an increment or more rounds up
+    Nearest,
+    Down,
+}
+
+/// What is rounded: every entry on its own, or the total of each day.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RoundingScope {
+    #[default]
+    PerEntry,
+    PerDay,
+}
+
+/// A billing rounding rule such as "15 minute increments, rounded up, per entry".
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rounding {
+    /// Increment in seconds, `0` disables rounding
+    pub increment: i64,
+    pub mode: RoundingMode,
+    pub scope: RoundingScope,
+}
+
+impl Rounding {
+    pub fn is_off(&self) -> bool {
+        self.increment <= 0
+    }
+
+    /// Rounds `time` to a multiple of the increment, leaving it as is when the result could
+    /// not be represented.
+    pub fn round(&self, time: Duration) -> Duration {
+        if self.is_off() {
+            return time;
+        }
+        let seconds = time.num_seconds();
+        let down = seconds - seconds.rem_euclid(self.increment);
+        let rounded = match self.mode {
+            RoundingMode::Down => Some(down),
+            _ if down == seconds => Some(seconds),
+            RoundingMode::Up => down.checked_add(self.increment),
+            RoundingMode::Nearest if (seconds - down) >= self.increment - (seconds - down) =>
+                down.checked_add(self.increment),
+            RoundingMode::Nearest => Some(down),
+        };
+        rounded.and_then(Duration::try_seconds).unwrap_or(time)
+    }
+
+    /// Parses `off` or `<increment> [up|nearest|down] [per-entry|per-day]`, e.g. `15m up per-day`.
+    pub fn parse(input: &str) -> Result<Self, Report<RoundingError>> {
+        let invalid = || Report::new(RoundingError::InvalidRule(input.to_owned()));
+        let mut words = input.split_whitespace();
+        let increment = words.next().ok_or_else(invalid)?;
+        if increment.eq_ignore_ascii_case("off") {
+            return match words.next() {
+                None => Ok(Self::default()),
+                Some(_) => Err(invalid()),
+            };
+        }
+        let increment = duration
+            ::parse(increment)
+            .map_err(|e| e.change_context(RoundingError::InvalidRule(input.to_owned())))?
+            .num_seconds();
+        if increment <= 0 {
+            return Err(invalid());
+        }
+        if increment > MAX_INCREMENT {
+            return Err(Report::new(RoundingError::IncrementTooLarge(input.to_owned())));
+        }
+        let mut rounding = Self { increment, ..Self::default() };
+        for word in words {
+            match word.to_lowercase().as_str() {
+                "up" => {
+                    rounding.mode = RoundingMode::Up;
+                }
+                "nearest" => {
+                    rounding.mode = RoundingMode::Nearest;
+                }
+                "down" => {
+                    rounding.mode = RoundingMode::Down;
+                }
+                "per-entry" | "entry" => {
+                    rounding.scope = RoundingScope::PerEntry;
+                }
+                "per-day" | "day" => {
+                    rounding.scope = RoundingScope::PerDay;
+                }
+                _ => {
+                    return Err(invalid());
+                }
+            }
+        }
+        Ok(rounding)
+    }
+}
+
+impl fmt::Display for Rounding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_off() {
+            return write!(f, "off");
+        }
+        let mode = match self.mode {
+            RoundingMode::Up => "up",
+            RoundingMode::Nearest => "nearest",
+            RoundingMode::Down => "down",
+        };
+        let scope = match self.scope {
+            RoundingScope::PerEntry => "per-entry",
+            RoundingScope::PerDay => "per-day",
+        };
+        let increment = match self.increment {
+            seconds if seconds % 3600 == 0 => format!("{}h", seconds / 3600),
+            seconds if seconds % 60 == 0 => format!("{}m", seconds / 60),
+            seconds => format!("{seconds}s"),
+        };
+        write!(f, "{increment} {mode} {scope}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(increment_minutes: i64, mode: RoundingMode) -> Rounding {
+        Rounding { increment: increment_minutes * 60, mode, ..Rounding::default() }
+    }
+
+    fn rounded(rounding: Rounding, seconds: i64) -> i64 {
+        rounding.round(Duration::seconds(seconds)).num_seconds()
+    }
+
+    #[test]
+    fn rounding_up_only_keeps_exact_multiples() {
+        let up = rule(15, RoundingMode::Up);
+        assert_eq!(rounded(up, 0), 0);
+        assert_eq!(rounded(up, 1), 900);
+        assert_eq!(rounded(up, 899), 900);
+        assert_eq!(rounded(up, 900), 900);
+        assert_eq!(rounded(up, 901), 1800);
+    }
+
+    #[test]
+    fn rounding_to_the_nearest_increment_rounds_halves_up() {
+        let nearest = rule(6, RoundingMode::Nearest);
+        assert_eq!(rounded(nearest, 179), 0);
+        assert_eq!(rounded(nearest, 180), 360);
+        assert_eq!(rounded(nearest, 539), 360);
+        assert_eq!(rounded(nearest, 540), 720);
+        let half_hours = rule(30, RoundingMode::Nearest);
+        assert_eq!(rounded(half_hours, 899), 0);
+        assert_eq!(rounded(half_hours, 900), 1800);
+    }
+
+    #[test]
+    fn rounding_down_drops_partial_increments() {
+        let down = rule(30, RoundingMode::Down);
+        assert_eq!(rounded(down, 1799), 0);
+        assert_eq!(rounded(down, 1800), 1800);
+        assert_eq!(rounded(down, 3599), 1800);
+        assert_eq!(rounded(Rounding::default(), 1234), 1234);
+    }
+
+    #[test]
+    fn rules_round_trip_through_text() {
+        let rounding = Rounding::parse("15m nearest per-day").unwrap();
+        assert_eq!(rounding, Rounding {
+            increment: 900,
+            mode: RoundingMode::Nearest,
+            scope: RoundingScope::PerDay,
+        });
+        assert_eq!(rounding.to_string(), "15m nearest per-day");
+        assert_eq!(Rounding::parse("6m").unwrap().to_string(), "6m up per-entry");
+        assert_eq!(Rounding::parse("1h down").unwrap().to_string(), "1h down per-entry");
+        assert_eq!(Rounding::parse("off").unwrap(), Rounding::default());
+        for invalid in ["", "0m", "15m sideways", "off up", "soon", "8d", "9223372036854775s"] {
+            assert!(Rounding::parse(invalid).is_err(), "expected {invalid:?} to fail");
+        }
+    }
+
+    #[test]
+    fn huge_increments_do_not_overflow() {
+        let huge = Rounding { increment: i64::MAX, ..Rounding::default() };
+        assert_eq!(rounded(huge, 1), 1);
+    }
+}
@@ -0,0 +1,138 @@
+use std::io::{ BufRead, Write };
+use chrono::{ DateTime, Duration, TimeZone };
+use clap::ValueEnum;
+use serde::{ Deserialize, Serialize };
+
+use super::{ duration::{ self, DurationFormat }, time_expr };
+
+/// What to do when a timer has been running for longer than the maximum session length.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CapPolicy {
+    /// Ask when running in a terminal, otherwise only warn
+    #[default]
+    Ask,
+    /// Only warn and keep the timer running
+    Warn,
+    /// Stop the timer at the maximum session length
+    Limit,
+    /// Stop the timer at the last time the tracker was used
+    LastActivity,
+}
+
+/// Where an overdue session should be stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CapChoice {
+    Keep,
+    Limit,
+    LastActivity,
+    At(u64),
+}
+
+impl CapChoice {
+    /// The instant the session should be stopped at, or `None` to keep it running.
+    pub fn stop_at(self, start: u64, max_session: Duration, last_activity: u64) -> Option<u64> {
+        match self {
+            Self::Keep => None,
+            Self::Limit => Some(start + (max_session.num_seconds().max(0) as u64)),
+            Self::LastActivity => Some(last_activity.max(start)),
+            Self::At(at) => Some(at),
+        }
+    }
+}
+
+/// Asks on `output` how to cap an overdue session until `input` gives a valid answer.
+/// Keeps the session running when `input` is exhausted.
+pub fn ask<R, W, Tz>(
+    mut input: R,
+    mut output: W,
+    now: &DateTime<Tz>
+) -> std::io::Result<CapChoice>
+    where R: BufRead, W: Write, Tz: TimeZone
+{
+    loop {
+        write!(
+            output,
+            "cap the entry at the [l]imit, at a given [t]ime, at the last [a]ctivity, or [k]eep it running? "
+        )?;
+        output.flush()?;
+        let Some(answer) = read_answer(&mut input)? else {
+            return Ok(CapChoice::Keep);
+        };
+        match answer.as_str() {
+            "l" | "limit" => {
+                return Ok(CapChoice::Limit);
+            }
+            "a" | "activity" => {
+                return Ok(CapChoice::LastActivity);
+            }
+            "k" | "keep" | "" => {
+                return Ok(CapChoice::Keep);
+            }
+            "t" | "time" => {
+                write!(output, "stop the entry at: ")?;
+                output.flush()?;
+                let Some(time) = read_answer(&mut input)? else {
+                    return Ok(CapChoice::Keep);
+                };
+                match time_expr::parse_at(&time, now) {
+                    Ok(at) => {
+                        return Ok(CapChoice::At(at.timestamp() as u64));
+                    }
+                    Err(e) => writeln!(output, "{}", e.current_context())?,
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn read_answer<R: BufRead>(input: &mut R) -> std::io::Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_lowercase()))
+}
+
+/// The warning printed for a session that exceeded `max_session`.
+pub fn warning(elapsed: Duration, max_session: Duration) -> String {
+    format!(
+        "the timer has been running for {}, longer than the maximum session length of {}",
+        duration::format(elapsed, DurationFormat::Compact),
+        duration::format(max_session, DurationFormat::Compact)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use super::*;
+
+    fn answer(input: &str) -> CapChoice {
+        let now = Utc.with_ymd_and_hms(2026, 9, 16, 12, 0, 0).unwrap();
+        ask(input.as_bytes(), Vec::new(), &now).unwrap()
+    }
+
+    #[test]
+    fn answers_select_how_to_cap() {
+        assert_eq!(answer("l\n"), CapChoice::Limit);
+        assert_eq!(answer("activity\n"), CapChoice::LastActivity);
+        assert_eq!(answer("k\n"), CapChoice::Keep);
+        assert_eq!(answer(""), CapChoice::Keep);
+        assert_eq!(
+            answer("what\nt\nnever\nt\n11:30\n"),
+            CapChoice::At(Utc.with_ymd_and_hms(2026, 9, 16, 11, 30, 0).unwrap().timestamp() as u64)
+        );
+    }
+
+    #[test]
+    fn choices_resolve_to_stop_times() {
+        let limit = Duration::hours(10);
+        assert_eq!(CapChoice::Limit.stop_at(100, limit, 500), Some(36_100));
+        assert_eq!(CapChoice::LastActivity.stop_at(100, limit, 500), Some(500));
+        assert_eq!(CapChoice::LastActivity.stop_at(100, limit, 50), Some(100));
+        assert_eq!(CapChoice::At(42).stop_at(100, limit, 500), Some(42));
+        assert_eq!(CapChoice::Keep.stop_at(100, limit, 500), None);
+    }
+}
@@ -0,0 +1,226 @@
+use chrono::{ Duration, NaiveDate, NaiveTime };
+use clap::ValueEnum;
+use error_stack::Report;
+use serde::{ Deserialize, Serialize };
+use thiserror::Error;
+
+use super::{
+    balance::Schedule,
+    day_boundary::DayBoundary,
+    duration::{ self, DurationFormat },
+    rounding::Rounding,
+    session_guard::CapPolicy,
+    target::{ self, DailyTarget },
+};
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("unknown setting: {0}")] UnknownKey(String),
+
+    #[error("invalid value for {0}: {1}")] InvalidValue(String, String),
+}
+
+/// Preferences stored alongside the entries in the database.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// How entries crossing the start of a day are handled
+    pub day_boundary: DayBoundary,
+
+    /// When a day starts, e.g. 04:00 for night owls
+    pub day_start: NaiveTime,
+
+    /// Longest expected session in seconds, longer sessions are treated as forgotten timers
+    pub max_session: Option<i64>,
+
+    /// What to do with sessions longer than `max_session`
+    pub auto_cap: CapPolicy,
+
+    /// Longest gap in seconds between two heartbeats of the same session
+    pub idle_timeout: i64,
+
+    /// How billed time is rounded unless the client has its own rule
+    pub rounding: Rounding,
+
+    /// Time to work per day on some weekdays
+    pub daily_target: Option<DailyTarget>,
+
+    /// Time to work per week in seconds
+    pub weekly_target: Option<i64>,
+
+    /// Hours to work per weekday, the basis of the overtime balance
+    pub schedule: Option<Schedule>,
+
+    /// First day counted in the balance, the first tracked day when `None`
+    pub schedule_start: Option<NaiveDate>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            day_boundary: DayBoundary::default(),
+            day_start: NaiveTime::MIN,
+            max_session: None,
+            auto_cap: CapPolicy::default(),
+            idle_timeout: 15 * 60,
+            rounding: Rounding::default(),
+            daily_target: None,
+            weekly_target: None,
+            schedule: None,
+            schedule_start: None,
+        }
+    }
+}
+
+impl Settings {
+    pub const KEYS: &[&str] = &[
+        "day-boundary",
+        "day-start",
+        "max-session",
+        "auto-cap",
+        "idle-timeout",
+        "rounding",
+        "daily-target",
+        "weekly-target",
+        "schedule",
+        "schedule-start",
+    ];
+
+    pub fn max_session(&self) -> Option<Duration> {
+        self.max_session.map(Duration::seconds)
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::seconds(self.idle_timeout)
+    }
+
+    pub fn get(&self, key: &str) -> Result<String, Report<SettingsError>> {
+        let value = match key {
+            "day-boundary" => enum_name(self.day_boundary),
+            "day-start" => self.day_start.format("%H:%M").to_string(),
+            "max-session" =>
+                self
+                    .max_session()
+                    .map_or("off".to_owned(), |max| duration::format(max, DurationFormat::Compact)),
+            "auto-cap" => enum_name(self.auto_cap),
+            "idle-timeout" => duration::format(self.idle_timeout(), DurationFormat::Compact),
+            "rounding" => self.rounding.to_string(),
+            "daily-target" => self.daily_target.as_ref().map_or("off".to_owned(), DailyTarget::to_string),
+            "weekly-target" =>
+                self.weekly_target.map_or("off".to_owned(), |target| {
+                    duration::format(Duration::seconds(target), DurationFormat::Compact)
+                }),
+            "schedule" => self.schedule.map_or("off".to_owned(), |schedule| schedule.to_string()),
+            "schedule-start" => self.schedule_start.map_or("off".to_owned(), |start| start.to_string()),
+            _ => {
+                return Err(Report::new(SettingsError::UnknownKey(key.to_owned())));
+            }
+        };
+        Ok(value)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), Report<SettingsError>> {
+        let invalid = || Report::new(SettingsError::InvalidValue(key.to_owned(), value.to_owned()));
+        match key {
+            "day-boundary" => {
+                self.day_boundary = DayBoundary::from_str(value, true).map_err(|_| invalid())?;
+            }
+            "day-start" => {
+                self.day_start = NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| invalid())?;
+            }
+            "max-session" if value == "off" => {
+                self.max_session = None;
+            }
+            "max-session" => {
+                let max = duration::parse(value).map_err(|_| invalid())?;
+                if max <= Duration::zero() {
+                    return Err(invalid());
+                }
+                self.max_session = Some(max.num_seconds());
+            }
+            "auto-cap" => {
+                self.auto_cap = CapPolicy::from_str(value, true).map_err(|_| invalid())?;
+            }
+            "idle-timeout" => {
+                let timeout = duration::parse(value).map_err(|_| invalid())?;
+                if timeout <= Duration::zero() {
+                    return Err(invalid());
+                }
+                self.idle_timeout = timeout.num_seconds();
+            }
+            "rounding" => {
+                self.rounding = Rounding::parse(value).map_err(|_| invalid())?;
+            }
+            "daily-target" if value == "off" => {
+                self.daily_target = None;
+            }
+            "daily-target" => {
+                self.daily_target = Some(DailyTarget::parse(value).map_err(|_| invalid())?);
+            }
+            "weekly-target" if value == "off" => {
+                self.weekly_target = None;
+            }
+            "weekly-target" => {
+                self.weekly_target = Some(target::parse_time(value).ok_or_else(invalid)?);
+            }
+            "schedule" if value == "off" => {
+                self.schedule = None;
+            }
+            "schedule" => {
+                self.schedule = Some(Schedule::parse(value).map_err(|_| invalid())?);
+            }
+            "schedule-start" if value == "off" => {
+                self.schedule_start = None;
+            }
+            "schedule-start" => {
+                self.schedule_start = Some(NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| invalid())?);
+            }
+            _ => {
+                return Err(Report::new(SettingsError::UnknownKey(key.to_owned())));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn enum_name<T: ValueEnum>(value: T) -> String {
+    value
+        .to_possible_value()
+        .map(|possible| possible.get_name().to_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip_through_get_and_set() {
+        let mut settings = Settings::default();
+        settings.set("day-boundary", "split").unwrap();
+        settings.set("day-start", "04:30").unwrap();
+        assert_eq!(settings.get("day-boundary").unwrap(), "split");
+        assert_eq!(settings.get("day-start").unwrap(), "04:30");
+        settings.set("max-session", "10h").unwrap();
+        settings.set("auto-cap", "last-activity").unwrap();
+        assert_eq!(settings.get("max-session").unwrap(), "10h 0m");
+        assert_eq!(settings.get("auto-cap").unwrap(), "last-activity");
+        settings.set("max-session", "off").unwrap();
+        assert_eq!(settings.get("max-session").unwrap(), "off");
+        assert!(settings.set("day-start", "4").is_err());
+        assert!(settings.set("max-session", "0m").is_err());
+        settings.set("rounding", "15m nearest per-day").unwrap();
+        assert_eq!(settings.get("rounding").unwrap(), "15m nearest per-day");
+        assert!(settings.set("rounding", "15m sideways").is_err());
+        settings.set("daily-target", "6h mon-thu").unwrap();
+        settings.set("weekly-target", "40h").unwrap();
+        assert_eq!(settings.get("daily-target").unwrap(), "6h 0m mon-thu");
+        assert_eq!(settings.get("weekly-target").unwrap(), "40h 0m");
+        assert!(settings.set("weekly-target", "0h").is_err());
+        settings.set("schedule", "8h mon-thu; 4h fri").unwrap();
+        settings.set("schedule-start", "2026-09-01").unwrap();
+        assert_eq!(settings.get("schedule").unwrap(), "8h 0m mon-thu; 4h 0m fri");
+        assert_eq!(settings.get("schedule-start").unwrap(), "2026-09-01");
+        assert!(settings.set("colour", "blue").is_err());
+    }
+}
@@ -3,6 +3,8 @@ pub trait StringExt {
     fn pad_end(&self, pad_count: usize, pad_char: Option<char>) -> String;
     fn pad_start_to_length(&self, length: usize, pad_char: Option<char>) -> String;
     fn pad_end_to_length(&self, length: usize, pad_char: Option<char>) -> String;
+    /// Number of characters, so that symbols such as `€` count as one column.
+    fn width(&self) -> usize;
 }
 
 impl StringExt for String {
@@ -17,22 +19,38 @@ impl StringExt for String {
     }
 
     fn pad_start_to_length(&self, length: usize, pad_char: Option<char>) -> String {
-        if length <= self.len() {
+        if length <= self.width() {
             self.to_string()
         } else {
-            let pad_length = length - self.len();
+            let pad_length = length - self.width();
             let padding = pad_char.unwrap_or(' ').to_string().repeat(pad_length);
             format!("{}{}", padding, self) 
         }
     }
 
     fn pad_end_to_length(&self, length: usize, pad_char: Option<char>) -> String {
-        if length <= self.len() {
+        if length <= self.width() {
             self.to_string()
         } else {
-            let pad_length = length - self.len();
+            let pad_length = length - self.width();
             let padding = pad_char.unwrap_or(' ').to_string().repeat(pad_length);
             format!("{}{}", self, padding)
         }
     }
+
+    fn width(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padding_counts_characters() {
+        assert_eq!("€5".to_owned().pad_start_to_length(4, None), "  €5");
+        assert_eq!("ab".to_owned().pad_end_to_length(4, Some('-')), "ab--");
+        assert_eq!("abc".to_owned().pad_end_to_length(2, None), "abc");
+    }
 }
@@ -0,0 +1,285 @@
+use std::fmt;
+use chrono::{ DateTime, Datelike, Duration, Local, NaiveDate, Weekday };
+use error_stack::Report;
+use serde::{ Deserialize, Serialize };
+use thiserror::Error;
+
+use super::{
+    day_boundary,
+    duration::{ self, DurationFormat },
+    settings::Settings,
+    string_ext::StringExt,
+    time_off::{ self, DayOff },
+    tracker::entry::Entry,
+};
+
+/// Width of the progress bars between the brackets.
+pub const BAR_WIDTH: usize = 20;
+
+pub const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+#[derive(Error, Debug)]
+pub enum TargetError {
+    #[error("invalid target: {0:?}, expected e.g. \"off\", \"8h\" or \"6h mon,wed-fri\"")]
+    InvalidTarget(String),
+}
+
+/// Time to work on each of some weekdays, such as 8h on Monday to Friday.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyTarget {
+    /// Target per day in seconds
+    pub time: i64,
+    pub weekdays: Vec<Weekday>,
+}
+
+impl DailyTarget {
+    /// Parses `<time> [days]`, where days are names such as `mon`, ranges such as `mon-fri`
+    /// or lists of both such as `mon,wed-fri`. Without days the target is for Monday to Friday.
+    pub fn parse(input: &str) -> Result<Self, Report<TargetError>> {
+        let invalid = || Report::new(TargetError::InvalidTarget(input.to_owned()));
+        let (time, days) = match input.trim().rsplit_once(' ') {
+            Some((time, days)) if days.chars().all(|c| c.is_ascii_alphabetic() || c == ',' || c == '-') =>
+                (time, Some(days)),
+            _ => (input, None),
+        };
+        let time = parse_time(time).ok_or_else(invalid)?;
+        let weekdays = match days {
+            Some(days) => parse_weekdays(days).ok_or_else(invalid)?,
+            None => WEEKDAYS[..5].to_vec(),
+        };
+        Ok(Self { time, weekdays })
+    }
+
+    /// The target on `day`, `None` on days without one.
+    pub fn on(&self, day: NaiveDate) -> Option<Duration> {
+        self.weekdays.contains(&day.weekday()).then(|| Duration::seconds(self.time))
+    }
+}
+
+impl fmt::Display for DailyTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = duration::format(Duration::seconds(self.time), DurationFormat::Compact);
+        write!(f, "{time} {}", format_weekdays(&self.weekdays))
+    }
+}
+
+/// Parses a positive time such as `8h` or `7h30m`, in seconds.
+pub fn parse_time(input: &str) -> Option<i64> {
+    duration
+        ::parse(input.trim())
+        .ok()
+        .map(|time| time.num_seconds())
+        .filter(|seconds| *seconds > 0)
+}
+
+/// Parses a full weekday name or its three letter abbreviation, ignoring case.
+fn parse_weekday(name: &str) -> Option<Weekday> {
+    name.trim().parse().ok()
+}
+
+fn parse_weekdays(input: &str) -> Option<Vec<Weekday>> {
+    let mut weekdays: Vec<Weekday> = Vec::new();
+    for part in input.split(',') {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (parse_weekday(first)?, parse_weekday(last)?),
+            None => {
+                let day = parse_weekday(part)?;
+                (day, day)
+            }
+        };
+        let (first, last) = (first.num_days_from_monday(), last.num_days_from_monday());
+        if first > last {
+            return None;
+        }
+        weekdays.extend(&WEEKDAYS[first as usize..=last as usize]);
+    }
+    weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+    weekdays.dedup();
+    Some(weekdays)
+}
+
+/// Weekdays as names and ranges such as `mon,wed-fri`.
+fn format_weekdays(weekdays: &[Weekday]) -> String {
+    let name = |weekday: Weekday| weekday.to_string().to_lowercase();
+    let mut parts: Vec<String> = Vec::new();
+    let mut run: Option<(Weekday, Weekday)> = None;
+    for weekday in WEEKDAYS.into_iter().filter(|weekday| weekdays.contains(weekday)) {
+        run = match run {
+            Some((first, last)) if last.succ() == weekday => Some((first, weekday)),
+            Some((first, last)) => {
+                parts.push(format_run(first, last, name));
+                Some((weekday, weekday))
+            }
+            None => Some((weekday, weekday)),
+        };
+    }
+    if let Some((first, last)) = run {
+        parts.push(format_run(first, last, name));
+    }
+    parts.join(",")
+}
+
+fn format_run(first: Weekday, last: Weekday, name: impl Fn(Weekday) -> String) -> String {
+    if first == last { name(first) } else { format!("{}-{}", name(first), name(last)) }
+}
+
+/// Time done towards a target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub done: Duration,
+    pub target: Duration,
+}
+
+impl Progress {
+    pub fn remaining(&self) -> Duration {
+        (self.target - self.done).max(Duration::zero())
+    }
+
+    /// A bar such as `[##########----------]` filled by the done share of the target.
+    pub fn bar(&self) -> String {
+        let filled = if self.target <= Duration::zero() {
+            BAR_WIDTH
+        } else {
+            let share = (self.done.num_seconds() as f64) / (self.target.num_seconds() as f64);
+            ((share * (BAR_WIDTH as f64)).floor() as usize).min(BAR_WIDTH)
+        };
+        format!("[{}]", "#".repeat(filled).pad_end_to_length(BAR_WIDTH, Some('-')))
+    }
+
+    /// When the target is reached when working on from `now`, `None` when it already is.
+    pub fn done_at(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        let remaining = self.remaining();
+        (remaining > Duration::zero()).then(|| now + remaining)
+    }
+
+    /// The bar followed by e.g. `5h 30m of 8h 0m, 2h 30m left, done at 17:30`.
+    pub fn describe(&self, now: DateTime<Local>, duration_format: DurationFormat) -> String {
+        let done = duration::format(self.done, duration_format);
+        let target = duration::format(self.target, duration_format);
+        match self.done_at(now) {
+            Some(done_at) =>
+                format!(
+                    "{} {done} of {target}, {} left, done at {}",
+                    self.bar(),
+                    duration::format(self.remaining(), duration_format),
+                    done_at.format(if done_at.date_naive() == now.date_naive() { "%H:%M" } else { "%a %H:%M" })
+                ),
+            None => format!("{} {done} of {target}, done", self.bar()),
+        }
+    }
+}
+
+/// Progress towards the daily and weekly targets at `now`, labelled `day` and `week`.
+/// Weeks start on Monday, and both follow the configured day start. There is no daily target
+/// on days off, and each day off on a working day lowers the weekly target by its share of
+/// the week's working days, which are the days of the daily target or Monday to Friday.
+pub fn progress(entries: &[Entry], settings: &Settings, days_off: &[DayOff], now: u64) -> Vec<(&'static str, Progress)> {
+    let today = day_boundary::day_of(now, settings.day_start);
+    let done_since = |day: NaiveDate| -> Duration {
+        let from = day_boundary::start_of_day(day, settings.day_start);
+        entries
+            .iter()
+            .map(|entry| entry.overlap(from, now, now))
+            .sum()
+    };
+    let is_working_day = |day: NaiveDate| match &settings.daily_target {
+        Some(target) => target.on(day).is_some(),
+        None => day.weekday().num_days_from_monday() < 5,
+    };
+    let mut progress = Vec::new();
+    if let Some(target) = settings.daily_target.as_ref().and_then(|target| target.on(today)) {
+        if time_off::on(days_off, today).is_none() {
+            progress.push(("day", Progress { done: done_since(today), target }));
+        }
+    }
+    if let Some(target) = settings.weekly_target {
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let working_days: Vec<NaiveDate> = monday
+            .iter_days()
+            .take(7)
+            .filter(|day| is_working_day(*day))
+            .collect();
+        let off = working_days
+            .iter()
+            .filter(|day| time_off::on(days_off, **day).is_some())
+            .count() as i64;
+        let target = match working_days.len() as i64 {
+            0 => target,
+            working => (target * (working - off)) / working,
+        };
+        progress.push(("week", Progress { done: done_since(monday), target: Duration::seconds(target) }));
+    }
+    progress
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use super::*;
+
+    #[test]
+    fn daily_targets_round_trip_through_text() {
+        let target = DailyTarget::parse("8h").unwrap();
+        assert_eq!(target.weekdays, WEEKDAYS[..5].to_vec());
+        assert_eq!(target.to_string(), "8h 0m mon-fri");
+        let target = DailyTarget::parse("7h30m sat,mon,wed-fri").unwrap();
+        assert_eq!(target.to_string(), "7h 30m mon,wed-sat");
+        assert_eq!(DailyTarget::parse(&target.to_string()).unwrap(), target);
+        assert!(DailyTarget::parse("4h monday-tuesday").is_ok());
+        for invalid in ["", "0h", "8h fri-mon", "8h someday", "8h monkey", "8h satur", "eight"] {
+            assert!(DailyTarget::parse(invalid).is_err(), "expected {invalid:?} to fail");
+        }
+        let saturday = NaiveDate::from_ymd_opt(2026, 9, 19).unwrap();
+        assert_eq!(DailyTarget::parse("8h").unwrap().on(saturday), None);
+    }
+
+    #[test]
+    fn progress_shows_a_bar_and_an_estimate() {
+        let now = Local.with_ymd_and_hms(2026, 9, 14, 12, 0, 0).unwrap();
+        let progress = Progress { done: Duration::minutes(330), target: Duration::hours(8) };
+        assert_eq!(
+            progress.describe(now, DurationFormat::Compact),
+            "[#############-------] 5h 30m of 8h 0m, 2h 30m left, done at 14:30"
+        );
+        let late = Local.with_ymd_and_hms(2026, 9, 14, 23, 0, 0).unwrap();
+        assert!(progress.describe(late, DurationFormat::Compact).ends_with("done at Tue 01:30"));
+        let overtime = Progress { done: Duration::hours(9), target: Duration::hours(8) };
+        assert_eq!(overtime.describe(now, DurationFormat::Compact), "[####################] 9h 0m of 8h 0m, done");
+    }
+
+    #[test]
+    fn days_off_lower_the_targets() {
+        let settings = Settings {
+            daily_target: Some(DailyTarget::parse("8h").unwrap()),
+            weekly_target: Some(40 * 3600),
+            ..Settings::default()
+        };
+        // Wednesday 2026-09-16 at noon, with Monday and Wednesday off
+        let now = Local.with_ymd_and_hms(2026, 9, 16, 12, 0, 0).unwrap().timestamp() as u64;
+        let days_off = time_off::range(
+            NaiveDate::from_ymd_opt(2026, 9, 14).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 9, 16).unwrap(),
+            time_off::OffKind::Vacation,
+            None
+        );
+        let days_off = [days_off[0].clone(), days_off[2].clone()];
+        let targets: Vec<(&str, i64)> = progress(&[], &settings, &days_off, now)
+            .into_iter()
+            .map(|(label, progress)| (label, progress.target.num_hours()))
+            .collect();
+        assert_eq!(targets, vec![("week", 24)]);
+        let targets: Vec<(&str, i64)> = progress(&[], &settings, &[], now)
+            .into_iter()
+            .map(|(label, progress)| (label, progress.target.num_hours()))
+            .collect();
+        assert_eq!(targets, vec![("day", 8), ("week", 40)]);
+    }
+}
@@ -0,0 +1,323 @@
+use chrono::{ DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Weekday };
+use error_stack::Report;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TimeExprError {
+    #[error("time expression is empty")]
+    Empty,
+
+    #[error("unrecognized time expression: {0}")] Unrecognized(String),
+
+    #[error("invalid time of day: {0}")] InvalidTime(String),
+
+    #[error("invalid date: {0}")] InvalidDate(String),
+
+    #[error("local time does not exist: {0}")] NonexistentLocalTime(String),
+
+    #[error("time is out of range: {0}")] OutOfRange(String),
+}
+
+/// Parses a time expression relative to `now`.
+///
+/// Supported forms:
+/// - `now`
+/// - relative: `15 minutes ago`, `1h 30m ago`, `in 10 min`
+/// - time of day (today): `17:30`, `17:30:15`, `5pm`, `5:30pm`, `noon`, `midnight`
+/// - absolute: `2026-09-14`, `2026-09-14 17:30`, `2026-09-14T17:30:00`
+/// - day words: `today`, `yesterday`, `tomorrow`, optionally followed by a time
+/// - weekdays: `monday`, `last fri`, `next tue 9am`
+///
+/// A bare weekday means the most recent such day (today included), `last` excludes
+/// today and `next` looks forward. Day-only expressions resolve to the start of the day.
+pub fn parse_at<Tz: TimeZone>(
+    input: &str,
+    now: &DateTime<Tz>
+) -> Result<DateTime<Tz>, Report<TimeExprError>> {
+    let normalized = input.trim().to_lowercase();
+    if normalized.is_empty() {
+        return Err(Report::new(TimeExprError::Empty));
+    }
+    let words: Vec<&str> = normalized
+        .split_whitespace()
+        .filter(|word| *word != "at")
+        .collect();
+
+    if words == ["now"] {
+        return Ok(now.clone());
+    }
+
+    let out_of_range = || Report::new(TimeExprError::OutOfRange(input.to_owned()));
+    if let Some(offset) = parse_relative(&words) {
+        let offset = offset.ok_or_else(out_of_range)?;
+        return now.clone().checked_add_signed(offset).ok_or_else(out_of_range);
+    }
+
+    let today = now.date_naive();
+    let (date, rest) = if let Some(date) = parse_date(words[0]) {
+        (date, &words[1..])
+    } else if let Some(date) = parse_day_word(words[0], today) {
+        (date, &words[1..])
+    } else if let Some(date) = parse_weekday(&words, today) {
+        let consumed = if matches!(words[0], "last" | "next" | "this") { 2 } else { 1 };
+        (date, &words[consumed..])
+    } else if let Some((date, time)) = parse_iso_datetime(words[0]) {
+        return resolve(now, date, time, input);
+    } else {
+        let time = parse_time(&words.concat()).ok_or_else(|| {
+            Report::new(TimeExprError::Unrecognized(input.to_owned()))
+        })?;
+        return resolve(now, today, time, input);
+    };
+
+    let time = if rest.is_empty() {
+        NaiveTime::MIN
+    } else {
+        parse_time(&rest.concat()).ok_or_else(|| {
+            Report::new(TimeExprError::InvalidTime(input.to_owned()))
+        })?
+    };
+
+    resolve(now, date, time, input)
+}
+
+fn resolve<Tz: TimeZone>(
+    now: &DateTime<Tz>,
+    date: NaiveDate,
+    time: NaiveTime,
+    input: &str
+) -> Result<DateTime<Tz>, Report<TimeExprError>> {
+    now.timezone()
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .ok_or_else(|| Report::new(TimeExprError::NonexistentLocalTime(input.to_owned())))
+}
+
+/// `<amount> <unit> ... ago` or `in <amount> <unit> ...`, with `Some(None)` for offsets too
+/// large to represent.
+fn parse_relative(words: &[&str]) -> Option<Option<Duration>> {
+    let (sign, body) = match words {
+        [body @ .., "ago"] => (-1, body),
+        ["in", body @ ..] => (1, body),
+        _ => {
+            return None;
+        }
+    };
+    if body.is_empty() {
+        return None;
+    }
+
+    // Accept both `15 minutes` and `15min`, as well as `1h30m` and `an hour`.
+    let joined: String = body
+        .iter()
+        .map(|word| if matches!(*word, "a" | "an") { "1" } else { word })
+        .collect();
+    let mut total = Duration::zero();
+    let mut chars = joined.chars().peekable();
+    while chars.peek().is_some() {
+        let mut amount = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+            amount.push(c);
+        }
+        let mut unit = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+            unit.push(c);
+        }
+        let unit = unit_duration(&unit)?;
+        if amount.is_empty() {
+            return None;
+        }
+        let Some(offset) = amount
+            .parse()
+            .ok()
+            .and_then(|amount| unit.checked_mul(amount))
+            .and_then(|offset| total.checked_add(&offset)) else {
+            return Some(None);
+        };
+        total = offset;
+    }
+
+    Some(total.checked_mul(sign))
+}
+
+fn unit_duration(unit: &str) -> Option<Duration> {
+    match unit {
+        "s" | "sec" | "secs" | "second" | "seconds" => Some(Duration::seconds(1)),
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(Duration::minutes(1)),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(Duration::hours(1)),
+        "d" | "day" | "days" => Some(Duration::days(1)),
+        "w" | "week" | "weeks" => Some(Duration::weeks(1)),
+        _ => None,
+    }
+}
+
+fn parse_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()
+}
+
+/// ISO 8601 style `2026-09-14T17:30`
+fn parse_iso_datetime(word: &str) -> Option<(NaiveDate, NaiveTime)> {
+    let (date, time) = word.split_once('t')?;
+    Some((parse_date(date)?, parse_time(time)?))
+}
+
+fn parse_day_word(word: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match word {
+        "today" => Some(today),
+        "yesterday" => today.pred_opt(),
+        "tomorrow" => today.succ_opt(),
+        _ => None,
+    }
+}
+
+fn parse_weekday(words: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    let (modifier, name) = match words {
+        [modifier @ ("last" | "next" | "this"), name, ..] => (*modifier, *name),
+        [name, ..] => ("", *name),
+        [] => {
+            return None;
+        }
+    };
+    let weekday: Weekday = name.parse().ok()?;
+    let current = today.weekday().num_days_from_monday() as i64;
+    let target = weekday.num_days_from_monday() as i64;
+    let days_back = (current - target).rem_euclid(7);
+
+    let date = match modifier {
+        "next" => today + Duration::days((target - current - 1).rem_euclid(7) + 1),
+        "last" => today - Duration::days(if days_back == 0 { 7 } else { days_back }),
+        _ => today - Duration::days(days_back),
+    };
+    Some(date)
+}
+
+/// Parses `17:30`, `17:30:15`, `5pm`, `5:30pm`, `noon` and `midnight`.
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    match word {
+        "noon" => {
+            return NaiveTime::from_hms_opt(12, 0, 0);
+        }
+        "midnight" => {
+            return Some(NaiveTime::MIN);
+        }
+        _ => {}
+    }
+
+    let (clock, meridiem) = if let Some(clock) = word.strip_suffix("am") {
+        (clock, Some(false))
+    } else if let Some(clock) = word.strip_suffix("pm") {
+        (clock, Some(true))
+    } else {
+        (word, None)
+    };
+
+    let mut parts = clock.split(':');
+    let hour: u32 = parts.next()?.parse().ok()?;
+    let minute: u32 = parts.next().map_or(Some(0), |m| m.parse().ok())?;
+    let second: u32 = parts.next().map_or(Some(0), |s| s.parse().ok())?;
+    if parts.next().is_some() {
+        return None;
+    }
+    // A bare number is only a time when it carries am/pm, otherwise `15` is ambiguous.
+    if meridiem.is_none() && !clock.contains(':') {
+        return None;
+    }
+
+    let hour = match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => {
+            return None;
+        }
+        Some(false) => hour % 12,
+        Some(true) => (hour % 12) + 12,
+        None => hour,
+    };
+
+    NaiveTime::from_hms_opt(hour, minute, second)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{ NaiveDateTime, Utc };
+    use super::*;
+
+    // 2026-09-16 is a Wednesday.
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 9, 16, 14, 45, 0).unwrap()
+    }
+
+    fn parse(input: &str) -> NaiveDateTime {
+        parse_at(input, &now())
+            .unwrap_or_else(|e| panic!("failed to parse {input:?}: {e:?}"))
+            .naive_local()
+    }
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, min, s).unwrap()
+    }
+
+    #[test]
+    fn now_is_the_reference_time() {
+        assert_eq!(parse("now"), at(2026, 9, 16, 14, 45, 0));
+    }
+
+    #[test]
+    fn relative_expressions_move_from_now() {
+        assert_eq!(parse("15 minutes ago"), at(2026, 9, 16, 14, 30, 0));
+        assert_eq!(parse("15m ago"), at(2026, 9, 16, 14, 30, 0));
+        assert_eq!(parse("1h 30m ago"), at(2026, 9, 16, 13, 15, 0));
+        assert_eq!(parse("an hour ago"), at(2026, 9, 16, 13, 45, 0));
+        assert_eq!(parse("2 days ago"), at(2026, 9, 14, 14, 45, 0));
+        assert_eq!(parse("in 10 min"), at(2026, 9, 16, 14, 55, 0));
+    }
+
+    #[test]
+    fn offsets_out_of_range_are_rejected() {
+        for input in ["in 999999999 days", "99999999999 weeks ago", "in 2000000000 weeks"] {
+            let error = parse_at(input, &now()).expect_err(input);
+            assert!(matches!(error.current_context(), TimeExprError::OutOfRange(_)), "{input}: {error:?}");
+        }
+    }
+
+    #[test]
+    fn time_of_day_refers_to_today() {
+        assert_eq!(parse("17:30"), at(2026, 9, 16, 17, 30, 0));
+        assert_eq!(parse("08:05:30"), at(2026, 9, 16, 8, 5, 30));
+        assert_eq!(parse("5pm"), at(2026, 9, 16, 17, 0, 0));
+        assert_eq!(parse("5:30 pm"), at(2026, 9, 16, 17, 30, 0));
+        assert_eq!(parse("12am"), at(2026, 9, 16, 0, 0, 0));
+        assert_eq!(parse("noon"), at(2026, 9, 16, 12, 0, 0));
+        assert_eq!(parse("midnight"), at(2026, 9, 16, 0, 0, 0));
+    }
+
+    #[test]
+    fn absolute_dates_with_optional_time() {
+        assert_eq!(parse("2026-09-14"), at(2026, 9, 14, 0, 0, 0));
+        assert_eq!(parse("2026-09-14 17:30"), at(2026, 9, 14, 17, 30, 0));
+        assert_eq!(parse("2026-09-14T17:30:10"), at(2026, 9, 14, 17, 30, 10));
+    }
+
+    #[test]
+    fn day_words_with_optional_time() {
+        assert_eq!(parse("today"), at(2026, 9, 16, 0, 0, 0));
+        assert_eq!(parse("yesterday 9am"), at(2026, 9, 15, 9, 0, 0));
+        assert_eq!(parse("yesterday at noon"), at(2026, 9, 15, 12, 0, 0));
+        assert_eq!(parse("tomorrow 08:00"), at(2026, 9, 17, 8, 0, 0));
+    }
+
+    #[test]
+    fn weekdays_resolve_around_today() {
+        assert_eq!(parse("monday"), at(2026, 9, 14, 0, 0, 0));
+        assert_eq!(parse("wed 9am"), at(2026, 9, 16, 9, 0, 0));
+        assert_eq!(parse("last wednesday"), at(2026, 9, 9, 0, 0, 0));
+        assert_eq!(parse("last fri 14:00"), at(2026, 9, 11, 14, 0, 0));
+        assert_eq!(parse("next monday"), at(2026, 9, 21, 0, 0, 0));
+        assert_eq!(parse("next wednesday"), at(2026, 9, 23, 0, 0, 0));
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        for input in ["", "soon", "25:00", "13pm", "15", "yesterday teatime", "2026-13-01"] {
+            assert!(parse_at(input, &now()).is_err(), "expected {input:?} to be rejected");
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use std::{ fmt, fs, path::Path };
+use chrono::{ Duration, NaiveDate };
+use clap::ValueEnum;
+use error_stack::Report;
+use serde::{ Deserialize, Serialize };
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TimeOffError {
+    #[error("failed to read the holiday file: {0}")] ReadError(#[source] std::io::Error),
+
+    #[error("invalid holiday on line {0}: {1:?}")] InvalidLine(usize, String),
+
+    #[error("no days off on {0}")] NotFound(String),
+}
+
+/// Why a day is not worked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OffKind {
+    #[default]
+    Vacation,
+    Sick,
+    Holiday,
+}
+
+impl fmt::Display for OffKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Vacation => "vacation",
+            Self::Sick => "sick",
+            Self::Holiday => "holiday",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A day without expected work, such as a vacation day or a public holiday.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayOff {
+    pub date: NaiveDate,
+
+    pub kind: OffKind,
+
+    /// Name of a holiday such as "Christmas Day"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl fmt::Display for DayOff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} {} ({name})", self.date, self.kind),
+            None => write!(f, "{} {}", self.date, self.kind),
+        }
+    }
+}
+
+/// The day off on `day`, if any.
+pub fn on(days_off: &[DayOff], day: NaiveDate) -> Option<&DayOff> {
+    days_off.iter().find(|off| off.date == day)
+}
+
+/// Adds `added` to `days_off`, replacing days off on the same dates, and keeps them sorted.
+/// Of several added days off on the same date the last one wins. Returns how many days were
+/// added or replaced.
+pub fn merge(days_off: &mut Vec<DayOff>, added: Vec<DayOff>) -> usize {
+    let mut unique: Vec<DayOff> = Vec::with_capacity(added.len());
+    for off in added {
+        unique.retain(|existing| existing.date != off.date);
+        unique.push(off);
+    }
+    let count = unique.len();
+    days_off.retain(|off| !unique.iter().any(|new| new.date == off.date));
+    days_off.extend(unique);
+    days_off.sort_by_key(|off| off.date);
+    count
+}
+
+/// Every day from `first` to `last`, both inclusive, as days off of `kind`.
+pub fn range(first: NaiveDate, last: NaiveDate, kind: OffKind, name: Option<&str>) -> Vec<DayOff> {
+    first
+        .iter_days()
+        .take_while(|day| *day <= last)
+        .map(|date| DayOff { date, kind, name: name.map(str::to_owned) })
+        .collect()
+}
+
+/// Reads days off of `kind` from an iCalendar file when `path` ends in `.ics`, and from a CSV
+/// file otherwise.
+pub fn import(path: &Path, kind: OffKind) -> Result<Vec<DayOff>, Report<TimeOffError>> {
+    let content = fs::read_to_string(path).map_err(|e| Report::new(TimeOffError::ReadError(e)))?;
+    let is_ics = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("ics"));
+    if is_ics { parse_ics(&content, kind) } else { parse_csv(&content, kind) }
+}
+
+/// Parses lines of `date[,name]` such as `2026-12-25,Christmas Day`. Empty lines, lines
+/// starting with `#` and a header line are skipped.
+pub fn parse_csv(content: &str, kind: OffKind) -> Result<Vec<DayOff>, Report<TimeOffError>> {
+    let mut days_off = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (date, name) = match line.split_once([',', ';']) {
+            Some((date, name)) => (date.trim(), Some(name.trim().trim_matches('"').trim())),
+            None => (line, None),
+        };
+        let Ok(date) = NaiveDate::parse_from_str(date.trim_matches('"'), "%Y-%m-%d") else {
+            if index == 0 {
+                continue;
+            }
+            return Err(Report::new(TimeOffError::InvalidLine(index + 1, line.to_owned())));
+        };
+        let name = name.filter(|name| !name.is_empty()).map(str::to_owned);
+        days_off.push(DayOff { date, kind, name });
+    }
+    Ok(days_off)
+}
+
+/// Parses the all-day events of an iCalendar file. Events spanning several days yield one day
+/// off per day, and the `SUMMARY` becomes the name. Timed events such as appointments are
+/// skipped, as they do not take the whole day.
+pub fn parse_ics(content: &str, kind: OffKind) -> Result<Vec<DayOff>, Report<TimeOffError>> {
+    // Lines starting with a space or tab continue the previous line
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, last))) => last.push_str(rest),
+            _ => lines.push((index + 1, line.trim_end().to_owned())),
+        }
+    }
+
+    let mut days_off = Vec::new();
+    let mut event: Option<Event> = None;
+    for (number, line) in lines {
+        let Some((property, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = property.split(';').next().unwrap_or_default().to_uppercase();
+        let date = || {
+            let date = value.get(..8).unwrap_or(value);
+            NaiveDate::parse_from_str(date, "%Y%m%d").map_err(|_| {
+                Report::new(TimeOffError::InvalidLine(number, line.clone()))
+            })
+        };
+        match (name.as_str(), event.as_mut()) {
+            ("BEGIN", _) if value.eq_ignore_ascii_case("VEVENT") => {
+                event = Some(Event::default());
+            }
+            ("END", Some(current)) if value.eq_ignore_ascii_case("VEVENT") => {
+                let Some(start) = current.start else {
+                    return Err(Report::new(TimeOffError::InvalidLine(number, line)));
+                };
+                if !current.timed {
+                    // The end of an all-day event is exclusive
+                    let last = current.end.map_or(start, |end| (end - Duration::days(1)).max(start));
+                    days_off.extend(range(start, last, kind, current.summary.as_deref()));
+                }
+                event = None;
+            }
+            ("DTSTART", Some(current)) => {
+                current.start = Some(date()?);
+                // All-day events hold a bare date, timed ones a date and a time
+                current.timed = value.contains(['T', 't']);
+            }
+            ("DTEND", Some(current)) => {
+                current.end = Some(date()?);
+            }
+            ("SUMMARY", Some(current)) => {
+                current.summary = Some(unescape(value));
+            }
+            _ => (),
+        }
+    }
+    Ok(days_off)
+}
+
+/// The properties of an iCalendar event read so far.
+#[derive(Default)]
+struct Event {
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+    summary: Option<String>,
+    timed: bool,
+}
+
+/// Resolves the escapes of iCalendar text values.
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push(' '),
+            Some(escaped) => unescaped.push(escaped),
+            None => (),
+        }
+    }
+    unescaped.trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Datelike;
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, month, day).unwrap()
+    }
+
+    #[test]
+    fn holidays_are_read_from_csv() {
+        let csv = "date,name\n2026-12-25,Christmas Day\n\n# observed\n2026-12-26\n";
+        let days_off = parse_csv(csv, OffKind::Holiday).unwrap();
+        assert_eq!(
+            days_off,
+            vec![
+                DayOff { date: date(12, 25), kind: OffKind::Holiday, name: Some("Christmas Day".to_owned()) },
+                DayOff { date: date(12, 26), kind: OffKind::Holiday, name: None }
+            ]
+        );
+        assert_eq!(days_off[0].to_string(), "2026-12-25 holiday (Christmas Day)");
+        assert!(parse_csv("2026-12-25\nsoon\n", OffKind::Holiday).is_err());
+    }
+
+    #[test]
+    fn holidays_are_read_from_all_day_events() {
+        let ics = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20261225\r\nDTEND;VALUE=DATE:20261227\r\n\
+            SUMMARY:Christmas\\, Boxing\r\n  Day\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nSUMMARY:New Year\r\n\
+            DTSTART;VALUE=DATE:20270101\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nSUMMARY:Dentist\r\n\
+            DTSTART:20270105T090000Z\r\nDTEND:20270105T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let days_off: Vec<String> = parse_ics(ics, OffKind::Holiday)
+            .unwrap()
+            .iter()
+            .map(DayOff::to_string)
+            .collect();
+        assert_eq!(
+            days_off,
+            vec![
+                "2026-12-25 holiday (Christmas, Boxing Day)",
+                "2026-12-26 holiday (Christmas, Boxing Day)",
+                "2027-01-01 holiday (New Year)"
+            ]
+        );
+        assert!(parse_ics("BEGIN:VEVENT\nDTSTART:tomorrow\nEND:VEVENT\n", OffKind::Holiday).is_err());
+    }
+
+    #[test]
+    fn merging_replaces_days_off_on_the_same_date() {
+        let mut days_off = range(date(12, 24), date(12, 26), OffKind::Vacation, None);
+        let added = parse_csv("2026-12-25\n2026-12-25,Christmas Day\n2026-12-01", OffKind::Holiday).unwrap();
+        assert_eq!(merge(&mut days_off, added), 2);
+        assert_eq!(days_off.len(), 4);
+        let kinds: Vec<(u32, OffKind)> = days_off
+            .iter()
+            .map(|off| (off.date.day0() + 1, off.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![(1, OffKind::Holiday), (24, OffKind::Vacation), (25, OffKind::Holiday), (26, OffKind::Vacation)]
+        );
+        assert_eq!(on(&days_off, date(12, 25)).map(|off| off.kind), Some(OffKind::Holiday));
+        assert_eq!(on(&days_off, date(12, 27)), None);
+    }
+}
@@ -0,0 +1,80 @@
+use serde::{ Deserialize, Serialize };
+
+use crate::feature::{
+    billing::{ Billing, Rates },
+    budget::Budget,
+    client::Client,
+    git::CommitRecord,
+    invoice::InvoiceRecord,
+    settings::Settings,
+    time_off::DayOff,
+};
+use super::entry::{ assign_ids, Entry, StoredEntries };
+
+/// Everything persisted in the database file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Database {
+    #[serde(default)]
+    pub entries: Vec<Entry>,
+
+    #[serde(default)]
+    pub settings: Settings,
+
+    /// Registered project paths such as `acme/website`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub projects: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clients: Vec<Client>,
+
+    /// Hourly rates of projects and tags
+    #[serde(default, skip_serializing_if = "Rates::is_empty")]
+    pub rates: Rates,
+
+    /// Commits recorded by the git post-commit hook
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<CommitRecord>,
+
+    /// Invoices issued by `track invoice`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invoices: Vec<InvoiceRecord>,
+
+    /// Time and money budgets of projects
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub budgets: Vec<Budget>,
+
+    /// Vacation, sick and holiday days, sorted by date
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days_off: Vec<DayOff>,
+}
+
+impl Database {
+    /// Clients, rates and rounding settings for billing the entries.
+    pub fn billing(&self) -> Billing<'_> {
+        Billing {
+            clients: &self.clients,
+            rates: &self.rates,
+            rounding: self.settings.rounding,
+            day_start: self.settings.day_start,
+        }
+    }
+}
+
+/// The database as found on disk. Older databases only stored the list of entries.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum StoredDatabase {
+    Document(Box<Database>),
+    Entries(StoredEntries),
+}
+
+impl From<StoredDatabase> for Database {
+    fn from(stored: StoredDatabase) -> Self {
+        let mut database = match stored {
+            StoredDatabase::Document(database) => *database,
+            StoredDatabase::Entries(entries) => Database { entries: entries.into(), ..Database::default() },
+        };
+        assign_ids(&mut database.entries);
+        database
+    }
+}
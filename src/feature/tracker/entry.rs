@@ -0,0 +1,232 @@
+use chrono::{ DateTime, Duration, Local, TimeZone, Utc };
+use serde::{ Deserialize, Serialize };
+
+use crate::feature::git::GitInfo;
+
+/// Descriptive metadata shared by running timers and finished entries.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EntryDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+
+    /// Whether the time can be billed, `None` when it was never decided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub billable: Option<bool>,
+}
+
+impl EntryDetails {
+    /// Combines the details of chronologically ordered entries: the project and billable flag
+    /// must agree, tags are unioned in order of first appearance and distinct notes are joined
+    /// with `; `. Returns `None` when the entries belong to different projects or disagree on
+    /// whether they are billable.
+    pub fn combine<'a, I>(details: I) -> Option<Self> where I: IntoIterator<Item = &'a EntryDetails> {
+        let mut combined = Self::default();
+        let mut notes: Vec<&str> = Vec::new();
+        for current in details {
+            match (&combined.project, &current.project) {
+                (Some(project), Some(other)) if project != other => {
+                    return None;
+                }
+                (None, Some(other)) => {
+                    combined.project = Some(other.clone());
+                }
+                _ => {}
+            }
+            match (combined.billable, current.billable) {
+                (Some(billable), Some(other)) if billable != other => {
+                    return None;
+                }
+                (None, Some(other)) => {
+                    combined.billable = Some(other);
+                }
+                _ => {}
+            }
+            for tag in &current.tags {
+                if !combined.tags.contains(tag) {
+                    combined.tags.push(tag.clone());
+                }
+            }
+            if let Some(note) = current.note.as_deref() {
+                if !note.is_empty() && !notes.contains(&note) {
+                    notes.push(note);
+                }
+            }
+        }
+        if !notes.is_empty() {
+            combined.note = Some(notes.join("; "));
+        }
+        Some(combined)
+    }
+}
+
+/// A tracked span of time. `stop` stays `None` while the timer is running.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Stable identifier, assigned when the entry is first stored. `0` means unassigned.
+    #[serde(default)]
+    pub id: u64,
+
+    pub start: u64,
+
+    #[serde(default)]
+    pub stop: Option<u64>,
+
+    #[serde(default)]
+    pub active: bool,
+
+    #[serde(flatten)]
+    pub details: EntryDetails,
+
+    /// Repository, branch and commit the entry was started in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitInfo>,
+
+    /// Number of the invoice the entry was billed on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invoice: Option<String>,
+}
+
+impl Entry {
+    pub fn new(start: u64, is_active: bool, details: EntryDetails) -> Self {
+        Self { id: 0, start, stop: None, active: is_active, details, git: None, invoice: None }
+    }
+
+    /// Time covered by the entry, counting a running entry up to `now`.
+    pub fn elapsed(&self, now: u64) -> Duration {
+        let stop = self.stop.unwrap_or(now);
+        Duration::seconds(stop.saturating_sub(self.start) as i64)
+    }
+
+    /// Whether the entry is a session derived from heartbeats. Those are not stored and have
+    /// no id, while stored entries are numbered from 1.
+    pub fn is_session(&self) -> bool {
+        self.id == 0
+    }
+
+    /// How the entry is referred to in listings, e.g. "#3", or "session" for heartbeat sessions.
+    pub fn label(&self) -> String {
+        if self.is_session() { "session".to_owned() } else { format!("#{}", self.id) }
+    }
+
+    /// The git branch the entry was started on, if any.
+    pub fn branch(&self) -> Option<&str> {
+        self.git.as_ref().and_then(|git| git.branch.as_deref())
+    }
+
+    /// Time the entry spends inside `[from, to)`, counting a running entry up to `now`.
+    pub fn overlap(&self, from: u64, to: u64, now: u64) -> Duration {
+        let start = self.start.max(from);
+        let stop = self.stop.unwrap_or(now).min(to);
+        Duration::seconds(stop.saturating_sub(start) as i64)
+    }
+}
+
+/// Entries as found on disk. Older databases stored `[start, stop, active]` tuples.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum StoredEntries {
+    Current(Vec<Entry>),
+    Legacy(Vec<(u64, Option<u64>, bool)>),
+}
+
+impl From<StoredEntries> for Vec<Entry> {
+    fn from(stored: StoredEntries) -> Self {
+        match stored {
+            StoredEntries::Current(entries) => entries,
+            StoredEntries::Legacy(tuples) =>
+                tuples
+                    .into_iter()
+                    .map(|(start, stop, active)| Entry {
+                        start,
+                        stop,
+                        active,
+                        ..Entry::default()
+                    })
+                    .collect(),
+        }
+    }
+}
+
+/// Gives every entry without an id the next free one, in order.
+pub fn assign_ids(entries: &mut [Entry]) {
+    let first_id = next_id(entries);
+    let unassigned = entries.iter_mut().filter(|entry| entry.id == 0);
+    for (id, entry) in (first_id..).zip(unassigned) {
+        entry.id = id;
+    }
+}
+
+/// The id for the next entry stored alongside `entries`.
+pub fn next_id(entries: &[Entry]) -> u64 {
+    entries
+        .iter()
+        .map(|entry| entry.id)
+        .max()
+        .unwrap_or(0) + 1
+}
+
+pub fn local_time(timestamp: u64) -> DateTime<Local> {
+    Utc.timestamp_opt(timestamp as i64, 0)
+        .single()
+        .expect("Invalid or ambiguous timestamp")
+        .with_timezone(&Local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(project: Option<&str>, tags: &[&str], note: Option<&str>) -> EntryDetails {
+        EntryDetails {
+            project: project.map(str::to_owned),
+            tags: tags
+                .iter()
+                .map(|tag| tag.to_string())
+                .collect(),
+            note: note.map(str::to_owned),
+            billable: None,
+        }
+    }
+
+    #[test]
+    fn combining_details_unions_tags_and_joins_notes() {
+        let parts = [
+            details(Some("web"), &["css", "ui"], Some("navbar")),
+            details(None, &["ui", "a11y"], Some("navbar")),
+            details(Some("web"), &[], Some("footer")),
+        ];
+        assert_eq!(
+            EntryDetails::combine(&parts),
+            Some(details(Some("web"), &["css", "ui", "a11y"], Some("navbar; footer")))
+        );
+    }
+
+    #[test]
+    fn combining_details_of_different_projects_fails() {
+        let parts = [details(Some("web"), &[], None), details(Some("api"), &[], None)];
+        assert_eq!(EntryDetails::combine(&parts), None);
+        let billable = EntryDetails { billable: Some(true), ..EntryDetails::default() };
+        let unbillable = EntryDetails { billable: Some(false), ..EntryDetails::default() };
+        assert_eq!(EntryDetails::combine([&billable, &unbillable]), None);
+    }
+
+    #[test]
+    fn legacy_entries_get_sequential_ids() {
+        let stored: StoredEntries = serde_json::from_str("[[10,20,false],[30,null,true]]").unwrap();
+        let mut entries: Vec<Entry> = stored.into();
+        assign_ids(&mut entries);
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.id, entry.start, entry.stop, entry.active))
+                .collect::<Vec<_>>(),
+            vec![(1, 10, Some(20), false), (2, 30, None, true)]
+        );
+    }
+}
@@ -1,12 +1,58 @@
-use std::{ fs::{ self, File, OpenOptions }, io::{ Seek, SeekFrom }, path::{ Path, PathBuf } };
+use std::{
+    collections::{ BTreeMap, BTreeSet },
+    fs::{ self, File, OpenOptions },
+    io::{ IsTerminal, Seek, SeekFrom },
+    path::{ Path, PathBuf },
+    time::{ SystemTime, UNIX_EPOCH },
+};
 use fs2::FileExt;
-use serde::{ Deserialize, Serialize };
 use thiserror::Error;
 use std::io::{ Read, Write };
-use chrono::{ Local, TimeZone, Utc };
+use chrono::{ DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime };
 use serde_json;
 use error_stack::Report;
 
+use crate::feature::{
+    aggregate,
+    balance::{ self, BalanceError, BalancePeriod },
+    billing::{ self, Amounts, RateTarget },
+    budget::{ Budget, BudgetError, Limit, DEFAULT_THRESHOLDS },
+    client::{ self, Client, ClientChanges, ClientError },
+    day_boundary::{ self, DayBoundary },
+    duration::{ self, DurationFormat },
+    export::{
+        self,
+        BalanceRecord,
+        BillingRecord,
+        BudgetRecord,
+        BurndownRecord,
+        ClientRecord,
+        DataFormat,
+        DayOffRecord,
+        EntryRecord,
+        GroupRecord,
+        ProjectRecord,
+        RateRecord,
+        Record,
+        RowRecord,
+    },
+    git::{ CommitRecord, GitInfo },
+    project::{ self, ProjectError },
+    heartbeat::{ self, Heartbeat },
+    invoice::{ self, InvoiceError, InvoiceOptions, Period },
+    report::{ self, BillingRow, Breakdown, ReportOptions },
+    render::{ self, ReportFormat, Table, TableRow, Terminal },
+    session_guard::{ self, CapChoice, CapPolicy },
+    settings::{ Settings, SettingsError },
+    string_ext::StringExt,
+    target,
+    time_off::{ self, DayOff, OffKind, TimeOffError },
+};
+use super::{
+    database::{ Database, StoredDatabase },
+    entry::{ local_time, next_id, Entry, EntryDetails },
+};
+
 #[derive(Error, Debug)]
 pub enum FlatFileError {
     #[error("timer is already running")]
@@ -31,30 +77,76 @@ pub enum FlatFileError {
         #[source] serde_json::Error,
     ),
 
+    #[error("the end of a time range must be after its start")]
+    InvalidTimeRange,
+
+    #[error("time is in the future")]
+    FutureTime,
+
+    #[error("entry not found")]
+    EntryNotFound,
+
+    #[error("entries cannot be merged")]
+    MergeConflict,
+
+    #[error("entry was already invoiced")]
+    Invoiced,
+
+    #[error("invalid setting")]
+    InvalidSetting,
+
+    #[error("the repository has no commits")]
+    NoCommit,
+
+    #[error("invalid project")]
+    InvalidProject,
+
+    #[error("invalid client")]
+    InvalidClient,
+
+    #[error("failed to create the invoice")]
+    InvoiceFailed,
+
+    #[error("invalid budget")]
+    InvalidBudget,
+
+    #[error("invalid schedule")]
+    InvalidSchedule,
+
+    #[error("invalid days off")]
+    InvalidTimeOff,
+
+    #[error("failed to format the output")]
+    ExportFailed,
+
     #[error("something went wrong")] SomethingWentWrong,
 }
 pub struct FlatFileTracker {
     db_dir: PathBuf,
     lockfile: PathBuf,
-}
-
-#[derive(Clone, Serialize, Deserialize)]
-struct Timestamp(u64, Option<u64>, bool);
-
-impl Timestamp {
-    fn new(timstamp: u64, is_active: bool) -> Self {
-        Self(timstamp, None, is_active)
-    }
+    heartbeats: PathBuf,
 }
 
 impl FlatFileTracker {
     pub fn new<D, L>(db_dir: D, lockfile: L) -> Self where D: Into<PathBuf>, L: Into<PathBuf> {
         let db_dir = db_dir.into();
         let lockfile = lockfile.into();
-        Self { db_dir, lockfile }
+        // Heartbeats are appended to `<db>.heartbeats.jsonl` next to the database.
+        let heartbeats = db_dir.with_extension("heartbeats.jsonl");
+        Self { db_dir, lockfile, heartbeats }
     }
 
     pub fn start(&self, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+        self.start_at(Local::now(), EntryDetails::default(), None, verbosity)
+    }
+
+    pub fn start_at(
+        &self,
+        at: DateTime<Local>,
+        details: EntryDetails,
+        git: Option<GitInfo>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
         if self.is_running() {
             return Err(
                 Report::new(FlatFileError::ActiveTimer).attach_printable(
@@ -62,30 +154,24 @@ impl FlatFileTracker {
                 )
             );
         }
-        self.open_lockfile()?;
-        let now = Local::now();
-        let timestamp = Timestamp::new(now.timestamp() as u64, true);
+        reject_future(at)?;
+        let details = normalize_project(details)?;
+        let mut lockfile = self.open_lockfile()?;
+        let mut entry = Entry::new(at.timestamp() as u64, true, details);
+        entry.git = git;
         let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let data = &mut db.entries;
+        entry.id = next_id(data);
 
-        let mut buffer = String::new();
-        db_file.read_to_string(&mut buffer).map_err(|e| Report::new(FlatFileError::ReadError(e)))?;
-
-        let data: Vec<Timestamp> = if buffer.trim().is_empty() {
-            vec![timestamp]
-        } else {
-            let mut parsed_data: Vec<Timestamp> = serde_json
-                ::from_str(&buffer)
-                .map_err(|e| Report::new(FlatFileError::JsonParseError(e)))?;
-            let last_index = parsed_data.len() - 1;
-            let last_active_option = parsed_data.get_mut(last_index);
-            if let Some(last_active_timestamp) = last_active_option {
-                last_active_timestamp.2 = false;
-            }
-            parsed_data.push(timestamp);
-            parsed_data
-        };
+        if let Some(last_active_entry) = data.last_mut() {
+            last_active_entry.active = false;
+        }
+        self.write_lockfile_state(&mut lockfile, &entry)?;
+        let project = entry.details.project.clone();
+        data.push(entry);
 
-        self.save_file(&mut db_file, data)?;
+        self.save_file(&mut db_file, &db)?;
 
         match verbosity {
             v if v <= -1 => {}
@@ -93,19 +179,58 @@ impl FlatFileTracker {
                 println!("timer started successfully.");
             }
             _ => {
-                println!("timer started at {}.", now.format("%Y-%m-%d %H:%M:%S"));
+                println!("timer started at {}.", at.format("%Y-%m-%d %H:%M:%S"));
             }
         }
+        self.warn_about_budgets(&db, project.as_deref(), verbosity)?;
 
         Ok(())
     }
 
     pub fn stop(&self, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+        self.stop_at(Local::now(), verbosity)
+    }
+
+    pub fn stop_at(
+        &self,
+        at: DateTime<Local>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
         if !self.is_running() {
             return Err(
                 Report::new(FlatFileError::InactiveTimer).attach_printable("timer is not running")
             );
         }
+        reject_future(at)?;
+
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let data = &mut db.entries;
+
+        let Some(position) = data.iter().rposition(|entry| entry.active) else {
+            return Err(
+                Report::new(
+                    FlatFileError::DbFileError(
+                        std::io::Error::new(std::io::ErrorKind::InvalidData, "no record found")
+                    )
+                )
+            );
+        };
+        let active_entry = &mut data[position];
+        let timestamp = at.timestamp() as u64;
+        if timestamp < active_entry.start {
+            return Err(
+                Report::new(FlatFileError::InvalidTimeRange).attach_printable(
+                    "the timer cannot be stopped before it was started"
+                )
+            );
+        }
+        active_entry.stop = Some(timestamp);
+        active_entry.active = false;
+        let project = active_entry.details.project.clone();
+        if db.settings.day_boundary == DayBoundary::Split {
+            split_stored_entry(&mut db.entries, position, db.settings.day_start);
+        }
 
         let lockfile = OpenOptions::new()
             .write(true)
@@ -140,195 +265,2039 @@ impl FlatFileTracker {
                 )
             )?;
 
-        let now = Local::now();
-        let timestamp = now.timestamp() as u64;
+        self.save_file(&mut db_file, &db)?;
 
-        let mut buffer = String::new();
-        let mut db_file = self.open_db()?;
-        db_file.read_to_string(&mut buffer).map_err(|e| Report::new(FlatFileError::ReadError(e)))?;
+        match verbosity {
+            v if v <= -1 => {}
+            0 => {
+                println!("timer stopped successfully.");
+            }
+            _ => {
+                println!("timer stopped at {}.", at.format("%Y-%m-%d %H:%M:%S"));
+            }
+        }
+        self.warn_about_budgets(&db, project.as_deref(), verbosity)?;
+        Ok(())
+    }
 
-        let data: Vec<Timestamp> = if buffer.trim().is_empty() {
+    /// Starts a new timer with the project, tags and note of the entry `id`, or of the
+    /// most recent entry when no id is given.
+    pub fn continue_entry(
+        &self,
+        id: Option<u64>,
+        at: DateTime<Local>,
+        git: Option<GitInfo>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        if self.is_running() {
             return Err(
-                Report::new(
-                    FlatFileError::DbFileError(
-                        std::io::Error::new(std::io::ErrorKind::InvalidData, "no record found")
-                    )
+                Report::new(FlatFileError::ActiveTimer).attach_printable(
+                    "a timer is already running"
                 )
             );
-        } else {
-            let mut parsed_data: Vec<Timestamp> = serde_json
-                ::from_str(&buffer)
-                .map_err(|e| Report::new(FlatFileError::JsonParseError(e)))?;
+        }
 
-            if parsed_data.is_empty() {
-                return Err(
-                    Report::new(
-                        FlatFileError::DbFileError(
-                            std::io::Error::new(std::io::ErrorKind::InvalidData, "no record found")
-                        )
-                    )
-                );
-            }
-            let last_index = parsed_data.len() - 1;
-            let last_active_option = parsed_data.get_mut(last_index);
-            if let Some(last_active_timestamp) = last_active_option {
-                last_active_timestamp.1 = Some(timestamp);
-                last_active_timestamp.2 = false;
-            }
+        let mut db_file = self.open_db()?;
+        let db = self.read_db(&mut db_file)?;
+        let data = db.entries;
+        drop(db_file);
 
-            parsed_data
+        let source = match id {
+            Some(id) => data.into_iter().find(|entry| entry.id == id),
+            None => data.into_iter().max_by_key(|entry| entry.start),
+        };
+        let Some(source) = source else {
+            let message = match id {
+                Some(id) => format!("no entry with id {id}"),
+                None => "there is no previous entry to continue".to_owned(),
+            };
+            return Err(Report::new(FlatFileError::EntryNotFound).attach_printable(message));
         };
 
-        self.save_file(&mut db_file, data)?;
+        self.start_at(at, source.details, git, verbosity)
+    }
+
+    /// Records a finished entry between `from` and `to` without touching the running timer.
+    pub fn add(
+        &self,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+        details: EntryDetails,
+        duration_format: DurationFormat,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        if to <= from {
+            return Err(
+                Report::new(FlatFileError::InvalidTimeRange).attach_printable(
+                    format!(
+                        "{} is not after {}",
+                        to.format("%Y-%m-%d %H:%M:%S"),
+                        from.format("%Y-%m-%d %H:%M:%S")
+                    )
+                )
+            );
+        }
+        reject_future(to)?;
+        let details = normalize_project(details)?;
+
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let data = &mut db.entries;
+
+        let mut entry = Entry::new(from.timestamp() as u64, false, details);
+        entry.id = next_id(data);
+        entry.stop = Some(to.timestamp() as u64);
+        let position = data.partition_point(|existing| existing.start <= entry.start);
+        data.insert(position, entry);
+        if db.settings.day_boundary == DayBoundary::Split {
+            split_stored_entry(&mut db.entries, position, db.settings.day_start);
+        }
+
+        self.save_file(&mut db_file, &db)?;
 
         match verbosity {
             v if v <= -1 => {}
             0 => {
-                println!("timer started successfully.");
+                println!("entry added successfully.");
             }
             _ => {
-                println!("timer stopped at {}.", now.format("%Y-%m-%d %H:%M:%S"));
+                println!(
+                    "entry added from {} to {} ({}).",
+                    from.format("%Y-%m-%d %H:%M:%S"),
+                    to.format("%Y-%m-%d %H:%M:%S"),
+                    duration::format(to - from, duration_format)
+                );
             }
         }
         Ok(())
     }
 
-    pub fn report(&self, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+    /// Splits the finished entry `id` at `at` into two entries with the same details. Invoiced
+    /// entries are left alone, their invoice lists them as they are.
+    pub fn split(
+        &self,
+        id: u64,
+        at: DateTime<Local>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
         let mut db_file = self.open_db()?;
-        let mut buffer = String::new();
-        db_file.read_to_string(&mut buffer).map_err(|e| Report::new(FlatFileError::ReadError(e)))?;
+        let mut db = self.read_db(&mut db_file)?;
+        let data = &mut db.entries;
+        let new_id = next_id(data);
 
-        let now = Local::now();
-        let timestamp = now.timestamp() as u64;
-        const TWENTY_FOUR_HOURS_IN_MILLISECONDS: u64 = 24 * 60 * 60 * 1000;
-        if buffer.trim().is_empty() {
+        let Some(position) = data.iter().position(|entry| entry.id == id) else {
             return Err(
-                Report::new(
-                    FlatFileError::ReadError(
-                        std::io::Error::new(
-                            std::io::ErrorKind::UnexpectedEof,
-                            "The database file is empty"
-                        )
-                    )
+                Report::new(FlatFileError::EntryNotFound).attach_printable(
+                    format!("no entry with id {id}")
                 )
             );
-        } else {
-            let parsed_data: Vec<Timestamp> = serde_json
-                ::from_str(&buffer)
-                .map_err(|e| Report::new(FlatFileError::JsonParseError(e)))?;
-            if parsed_data.len() == 0 {
-                println!("no records found");
-            } else {
-                parsed_data
-                    .into_iter()
-                    .filter(
-                        |ts|
-                            timestamp - ts.0 < TWENTY_FOUR_HOURS_IN_MILLISECONDS &&
-                            ts.1 != None &&
-                            ts.2 != true
+        };
+        let entry = &mut data[position];
+        let Some(stop) = entry.stop else {
+            return Err(
+                Report::new(FlatFileError::ActiveTimer).attach_printable(
+                    "a running entry cannot be split"
+                )
+            );
+        };
+        if let Some(number) = &entry.invoice {
+            return Err(
+                Report::new(FlatFileError::Invoiced).attach_printable(
+                    format!("entry {id} was billed on invoice {number}")
+                )
+            );
+        }
+        let split_at = at.timestamp() as u64;
+        if split_at <= entry.start || split_at >= stop {
+            return Err(
+                Report::new(FlatFileError::InvalidTimeRange).attach_printable(
+                    format!(
+                        "{} is not between the start and stop of entry {id}",
+                        at.format("%Y-%m-%d %H:%M:%S")
                     )
-                    .try_for_each(
-                        |ts| -> Result<(), Report<FlatFileError>> {
-                            let start = self.format_timestamp(ts.0, verbosity);
-                            let stop_timestamp = ts.1.ok_or_else(||
-                                Report::new(FlatFileError::SomethingWentWrong)
-                            )?;
-                            let stop = self.format_timestamp(stop_timestamp, verbosity);
-                            println!("started timer at {} and stopped timer at {}", start, stop);
-                            Ok(())
-                        }
-                    )?;
-            }
+                )
+            );
         }
 
+        let mut second = entry.clone();
+        second.id = new_id;
+        second.start = split_at;
+        entry.stop = Some(split_at);
+        data.insert(position + 1, second);
+
+        self.save_file(&mut db_file, &db)?;
+
+        match verbosity {
+            v if v <= -1 => {}
+            0 => {
+                println!("entry split successfully.");
+            }
+            _ => {
+                println!(
+                    "entry #{id} split at {} into #{id} and #{new_id}.",
+                    at.format("%Y-%m-%d %H:%M:%S")
+                );
+            }
+        }
         Ok(())
     }
 
-    fn format_timestamp(&self, timestamp: u64, verbosity: i8) -> String {
-        let utc_datetime = Utc.timestamp_opt(timestamp as i64, 0)
-            .single()
-            .expect("Invalid or ambiguous timestamp");
+    /// Merges finished entries that follow each other in the timeline into the earliest one.
+    /// The entries must not overlap, no other entry may lie between them and the gap between
+    /// two consecutive entries must not exceed `max_gap`, and none may have been invoiced.
+    /// Details are combined with [`EntryDetails::combine`].
+    pub fn merge(
+        &self,
+        ids: &[u64],
+        max_gap: Duration,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        let conflict = |message: String| {
+            Report::new(FlatFileError::MergeConflict).attach_printable(message)
+        };
+        if ids.len() < 2 {
+            return Err(conflict("at least two entries are required".to_owned()));
+        }
 
-        let local_datetime = utc_datetime.with_timezone(&Local);
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let data = &mut db.entries;
+        data.sort_by_key(|entry| entry.start);
 
-        let format_str = if verbosity >= 1 { "%Y-%m-%d %H:%M:%S" } else { "%Y-%m-%d" };
+        let mut positions = Vec::with_capacity(ids.len());
+        for id in ids {
+            let Some(position) = data.iter().position(|entry| entry.id == *id) else {
+                return Err(
+                    Report::new(FlatFileError::EntryNotFound).attach_printable(
+                        format!("no entry with id {id}")
+                    )
+                );
+            };
+            if data[position].stop.is_none() {
+                return Err(
+                    Report::new(FlatFileError::ActiveTimer).attach_printable(
+                        "a running entry cannot be merged"
+                    )
+                );
+            }
+            if let Some(number) = &data[position].invoice {
+                return Err(
+                    Report::new(FlatFileError::Invoiced).attach_printable(
+                        format!("entry {id} was billed on invoice {number}")
+                    )
+                );
+            }
+            if positions.contains(&position) {
+                return Err(conflict(format!("entry {id} is listed more than once")));
+            }
+            positions.push(position);
+        }
+        positions.sort_unstable();
 
-        local_datetime.format(format_str).to_string()
+        for pair in positions.windows(2) {
+            let (previous, next) = (&data[pair[0]], &data[pair[1]]);
+            if pair[1] != pair[0] + 1 {
+                return Err(
+                    conflict(
+                        format!("entries {} and {} are not adjacent", previous.id, next.id)
+                    )
+                );
+            }
+            let previous_stop = previous.stop.unwrap_or(previous.start);
+            if next.start < previous_stop {
+                return Err(conflict(format!("entries {} and {} overlap", previous.id, next.id)));
+            }
+            if Duration::seconds((next.start - previous_stop) as i64) > max_gap {
+                return Err(
+                    conflict(
+                        format!(
+                            "the gap between entries {} and {} is longer than {}",
+                            previous.id,
+                            next.id,
+                            duration::format(max_gap, DurationFormat::Compact)
+                        )
+                    )
+                );
+            }
+        }
+
+        let first = positions[0];
+        let last = positions[positions.len() - 1];
+        let details = EntryDetails::combine(data[first..=last].iter().map(|entry| &entry.details));
+        let Some(details) = details else {
+            return Err(conflict("the entries belong to different projects".to_owned()));
+        };
+        let stop = data[last].stop;
+        let merged_ids: Vec<String> = data[first..=last]
+            .iter()
+            .map(|entry| format!("#{}", entry.id))
+            .collect();
+        data.drain(first + 1..=last);
+        let merged = &mut data[first];
+        merged.stop = stop;
+        merged.details = details;
+        let merged_id = merged.id;
+
+        self.save_file(&mut db_file, &db)?;
+
+        match verbosity {
+            v if v <= -1 => {}
+            0 => {
+                println!("entries merged successfully.");
+            }
+            _ => {
+                println!("entries {} merged into #{merged_id}.", merged_ids.join(", "));
+            }
+        }
+        Ok(())
     }
 
-    fn open_db(&self) -> Result<File, Report<FlatFileError>> {
-        let db = OpenOptions::new()
-            .write(true)
+    /// Appends a heartbeat to the heartbeat store.
+    pub fn heartbeat(
+        &self,
+        heartbeat: &Heartbeat,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        let mut heartbeat = heartbeat.clone();
+        if let Some(path) = heartbeat.project.as_mut() {
+            *path = project::normalize(path).map_err(|e| e.change_context(FlatFileError::InvalidProject))?;
+        }
+        let mut line = serde_json
+            ::to_string(&heartbeat)
+            .map_err(|e| Report::new(FlatFileError::JsonSerializeError(e)))?;
+        line.push('\n');
+
+        let mut heartbeats = OpenOptions::new()
             .create(true)
-            .read(true)
-            .open(&self.db_dir)
+            .append(true)
+            .open(&self.heartbeats)
             .map_err(|e|
                 Report::new(FlatFileError::DbFileError(e)).attach_printable(
-                    "failed to open db file"
+                    "failed to open the heartbeat file"
                 )
             )?;
-
-        Ok(db)
-    }
-
-    fn open_lockfile(&self) -> Result<(), Report<FlatFileError>> {
-        OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(&self.lockfile)
-            .map_err(|e|
-                Report::new(FlatFileError::ReadError(e)).attach_printable("failed to open lockfile")
-            )?
+        heartbeats
             .lock_exclusive()
+            .map_err(|e| Report::new(FlatFileError::LockFileError(e)))?;
+        heartbeats
+            .write_all(line.as_bytes())
             .map_err(|e|
-                Report::new(FlatFileError::ReadError(e)).attach_printable(
-                    "failed to acquire exclusive lock"
+                Report::new(FlatFileError::WriteError(e)).attach_printable(
+                    "failed to write to the heartbeat file"
                 )
             )?;
+        heartbeats.unlock().map_err(|e| Report::new(FlatFileError::LockFileError(e)))?;
 
+        if verbosity >= 1 {
+            println!("heartbeat recorded at {}.", self.format_timestamp(heartbeat.time, 1));
+        }
         Ok(())
     }
 
-    fn save_file(
+    /// Prints the time tracked in the repository of `git` since its last recorded commit.
+    pub fn commit_spent(
         &self,
-        db_file: &mut File,
-        data: Vec<Timestamp>
-    ) -> Result<(), Report<FlatFileError>> {
-        db_file
-            .set_len(0)
-            .map_err(|e|
-                Report::new(FlatFileError::DbFileError(e)).attach_printable(
-                    "failed to truncate the file"
-                )
-            )?;
-        db_file
-            .seek(SeekFrom::Start(0))
-            .map_err(|e|
-                Report::new(FlatFileError::DbFileError(e)).attach_printable(
-                    "failed to find the first line"
-                )
-            )?;
+        git: &GitInfo,
+        at: DateTime<Local>,
+        verbosity: i8
+    ) -> Result<Duration, Report<FlatFileError>> {
+        let mut db_file = self.open_db()?;
+        let db = self.read_db(&mut db_file)?;
+        let spent = self.spent_since_last_commit(&db, &git.repository, at.timestamp() as u64);
+        if verbosity > -1 {
+            println!("{}", duration::format(spent, DurationFormat::Compact));
+        }
+        Ok(spent)
+    }
 
-        let json_data = serde_json
-            ::to_string(&data)
-            .map_err(|e|
-                Report::new(FlatFileError::JsonParseError(e)).attach_printable(
-                    "failed to serialize data to JSON"
-                )
-            )?;
-        db_file
-            .write_all(json_data.as_bytes())
-            .map_err(|e|
-                Report::new(FlatFileError::WriteError(e)).attach_printable(
-                    "failed to write to database"
-                )
-            )?;
+    /// Records the HEAD commit of `git` with the time tracked in its repository since the
+    /// previous recorded commit, and prints that time.
+    pub fn record_commit(
+        &self,
+        git: GitInfo,
+        at: DateTime<Local>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        let Some(commit) = git.commit else {
+            return Err(Report::new(FlatFileError::NoCommit));
+        };
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let time = at.timestamp() as u64;
+        let spent = self.spent_since_last_commit(&db, &git.repository, time);
+        db.commits.push(CommitRecord {
+            commit,
+            repository: git.repository,
+            branch: git.branch,
+            time,
+            spent: spent.num_seconds(),
+        });
+        self.save_file(&mut db_file, &db)?;
+        if verbosity > -1 {
+            println!("{}", duration::format(spent, DurationFormat::Compact));
+        }
         Ok(())
     }
 
-    pub fn is_running(&self) -> bool {
-        if Path::new(&self.lockfile).exists() { true } else { false }
+    fn spent_since_last_commit(&self, db: &Database, repository: &str, at: u64) -> Duration {
+        let since = db.commits
+            .iter()
+            .filter(|record| record.repository == repository && record.time <= at)
+            .map(|record| record.time)
+            .max()
+            .unwrap_or(0);
+        let now = Local::now().timestamp() as u64;
+        db.entries
+            .iter()
+            .filter(|entry| entry.git.as_ref().is_some_and(|git| git.repository == repository))
+            .map(|entry| entry.overlap(since, at, now))
+            .sum()
+    }
+
+    fn read_heartbeats(&self) -> Result<Vec<Heartbeat>, Report<FlatFileError>> {
+        let buffer = match fs::read_to_string(&self.heartbeats) {
+            Ok(buffer) => buffer,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Vec::new());
+            }
+            Err(e) => {
+                return Err(
+                    Report::new(FlatFileError::ReadError(e)).attach_printable(
+                        "failed to read the heartbeat file"
+                    )
+                );
+            }
+        };
+        parse_heartbeats(&buffer)
+    }
+
+    /// Moves the heartbeats of `from` and its subprojects below `to`, rewriting the heartbeat
+    /// file while it is locked. Returns how many heartbeats were moved.
+    fn move_heartbeats(&self, from: &str, to: &str) -> Result<usize, Report<FlatFileError>> {
+        let mut file = match OpenOptions::new().read(true).write(true).open(&self.heartbeats) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(0);
+            }
+            Err(e) => {
+                return Err(
+                    Report::new(FlatFileError::DbFileError(e)).attach_printable(
+                        "failed to open the heartbeat file"
+                    )
+                );
+            }
+        };
+        file.lock_exclusive().map_err(|e| Report::new(FlatFileError::LockFileError(e)))?;
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer).map_err(|e| Report::new(FlatFileError::ReadError(e)))?;
+        let mut heartbeats = parse_heartbeats(&buffer)?;
+        let mut moved_heartbeats = 0;
+        for heartbeat in &mut heartbeats {
+            let moved = heartbeat.project
+                .as_deref()
+                .and_then(|path| project::moved(path, from, to));
+            if moved.is_some() {
+                heartbeat.project = moved;
+                moved_heartbeats += 1;
+            }
+        }
+        if moved_heartbeats > 0 {
+            let mut lines = String::new();
+            for heartbeat in &heartbeats {
+                let line = serde_json
+                    ::to_string(heartbeat)
+                    .map_err(|e| Report::new(FlatFileError::JsonSerializeError(e)))?;
+                lines.push_str(&line);
+                lines.push('\n');
+            }
+            file.set_len(0).map_err(|e| Report::new(FlatFileError::DbFileError(e)))?;
+            file.seek(SeekFrom::Start(0)).map_err(|e| Report::new(FlatFileError::DbFileError(e)))?;
+            file.write_all(lines.as_bytes()).map_err(|e|
+                Report::new(FlatFileError::WriteError(e)).attach_printable(
+                    "failed to write to the heartbeat file"
+                )
+            )?;
+        }
+        file.unlock().map_err(|e| Report::new(FlatFileError::LockFileError(e)))?;
+        Ok(moved_heartbeats)
+    }
+
+    /// Stored entries followed by the sessions derived from heartbeats.
+    fn entries_with_sessions(&self, db: &Database) -> Result<Vec<Entry>, Report<FlatFileError>> {
+        let heartbeats = self.read_heartbeats()?;
+        let mut entries = db.entries.clone();
+        entries.extend(heartbeat::sessions(&heartbeats, db.settings.idle_timeout()));
+        Ok(entries)
+    }
+
+    /// Prints the total time tracked in the last 24 hours or the requested range, preceded by
+    /// the individual entries when `verbosity` is at least 1, by the requested breakdown, or
+    /// by the nested groups with their subtotals.
+    pub fn report(&self, options: &ReportOptions, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+        let duration_format = options.duration_format;
+        let mut db_file = self.open_db()?;
+        let buffer = self.read_buffer(&mut db_file)?;
+
+        let now = Local::now();
+        let timestamp = now.timestamp() as u64;
+        const TWENTY_FOUR_HOURS_IN_SECONDS: u64 = 24 * 60 * 60;
+        let to = options.to.unwrap_or(timestamp);
+        let from = options.from.unwrap_or_else(|| to.saturating_sub(TWENTY_FOUR_HOURS_IN_SECONDS));
+        if from >= to {
+            return Err(
+                Report::new(FlatFileError::InvalidTimeRange).attach_printable(
+                    "the end of the report must be after its start"
+                )
+            );
+        }
+        let on_branch = |branch: Option<&str>| {
+            options.branch.as_deref().is_none_or(|filter| branch == Some(filter))
+        };
+        let data_format = options.format.and_then(ReportFormat::data_format);
+        if options.billing {
+            let db = self.parse_db(&buffer)?;
+            let entries: Vec<Entry> = self
+                .entries_with_sessions(&db)?
+                .into_iter()
+                .filter(|entry| on_branch(entry.branch()))
+                .collect();
+            let by = options.breakdown.unwrap_or(Breakdown::Client);
+            let billing = db.billing();
+            let rows = report::billing(&entries, by, &billing, from, to, timestamp);
+            if let Some(format) = data_format {
+                let records: Vec<BillingRecord> = rows.iter().map(BillingRecord::from).collect();
+                return self.print_records(&records, BillingRecord::COLUMNS, format);
+            }
+            let key = render::value_name(by);
+            let table = self.billing_table(format!("Billing by {key}"), &key, rows, billing.is_rounded(), duration_format);
+            self.print_table(table, options, from, to);
+            return Ok(());
+        }
+        if !options.group_by.is_empty() {
+            let db = self.parse_db(&buffer)?;
+            let entries: Vec<Entry> = self
+                .entries_with_sessions(&db)?
+                .into_iter()
+                .filter(|entry| on_branch(entry.branch()))
+                .collect();
+            let day_start = db.settings.day_start;
+            let mut total = aggregate::aggregate(&entries, &options.group_by, &db.clients, day_start, from, to, timestamp);
+            if let Some(format) = data_format {
+                let records = GroupRecord::from_group(&total, options.group_by.len());
+                return self.print_records(&records, &GroupRecord::columns(&options.group_by), format);
+            }
+            let (first, last) = (day_boundary::day_of(from, day_start), day_boundary::day_of(to - 1, day_start));
+            aggregate::mark_days_off(&mut total, &options.group_by, &db.days_off, first, last);
+            let levels: Vec<String> = options.group_by.iter().copied().map(render::value_name).collect();
+            let rows = total
+                .flatten()
+                .into_iter()
+                .map(|(depth, group)| (depth, group.key.clone(), group.time))
+                .collect();
+            let title = format!("Time by {}", levels.join(", "));
+            let table = self.time_table(title, &levels.join(" / "), rows, total.time, duration_format);
+            self.print_table(table, options, from, to);
+            return Ok(());
+        }
+        if let Some(by) = options.breakdown {
+            let db = self.parse_db(&buffer)?;
+            let rows = match by {
+                Breakdown::Project => {
+                    let entries: Vec<Entry> = self
+                        .entries_with_sessions(&db)?
+                        .into_iter()
+                        .filter(|entry| on_branch(entry.branch()))
+                        .collect();
+                    let paths = report::project_paths(&entries, from, to, timestamp, &options.expansion);
+                    if let Some(format) = data_format {
+                        let records: Vec<RowRecord> = paths
+                            .iter()
+                            .map(|(path, time)| RowRecord::new(path, *time))
+                            .collect();
+                        return self.print_records(&records, RowRecord::COLUMNS, format);
+                    }
+                    let total = entries
+                        .iter()
+                        .map(|entry| entry.overlap(from, to, timestamp))
+                        .sum();
+                    let rows = paths
+                        .iter()
+                        .map(|(path, time)| (project::depth(path), project::name(path).to_owned(), *time))
+                        .collect();
+                    let table = self.time_table("Time by project".to_owned(), "project", rows, total, duration_format);
+                    self.print_table(table, options, from, to);
+                    return Ok(());
+                }
+                Breakdown::Client => {
+                    let entries: Vec<Entry> = self
+                        .entries_with_sessions(&db)?
+                        .into_iter()
+                        .filter(|entry| on_branch(entry.branch()))
+                        .collect();
+                    report::clients(&entries, &db.clients, from, to, timestamp)
+                }
+                Breakdown::Branch => {
+                    let entries: Vec<Entry> = db.entries
+                        .into_iter()
+                        .filter(|entry| on_branch(entry.branch()))
+                        .collect();
+                    report::branches(&entries, from, to, timestamp)
+                }
+                Breakdown::Commit => {
+                    let commits: Vec<CommitRecord> = db.commits
+                        .into_iter()
+                        .filter(|record| on_branch(record.branch.as_deref()))
+                        .collect();
+                    report::commits(&commits, from, to)
+                }
+                Breakdown::Language | Breakdown::Directory | Breakdown::File => {
+                    let heartbeats = self.read_heartbeats()?;
+                    let spans = heartbeat::spans(&heartbeats, db.settings.idle_timeout());
+                    report::breakdown(&spans, by, from, to)
+                }
+            };
+            let total: Duration = rows
+                .iter()
+                .map(|(_, time)| *time)
+                .sum();
+            let rows = match options.top {
+                Some(n) => report::top(rows, n),
+                None => rows,
+            };
+            if let Some(format) = data_format {
+                let records: Vec<RowRecord> = rows
+                    .iter()
+                    .map(|(key, time)| RowRecord::new(key, *time))
+                    .collect();
+                return self.print_records(&records, RowRecord::COLUMNS, format);
+            }
+            let key = render::value_name(by);
+            let rows = rows
+                .into_iter()
+                .map(|(key, time)| (0, key, time))
+                .collect();
+            let table = self.time_table(format!("Time by {key}"), &key, rows, total, duration_format);
+            self.print_table(table, options, from, to);
+            return Ok(());
+        }
+        if buffer.trim().is_empty() && !self.heartbeats.exists() {
+            return Err(
+                Report::new(
+                    FlatFileError::ReadError(
+                        std::io::Error::new(
+                            std::io::ErrorKind::UnexpectedEof,
+                            "The database file is empty"
+                        )
+                    )
+                )
+            );
+        } else {
+            let db = self.parse_db(&buffer)?;
+            let parsed_data = self.entries_with_sessions(&db)?;
+            let settings = &db.settings;
+            let entries: Vec<Entry> = parsed_data
+                .iter()
+                .flat_map(|entry| match settings.day_boundary {
+                    DayBoundary::Virtual => day_boundary::split(entry, settings.day_start, timestamp),
+                    DayBoundary::None | DayBoundary::Split => vec![entry.clone()],
+                })
+                .filter(
+                    |entry|
+                        (from..to).contains(&entry.start) &&
+                        entry.stop.is_some() &&
+                        !entry.active &&
+                        options.branch.as_ref().is_none_or(|branch| entry.branch() == Some(branch))
+                )
+                .collect();
+            if let Some(format) = data_format {
+                let records: Vec<EntryRecord> = entries
+                    .iter()
+                    .map(|entry| EntryRecord::new(entry, timestamp))
+                    .collect();
+                return self.print_records(&records, EntryRecord::COLUMNS, format);
+            }
+            if options.format.is_some() {
+                let table = self.entries_table(&entries, timestamp, duration_format);
+                self.print_table(table, options, from, to);
+                return Ok(());
+            }
+            if parsed_data.is_empty() {
+                println!("no records found");
+            } else {
+                let mut total = Duration::zero();
+                entries.iter().try_for_each(
+                    |entry| -> Result<(), Report<FlatFileError>> {
+                        let stop_timestamp = entry.stop.ok_or_else(||
+                            Report::new(FlatFileError::SomethingWentWrong)
+                        )?;
+                        let elapsed = entry.elapsed(timestamp);
+                        total += elapsed;
+                        if verbosity >= 1 {
+                            let start = self.format_timestamp(entry.start, verbosity);
+                            let stop = self.format_timestamp(stop_timestamp, verbosity);
+                            println!(
+                                "{} started timer at {} and stopped timer at {} ({})",
+                                entry.label(),
+                                start,
+                                stop,
+                                duration::format(elapsed, duration_format)
+                            );
+                        }
+                        Ok(())
+                    }
+                )?;
+                println!("{}", duration::format(total, duration_format));
+                self.print_targets(&parsed_data, &db, now, duration_format);
+            }
+            if verbosity > -1 {
+                let billing = db.billing();
+                for budget in &db.budgets {
+                    let usage = budget.usage(&parsed_data, &billing, timestamp);
+                    if let Some(warning) = budget.warning(usage, &billing, duration_format) {
+                        eprintln!("warning: {warning}");
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prints a progress bar with the remaining time for each configured target. Days off
+    /// are shown instead of the daily target.
+    fn print_targets(&self, entries: &[Entry], db: &Database, now: DateTime<Local>, duration_format: DurationFormat) {
+        let settings = &db.settings;
+        let today = day_boundary::day_of(now.timestamp() as u64, settings.day_start);
+        if settings.daily_target.as_ref().is_some_and(|target| target.on(today).is_some()) {
+            if let Some(off) = time_off::on(&db.days_off, today) {
+                println!("day:  off, {}", off.kind);
+            }
+        }
+        for (label, progress) in target::progress(entries, settings, &db.days_off, now.timestamp() as u64) {
+            println!(
+                "{} {}",
+                format!("{label}:").pad_end_to_length("week:".len(), None),
+                progress.describe(now, duration_format)
+            );
+        }
+    }
+
+    /// Prints `records` in a machine-readable format, with `columns` as the CSV header.
+    fn print_records<R: Record>(
+        &self,
+        records: &[R],
+        columns: &[&str],
+        format: DataFormat
+    ) -> Result<(), Report<FlatFileError>> {
+        let output = export
+            ::render_with_columns(records, columns, format)
+            .map_err(|e| e.change_context(FlatFileError::ExportFailed))?;
+        print!("{output}");
+        Ok(())
+    }
+
+    /// A table of report rows, each with its depth, key and time, followed by the total.
+    fn time_table(
+        &self,
+        title: String,
+        key: &str,
+        rows: Vec<(usize, String, Duration)>,
+        total: Duration,
+        duration_format: DurationFormat
+    ) -> Table {
+        Table {
+            title,
+            period: String::new(),
+            columns: vec![key.to_owned(), "time".to_owned()],
+            header: false,
+            rows: rows
+                .into_iter()
+                .map(|(depth, key, time)| TableRow {
+                    depth,
+                    cells: vec![key, duration::format(time, duration_format)],
+                    time,
+                })
+                .collect(),
+            total: TableRow {
+                depth: 0,
+                cells: vec!["total".to_owned(), duration::format(total, duration_format)],
+                time: total,
+            },
+            separate_total: true,
+        }
+    }
+
+    /// A table of report rows with their time and billable amounts, followed by the totals.
+    /// When time is rounded, the raw and the rounded time are shown side by side under a header.
+    fn billing_table(
+        &self,
+        title: String,
+        key: &str,
+        rows: Vec<BillingRow>,
+        rounded: bool,
+        duration_format: DurationFormat
+    ) -> Table {
+        let cells = |time: Duration, rounded_time: Duration, amounts: &Amounts| {
+            let mut cells = vec![duration::format(time, duration_format)];
+            if rounded {
+                cells.push(duration::format(rounded_time, duration_format));
+            }
+            cells.push(amounts.format());
+            cells
+        };
+        let mut total_time = Duration::zero();
+        let mut total_rounded = Duration::zero();
+        let mut total_amounts = Amounts::default();
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                total_time += row.time;
+                total_rounded += row.rounded;
+                total_amounts.merge(&row.amounts);
+                let mut line = vec![row.key];
+                line.extend(cells(row.time, row.rounded, &row.amounts));
+                TableRow { depth: 0, cells: line, time: row.time }
+            })
+            .collect();
+        let mut total = vec!["total".to_owned()];
+        total.extend(cells(total_time, total_rounded, &total_amounts));
+        let columns: &[&str] = if rounded { &["raw", "rounded", "amount"] } else { &["time", "amount"] };
+        Table {
+            title,
+            period: String::new(),
+            columns: std::iter
+                ::once(key)
+                .chain(columns.iter().copied())
+                .map(str::to_owned)
+                .collect(),
+            header: rounded,
+            rows,
+            total: TableRow { depth: 0, cells: total, time: total_time },
+            separate_total: false,
+        }
+    }
+
+    /// A table of finished entries with their project, followed by the total.
+    fn entries_table(&self, entries: &[Entry], now: u64, duration_format: DurationFormat) -> Table {
+        let time = |timestamp: u64| local_time(timestamp).format("%Y-%m-%d %H:%M").to_string();
+        let rows: Vec<TableRow> = entries
+            .iter()
+            .map(|entry| {
+                let elapsed = entry.elapsed(now);
+                TableRow {
+                    depth: 0,
+                    cells: vec![
+                        entry.label(),
+                        time(entry.start),
+                        entry.stop.map(time).unwrap_or_default(),
+                        entry.details.project.clone().unwrap_or_default(),
+                        duration::format(elapsed, duration_format)
+                    ],
+                    time: elapsed,
+                }
+            })
+            .collect();
+        let total: Duration = rows
+            .iter()
+            .map(|row| row.time)
+            .sum();
+        let mut total_cells = vec![String::new(); 5];
+        total_cells[0] = "total".to_owned();
+        total_cells[4] = duration::format(total, duration_format);
+        Table {
+            title: "Entries".to_owned(),
+            period: String::new(),
+            columns: ["entry", "start", "stop", "project", "time"].map(str::to_owned).to_vec(),
+            header: true,
+            rows,
+            total: TableRow { depth: 0, cells: total_cells, time: total },
+            separate_total: false,
+        }
+    }
+
+    /// Prints `table` for the terminal unless `options` ask for a rendered document.
+    fn print_table(&self, mut table: Table, options: &ReportOptions, from: u64, to: u64) {
+        let time = |timestamp: u64| local_time(timestamp).format("%Y-%m-%d %H:%M").to_string();
+        table.period = format!("{} to {}", time(from), time(to));
+        let renderer = options.format.and_then(|format| format.renderer(options.charts));
+        print!("{}", renderer.as_deref().unwrap_or(&Terminal).render(&table));
+    }
+
+    /// Prints the running timer and today's total. Returns whether a timer is running.
+    pub fn status(
+        &self,
+        short: bool,
+        duration_format: DurationFormat,
+        verbosity: i8
+    ) -> Result<bool, Report<FlatFileError>> {
+        let mut db_file = self.open_db()?;
+        let db = self.read_db(&mut db_file)?;
+        let data = &db.entries;
+        // Today's time, budgets and targets count heartbeat sessions like reports do.
+        let entries = self.entries_with_sessions(&db)?;
+
+        let now = Local::now();
+        let timestamp = now.timestamp() as u64;
+        let day_start = db.settings.day_start;
+        let start_of_today = day_boundary::start_of_day(
+            day_boundary::day_of(timestamp, day_start),
+            day_start
+        );
+        let today: Duration = entries
+            .iter()
+            .map(|entry| entry.overlap(start_of_today, timestamp, timestamp))
+            .sum();
+        let today = duration::format(today, duration_format);
+
+        let running = if self.is_running() {
+            data.iter()
+                .rev()
+                .find(|entry| entry.active)
+        } else {
+            None
+        };
+
+        let Some(entry) = running else {
+            match (short, verbosity) {
+                (_, v) if v <= -1 => {}
+                (true, _) => println!("idle | today {today}"),
+                (false, _) => {
+                    println!("not running");
+                    println!("today: {today}");
+                    self.print_targets(&entries, &db, now, duration_format);
+                }
+            }
+            return Ok(false);
+        };
+
+        let elapsed = duration::format(entry.elapsed(timestamp), duration_format);
+        let EntryDetails { project, tags, note, .. } = &entry.details;
+        if short {
+            if verbosity > -1 {
+                let mut line = format!("running {elapsed}");
+                if let Some(project) = project {
+                    line.push_str(&format!(" | {project}"));
+                }
+                if !tags.is_empty() {
+                    line.push_str(&format!(" | {}", tags.join(", ")));
+                }
+                println!("{line} | today {today}");
+            }
+            return Ok(true);
+        }
+
+        if verbosity > -1 {
+            println!(
+                "running since {} ({elapsed})",
+                self.format_timestamp(entry.start, 1)
+            );
+            if let Some(project) = project {
+                println!("project: {project}");
+            }
+            if !tags.is_empty() {
+                println!("tags: {}", tags.join(", "));
+            }
+            if let Some(note) = note {
+                println!("note: {note}");
+            }
+            if let Some(git) = &entry.git {
+                match &git.branch {
+                    Some(branch) => println!("git: {} on {branch}", git.name()),
+                    None => println!("git: {}", git.name()),
+                }
+            }
+            if let Some(project) = project {
+                let billing = db.billing();
+                for budget in db.budgets.iter().filter(|budget| budget.covers(project)) {
+                    let usage = budget.usage(&entries, &billing, timestamp);
+                    let mark = if budget.reached(usage).is_some() { " !" } else { "" };
+                    println!(
+                        "budget {}: {}{mark}",
+                        budget.project,
+                        budget.summary(usage, &billing, duration_format)
+                    );
+                }
+            }
+            println!("today: {today}");
+            self.print_targets(&entries, &db, now, duration_format);
+        }
+
+        Ok(true)
+    }
+
+    /// Protects against forgotten timers. When the running session is longer than the
+    /// configured maximum, warns and caps it according to `auto_cap`, or to the configured
+    /// policy when `auto_cap` is `None`. Otherwise records the current time as the last
+    /// activity. Returns whether the timer was stopped.
+    pub fn guard_session(
+        &self,
+        auto_cap: Option<CapPolicy>,
+        verbosity: i8
+    ) -> Result<bool, Report<FlatFileError>> {
+        let Some(entry) = self.running_entry()? else {
+            return Ok(false);
+        };
+        let mut db_file = self.open_db()?;
+        let settings = self.read_db(&mut db_file)?.settings;
+        drop(db_file);
+
+        let now = Local::now();
+        let timestamp = now.timestamp() as u64;
+        let elapsed = entry.elapsed(timestamp);
+        let Some(max_session) = settings.max_session().filter(|max| elapsed > *max) else {
+            self.touch_lockfile()?;
+            return Ok(false);
+        };
+
+        eprintln!("warning: {}", session_guard::warning(elapsed, max_session));
+        let choice = match auto_cap.unwrap_or(settings.auto_cap) {
+            CapPolicy::Limit => CapChoice::Limit,
+            CapPolicy::LastActivity => CapChoice::LastActivity,
+            CapPolicy::Warn => CapChoice::Keep,
+            CapPolicy::Ask if std::io::stdin().is_terminal() => {
+                session_guard
+                    ::ask(std::io::stdin().lock(), std::io::stderr(), &now)
+                    .map_err(|e| Report::new(FlatFileError::ReadError(e)))?
+            }
+            CapPolicy::Ask => CapChoice::Keep,
+        };
+
+        let last_activity = self.last_activity()?.unwrap_or(entry.start);
+        let Some(stop) = choice.stop_at(entry.start, max_session, last_activity) else {
+            return Ok(false);
+        };
+        // The notice goes to stderr, as the output belongs to the command that was run
+        self.stop_at(local_time(stop), -1)?;
+        if verbosity > -1 {
+            eprintln!(
+                "timer stopped at {}, where the session was capped.",
+                local_time(stop).format("%Y-%m-%d %H:%M:%S")
+            );
+        }
+        Ok(true)
+    }
+
+    /// The last time the tracker was used while the timer was running.
+    fn last_activity(&self) -> Result<Option<u64>, Report<FlatFileError>> {
+        let modified = fs
+            ::metadata(&self.lockfile)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| Report::new(FlatFileError::LockFileError(e)))?;
+        Ok(
+            modified
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|since_epoch| since_epoch.as_secs())
+        )
+    }
+
+    fn touch_lockfile(&self) -> Result<(), Report<FlatFileError>> {
+        OpenOptions::new()
+            .write(true)
+            .open(&self.lockfile)
+            .and_then(|lockfile| lockfile.set_modified(SystemTime::now()))
+            .map_err(|e|
+                Report::new(FlatFileError::LockFileError(e)).attach_printable(
+                    "failed to record activity in the lockfile"
+                )
+            )
+    }
+
+    /// Registers the project `path`, e.g. `acme/website/frontend`.
+    pub fn add_project(&self, path: &str, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+        let path = project::normalize(path).map_err(|e| e.change_context(FlatFileError::InvalidProject))?;
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        if !db.projects.contains(&path) {
+            db.projects.push(path.clone());
+            db.projects.sort();
+            self.save_file(&mut db_file, &db)?;
+        }
+        if verbosity >= 1 {
+            println!("project {path} added.");
+        }
+        Ok(())
+    }
+
+    /// Prints the registered projects and the projects of stored entries and heartbeats as a
+    /// tree.
+    pub fn list_projects(&self, format: Option<DataFormat>, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+        let mut db_file = self.open_db()?;
+        let db = self.read_db(&mut db_file)?;
+        let heartbeats = self.read_heartbeats()?;
+        let paths = db.projects
+            .iter()
+            .chain(db.entries.iter().filter_map(|entry| entry.details.project.as_ref()))
+            .chain(heartbeats.iter().filter_map(|heartbeat| heartbeat.project.as_ref()));
+        let mut tree: BTreeSet<Vec<&str>> = BTreeSet::new();
+        for path in paths {
+            let segments: Vec<&str> = path.split(project::SEPARATOR).collect();
+            for depth in 1..=segments.len() {
+                tree.insert(segments[..depth].to_vec());
+            }
+        }
+        if let Some(format) = format {
+            let records: Vec<ProjectRecord> = tree
+                .iter()
+                .map(|segments| ProjectRecord { path: segments.join(&project::SEPARATOR.to_string()) })
+                .collect();
+            return self.print_records(&records, ProjectRecord::COLUMNS, format);
+        }
+        if verbosity > -1 {
+            for segments in tree {
+                println!("{}{}", "  ".repeat(segments.len() - 1), segments[segments.len() - 1]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the project `from` and all its subprojects below `to`, in the registry, in client
+    /// links, in project rates and budgets, in stored entries and in heartbeats.
+    pub fn move_project(&self, from: &str, to: &str, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+        let from = project::normalize(from).map_err(|e| e.change_context(FlatFileError::InvalidProject))?;
+        let to = project::normalize(to).map_err(|e| e.change_context(FlatFileError::InvalidProject))?;
+        if project::is_within(&to, &from) {
+            return Err(
+                Report::new(ProjectError::MoveIntoItself(from)).change_context(FlatFileError::InvalidProject)
+            );
+        }
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let heartbeats = self.read_heartbeats()?;
+        let known = db.projects
+            .iter()
+            .chain(db.entries.iter().filter_map(|entry| entry.details.project.as_ref()))
+            .chain(heartbeats.iter().filter_map(|heartbeat| heartbeat.project.as_ref()))
+            .chain(db.rates.projects.keys())
+            .chain(db.budgets.iter().map(|budget| &budget.project))
+            .any(|path| project::is_within(path, &from));
+        if !known {
+            return Err(
+                Report::new(ProjectError::UnknownProject(from)).change_context(FlatFileError::InvalidProject)
+            );
+        }
+        // Rates and budgets are not merged: the target keeps its own only if nothing is moved
+        // onto it.
+        let rates = std::mem::take(&mut db.rates.projects);
+        let (moved_rates, mut rates): (BTreeMap<_, _>, BTreeMap<_, _>) = rates
+            .into_iter()
+            .partition(|(path, _)| project::is_within(path, &from));
+        for (path, rate) in moved_rates {
+            let path = project::moved(&path, &from, &to).unwrap_or(path);
+            if rates.insert(path.clone(), rate).is_some() {
+                return Err(
+                    Report::new(ProjectError::RateExists(path)).change_context(FlatFileError::InvalidProject)
+                );
+            }
+        }
+        db.rates.projects = rates;
+        let budgets: BTreeSet<String> = db.budgets
+            .iter()
+            .filter(|budget| !project::is_within(&budget.project, &from))
+            .map(|budget| budget.project.clone())
+            .collect();
+        for budget in &mut db.budgets {
+            let Some(moved) = project::moved(&budget.project, &from, &to) else {
+                continue;
+            };
+            if budgets.contains(&moved) {
+                return Err(
+                    Report::new(ProjectError::BudgetExists(moved)).change_context(FlatFileError::InvalidProject)
+                );
+            }
+            budget.project = moved;
+        }
+
+        let mut projects: BTreeSet<String> = BTreeSet::new();
+        for path in db.projects.drain(..) {
+            projects.insert(project::moved(&path, &from, &to).unwrap_or(path));
+        }
+        db.projects = projects.into_iter().collect();
+        for client in &mut db.clients {
+            let mut links: Vec<String> = Vec::new();
+            for path in client.projects.drain(..) {
+                let path = project::moved(&path, &from, &to).unwrap_or(path);
+                if !links.contains(&path) {
+                    links.push(path);
+                }
+            }
+            client.projects = links;
+        }
+        let mut moved_entries = 0;
+        for entry in &mut db.entries {
+            let Some(path) = entry.details.project.as_deref() else {
+                continue;
+            };
+            if let Some(moved) = project::moved(path, &from, &to) {
+                entry.details.project = Some(moved);
+                moved_entries += 1;
+            }
+        }
+        self.save_file(&mut db_file, &db)?;
+        self.move_heartbeats(&from, &to)?;
+        if let Some(mut entry) = self.running_entry()? {
+            let moved = entry.details.project
+                .as_deref()
+                .and_then(|path| project::moved(path, &from, &to));
+            if let Some(moved) = moved {
+                entry.details.project = Some(moved);
+                let mut lockfile = self.open_lockfile()?;
+                self.write_lockfile_state(&mut lockfile, &entry)?;
+            }
+        }
+
+        if verbosity > -1 {
+            println!("moved {from} to {to} ({moved_entries} entries).");
+        }
+        Ok(())
+    }
+
+    /// Adds the client `name` with the details in `changes`.
+    pub fn add_client(
+        &self,
+        name: &str,
+        changes: ClientChanges,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        if db.clients.iter().any(|client| client.name == name) {
+            return Err(
+                Report::new(ClientError::DuplicateClient(name.to_owned())).change_context(
+                    FlatFileError::InvalidClient
+                )
+            );
+        }
+        let mut client = Client { name: name.to_owned(), ..Client::default() };
+        client.apply(self.normalize_links(changes)?);
+        db.clients.push(client);
+        self.save_file(&mut db_file, &db)?;
+        if verbosity >= 1 {
+            println!("client {name} added.");
+        }
+        Ok(())
+    }
+
+    /// Changes the details of the client `name`.
+    pub fn edit_client(
+        &self,
+        name: &str,
+        changes: ClientChanges,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        if let Some(new_name) = &changes.name {
+            if new_name != name && db.clients.iter().any(|client| &client.name == new_name) {
+                return Err(
+                    Report::new(ClientError::DuplicateClient(new_name.clone())).change_context(
+                        FlatFileError::InvalidClient
+                    )
+                );
+            }
+        }
+        let changes = self.normalize_links(changes)?;
+        client
+            ::find(&mut db.clients, name)
+            .map_err(|e| e.change_context(FlatFileError::InvalidClient))?
+            .apply(changes);
+        self.save_file(&mut db_file, &db)?;
+        if verbosity >= 1 {
+            println!("client {name} updated.");
+        }
+        Ok(())
+    }
+
+    /// Prints all clients with their details.
+    pub fn list_clients(&self, format: Option<DataFormat>, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+        let mut db_file = self.open_db()?;
+        let db = self.read_db(&mut db_file)?;
+        if let Some(format) = format {
+            let records: Vec<ClientRecord> = db.clients.iter().map(ClientRecord::from).collect();
+            return self.print_records(&records, ClientRecord::COLUMNS, format);
+        }
+        if verbosity <= -1 {
+            return Ok(());
+        }
+        for client in &db.clients {
+            println!("{}", client.name);
+            if let Some(currency) = &client.currency {
+                println!("  currency: {currency}");
+            }
+            if let Some(rate) = client.default_rate {
+                println!("  rate: {rate:.2}");
+            }
+            if let Some(tax_id) = &client.tax_id {
+                println!("  tax id: {tax_id}");
+            }
+            if let Some(tax_rate) = client.tax_rate {
+                println!("  tax rate: {tax_rate}%");
+            }
+            if let Some(rounding) = &client.rounding {
+                println!("  rounding: {rounding}");
+            }
+            if let Some(address) = &client.address {
+                println!("  address: {address}");
+            }
+            if !client.projects.is_empty() {
+                println!("  projects: {}", client.projects.join(", "));
+            }
+        }
+        Ok(())
+    }
+
+    fn normalize_links(&self, mut changes: ClientChanges) -> Result<ClientChanges, Report<FlatFileError>> {
+        for path in changes.link.iter_mut().chain(changes.unlink.iter_mut()) {
+            *path = project::normalize(path).map_err(|e| e.change_context(FlatFileError::InvalidProject))?;
+        }
+        Ok(changes)
+    }
+
+    /// Sets the hourly rate of a client, project or tag, or removes it when `rate` is `None`.
+    pub fn set_rate(
+        &self,
+        target: RateTarget,
+        rate: Option<f64>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let description = match target {
+            RateTarget::Client(name) => {
+                client
+                    ::find(&mut db.clients, &name)
+                    .map_err(|e| e.change_context(FlatFileError::InvalidClient))?.default_rate = rate;
+                format!("client {name}")
+            }
+            RateTarget::Project(path) => {
+                let path = project::normalize(&path).map_err(|e| e.change_context(FlatFileError::InvalidProject))?;
+                match rate {
+                    Some(rate) => db.rates.projects.insert(path.clone(), rate),
+                    None => db.rates.projects.remove(&path),
+                };
+                format!("project {path}")
+            }
+            RateTarget::Tag(tag) => {
+                match rate {
+                    Some(rate) => db.rates.tags.insert(tag.clone(), rate),
+                    None => db.rates.tags.remove(&tag),
+                };
+                format!("tag {tag}")
+            }
+        };
+        self.save_file(&mut db_file, &db)?;
+        if verbosity >= 1 {
+            match rate {
+                Some(rate) => println!("rate of {description} set to {rate:.2}."),
+                None => println!("rate of {description} removed."),
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints the rates of all clients, projects and tags.
+    pub fn list_rates(&self, format: Option<DataFormat>, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+        let mut db_file = self.open_db()?;
+        let db = self.read_db(&mut db_file)?;
+        if let Some(format) = format {
+            let record = |target, name: &str, rate: f64| RateRecord { target, name: name.to_owned(), rate };
+            let records: Vec<RateRecord> = db.clients
+                .iter()
+                .filter_map(|client| client.default_rate.map(|rate| record("client", &client.name, rate)))
+                .chain(db.rates.projects.iter().map(|(path, rate)| record("project", path, *rate)))
+                .chain(db.rates.tags.iter().map(|(tag, rate)| record("tag", tag, *rate)))
+                .collect();
+            return self.print_records(&records, RateRecord::COLUMNS, format);
+        }
+        if verbosity <= -1 {
+            return Ok(());
+        }
+        for client in &db.clients {
+            if let Some(rate) = client.default_rate {
+                println!("client {}: {rate:.2}", client.name);
+            }
+        }
+        for (path, rate) in &db.rates.projects {
+            println!("project {path}: {rate:.2}");
+        }
+        for (tag, rate) in &db.rates.tags {
+            println!("tag {tag}: {rate:.2}");
+        }
+        Ok(())
+    }
+
+    /// Renders an invoice for the unbilled time of a client and marks the billed entries with
+    /// the invoice number, unless `options.dry_run` is set.
+    pub fn invoice(&self, options: &InvoiceOptions, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+        let to_flat_file_error = |e: Report<InvoiceError>| e.change_context(FlatFileError::InvoiceFailed);
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let client = db.clients
+            .iter()
+            .find(|client| client.name == options.client)
+            .ok_or_else(|| {
+                Report::new(ClientError::UnknownClient(options.client.clone())).change_context(
+                    FlatFileError::InvalidClient
+                )
+            })?;
+
+        let today = Local::now().date_naive();
+        let number = match &options.number {
+            Some(number) if db.invoices.iter().any(|record| &record.number == number) => {
+                return Err(to_flat_file_error(Report::new(InvoiceError::DuplicateNumber(number.clone()))));
+            }
+            Some(number) => number.clone(),
+            None => invoice::next_number(&db.invoices, today.year()),
+        };
+        let period = options.period.unwrap_or_else(|| Period::previous_month(today));
+        let billing = db.billing();
+        let mut document = invoice
+            ::build(number, client, &db.entries, &billing, period, today)
+            .map_err(to_flat_file_error)?;
+        if options.tax_rate.is_some() {
+            document.tax_rate = options.tax_rate;
+        }
+        let template = invoice
+            ::template(options.format, options.template.as_deref().map(Path::new))
+            .map_err(to_flat_file_error)?;
+        let rendered = invoice::render(&template, &document, options.format).map_err(to_flat_file_error)?;
+
+        // The entries are marked before the invoice is written, so that a failure in between
+        // can at worst lose the document, which can be rendered again, but never bill twice.
+        if !options.dry_run {
+            for entry in db.entries.iter_mut().filter(|entry| document.entries.contains(&entry.id)) {
+                entry.invoice = Some(document.number.clone());
+            }
+            db.invoices.push(document.record());
+            self.save_file(&mut db_file, &db)?;
+        }
+        match &options.output {
+            Some(path) => {
+                fs::write(path, rendered).map_err(|e| {
+                    Report::new(FlatFileError::WriteError(e))
+                        .change_context(FlatFileError::InvoiceFailed)
+                        .attach_printable(path.clone())
+                })?;
+            }
+            None => print!("{rendered}"),
+        }
+        if options.dry_run {
+            return Ok(());
+        }
+        // The invoice itself may go to standard output.
+        if verbosity > -1 {
+            let total = billing::format_money(document.total(), document.currency.as_deref());
+            eprintln!(
+                "invoice {} for {} ({}): {} entries, {total}.",
+                document.number,
+                document.client.name,
+                document.period,
+                document.entries.len()
+            );
+        }
+        Ok(())
+    }
+
+    /// Sets the budget of a project, keeping its thresholds unless `thresholds` is given.
+    pub fn set_budget(
+        &self,
+        project: &str,
+        limit: Limit,
+        thresholds: Option<Vec<u32>>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        let project = project::normalize(project).map_err(|e| e.change_context(FlatFileError::InvalidProject))?;
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        match db.budgets.iter_mut().find(|budget| budget.project == project) {
+            Some(budget) => {
+                budget.limit = limit;
+                if let Some(thresholds) = thresholds {
+                    budget.thresholds = thresholds;
+                }
+            }
+            None => {
+                let thresholds = thresholds.unwrap_or_else(|| DEFAULT_THRESHOLDS.to_vec());
+                db.budgets.push(Budget { project: project.clone(), limit, thresholds });
+            }
+        }
+        if let Some(budget) = db.budgets.iter().find(|budget| budget.project == project) {
+            budget.check_currencies(&db.clients).map_err(|e| e.change_context(FlatFileError::InvalidBudget))?;
+        }
+        self.save_file(&mut db_file, &db)?;
+        if verbosity >= 1 {
+            println!("budget of {project} set.");
+        }
+        Ok(())
+    }
+
+    pub fn remove_budget(&self, project: &str, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+        let project = project::normalize(project).map_err(|e| e.change_context(FlatFileError::InvalidProject))?;
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let count = db.budgets.len();
+        db.budgets.retain(|budget| budget.project != project);
+        if db.budgets.len() == count {
+            return Err(
+                Report::new(BudgetError::UnknownBudget(project)).change_context(
+                    FlatFileError::InvalidBudget
+                )
+            );
+        }
+        self.save_file(&mut db_file, &db)?;
+        if verbosity >= 1 {
+            println!("budget of {project} removed.");
+        }
+        Ok(())
+    }
+
+    /// Prints the used and remaining budget of every project, or the weekly burn-down of the
+    /// budget of `project`, as records when `format` is given.
+    pub fn budgets(
+        &self,
+        project: Option<&str>,
+        duration_format: DurationFormat,
+        format: Option<DataFormat>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        let project = project
+            .map(project::normalize)
+            .transpose()
+            .map_err(|e| e.change_context(FlatFileError::InvalidProject))?;
+        let project = project.as_deref();
+        let mut db_file = self.open_db()?;
+        let db = self.read_db(&mut db_file)?;
+        if verbosity <= -1 && format.is_none() {
+            return Ok(());
+        }
+        for budget in db.budgets.iter().filter(|budget| project.is_none_or(|project| budget.project == project)) {
+            budget.check_currencies(&db.clients).map_err(|e| e.change_context(FlatFileError::InvalidBudget))?;
+        }
+        let entries = self.entries_with_sessions(&db)?;
+        let billing = db.billing();
+        let now = Local::now().timestamp() as u64;
+
+        if let (None, Some(format)) = (project, format) {
+            let records: Vec<BudgetRecord> = db.budgets
+                .iter()
+                .map(|budget| {
+                    let usage = budget.usage(&entries, &billing, now);
+                    let (kind, currency) = match budget.limit {
+                        Limit::Time(_) => ("time", None),
+                        Limit::Money(_) =>
+                            (
+                                "money",
+                                client
+                                    ::client_of(&db.clients, &budget.project)
+                                    .and_then(|client| client.currency.clone()),
+                            ),
+                    };
+                    BudgetRecord {
+                        project: budget.project.clone(),
+                        kind,
+                        limit: usage.limit,
+                        used: usage.used,
+                        remaining: usage.remaining(),
+                        percent: usage.percent(),
+                        currency,
+                    }
+                })
+                .collect();
+            return self.print_records(&records, BudgetRecord::COLUMNS, format);
+        }
+        let Some(project) = project else {
+            let rows: Vec<[String; 3]> = db.budgets
+                .iter()
+                .map(|budget| {
+                    let usage = budget.usage(&entries, &billing, now);
+                    let mark = if budget.reached(usage).is_some() { " !" } else { "" };
+                    [
+                        budget.project.clone(),
+                        budget.summary(usage, &billing, duration_format),
+                        format!("{} left{mark}", budget.format(usage.remaining(), &billing, duration_format)),
+                    ]
+                })
+                .collect();
+            let width = |column: usize| rows.iter().map(|row| row[column].width()).max().unwrap_or(0);
+            let (project_width, summary_width) = (width(0), width(1));
+            for [project, summary, remaining] in rows {
+                println!(
+                    "{} {} {remaining}",
+                    project.pad_end_to_length(project_width, None),
+                    summary.pad_end_to_length(summary_width, None)
+                );
+            }
+            return Ok(());
+        };
+
+        let budget = db.budgets
+            .iter()
+            .find(|budget| budget.project == project)
+            .ok_or_else(|| {
+                Report::new(BudgetError::UnknownBudget(project.to_owned())).change_context(
+                    FlatFileError::InvalidBudget
+                )
+            })?;
+        if let Some(format) = format {
+            let records: Vec<BurndownRecord> = budget
+                .burndown(&entries, &billing, now)
+                .iter()
+                .map(|row| BurndownRecord::new(&budget.project, row))
+                .collect();
+            return self.print_records(&records, BurndownRecord::COLUMNS, format);
+        }
+        let format = |value| budget.format(value, &billing, duration_format);
+        let rows: Vec<[String; 3]> = budget
+            .burndown(&entries, &billing, now)
+            .into_iter()
+            .map(|row| [row.week.format("%G-W%V").to_string(), format(row.used), format(row.remaining)])
+            .collect();
+        let header = ["week".to_owned(), "used".to_owned(), "remaining".to_owned()];
+        let width = |column: usize| {
+            rows.iter()
+                .chain([&header])
+                .map(|row| row[column].width())
+                .max()
+                .unwrap_or(0)
+        };
+        let widths = [width(0), width(1), width(2)];
+        println!("{project}: {} budget", format(budget.usage(&entries, &billing, now).limit));
+        for [week, used, remaining] in [header].into_iter().chain(rows) {
+            println!(
+                "{} {} {}",
+                week.pad_end_to_length(widths[0], None),
+                used.pad_start_to_length(widths[1], None),
+                remaining.pad_start_to_length(widths[2], None)
+            );
+        }
+        Ok(())
+    }
+
+    /// Prints the expected and worked time per week or month since the schedule started,
+    /// followed by the overtime balance. Today is left out until it is over.
+    pub fn balance(
+        &self,
+        by: BalancePeriod,
+        duration_format: DurationFormat,
+        format: Option<DataFormat>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        let mut db_file = self.open_db()?;
+        let db = self.read_db(&mut db_file)?;
+        let schedule = db.settings.schedule.ok_or_else(|| {
+            Report::new(BalanceError::NoSchedule).change_context(FlatFileError::InvalidSchedule)
+        })?;
+        if verbosity <= -1 && format.is_none() {
+            return Ok(());
+        }
+        let entries = self.entries_with_sessions(&db)?;
+        let now = Local::now().timestamp() as u64;
+        let worked = balance::worked_per_day(&entries, db.settings.day_start, now);
+        let yesterday = day_boundary::day_of(now, db.settings.day_start) - Duration::days(1);
+        let first = db.settings.schedule_start.or_else(|| worked.keys().next().copied());
+        let rows = match first {
+            Some(first) => balance::balance(&worked, &schedule, &db.days_off, first, yesterday, by),
+            None => Vec::new(),
+        };
+        if let Some(format) = format {
+            let records: Vec<BalanceRecord> = rows
+                .iter()
+                .map(|row| BalanceRecord::new(row, by.label(row.period)))
+                .collect();
+            return self.print_records(&records, BalanceRecord::COLUMNS, format);
+        }
+        let total = rows.last().map_or_else(Duration::zero, |row| row.running);
+        let label = match by {
+            BalancePeriod::Week => "week",
+            BalancePeriod::Month => "month",
+        };
+        let has_days_off = rows.iter().any(|row| !row.days_off.is_empty());
+        let header = [label, "expected", "worked", "balance", "running", if has_days_off { "days off" } else { "" }];
+        let header = header.map(str::to_owned);
+        let rows: Vec<[String; 6]> = rows
+            .into_iter()
+            .map(|row| {
+                [
+                    by.label(row.period),
+                    duration::format(row.expected, duration_format),
+                    duration::format(row.worked, duration_format),
+                    balance::format_signed(row.balance(), duration_format),
+                    balance::format_signed(row.running, duration_format),
+                    row.describe_days_off(),
+                ]
+            })
+            .collect();
+        if !rows.is_empty() {
+            let width = |column: usize| {
+                rows.iter()
+                    .chain([&header])
+                    .map(|row| row[column].width())
+                    .max()
+                    .unwrap_or(0)
+            };
+            let widths = [width(0), width(1), width(2), width(3), width(4)];
+            for row in [header].iter().chain(&rows) {
+                let cells: Vec<String> = row
+                    .iter()
+                    .enumerate()
+                    .map(|(column, cell)| {
+                        match column {
+                            0 => cell.pad_end_to_length(widths[column], None),
+                            5 => cell.clone(),
+                            _ => cell.pad_start_to_length(widths[column], None),
+                        }
+                    })
+                    .collect();
+                println!("{}", cells.join(" ").trim_end());
+            }
+        }
+        println!("balance: {}", balance::format_signed(total, duration_format));
+        Ok(())
+    }
+
+    /// Records the days from `first` to `last` as days off of `kind`, replacing days off
+    /// already recorded on them.
+    pub fn add_days_off(
+        &self,
+        first: NaiveDate,
+        last: NaiveDate,
+        kind: OffKind,
+        name: Option<&str>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        if last < first {
+            return Err(
+                Report::new(FlatFileError::InvalidTimeRange).attach_printable(
+                    "the last day off must not be before the first"
+                )
+            );
+        }
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let count = time_off::merge(&mut db.days_off, time_off::range(first, last, kind, name));
+        self.save_file(&mut db_file, &db)?;
+        if verbosity >= 1 {
+            println!("{count} {kind} day(s) added.");
+        }
+        Ok(())
+    }
+
+    pub fn remove_days_off(&self, first: NaiveDate, last: NaiveDate, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let count = db.days_off.len();
+        db.days_off.retain(|off| off.date < first || last < off.date);
+        if db.days_off.len() == count {
+            let days = if first == last { first.to_string() } else { format!("{first}..{last}") };
+            return Err(
+                Report::new(TimeOffError::NotFound(days)).change_context(FlatFileError::InvalidTimeOff)
+            );
+        }
+        self.save_file(&mut db_file, &db)?;
+        if verbosity >= 1 {
+            println!("{} day(s) off removed.", count - db.days_off.len());
+        }
+        Ok(())
+    }
+
+    /// Adds the holidays of an iCalendar or CSV file as days off of `kind`.
+    pub fn import_days_off(&self, path: &Path, kind: OffKind, verbosity: i8) -> Result<(), Report<FlatFileError>> {
+        let days_off = time_off::import(path, kind).map_err(|e| e.change_context(FlatFileError::InvalidTimeOff))?;
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let count = time_off::merge(&mut db.days_off, days_off);
+        self.save_file(&mut db_file, &db)?;
+        if verbosity >= 1 {
+            println!("{count} day(s) off imported.");
+        }
+        Ok(())
+    }
+
+    /// Lists the days off, only those from `from` on when given.
+    pub fn list_days_off(
+        &self,
+        from: Option<NaiveDate>,
+        format: Option<DataFormat>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        let mut db_file = self.open_db()?;
+        let db = self.read_db(&mut db_file)?;
+        let days_off: Vec<&DayOff> = db.days_off
+            .iter()
+            .filter(|off| from.is_none_or(|from| from <= off.date))
+            .collect();
+        if let Some(format) = format {
+            let records: Vec<DayOffRecord> = days_off.into_iter().map(DayOffRecord::from).collect();
+            return self.print_records(&records, DayOffRecord::COLUMNS, format);
+        }
+        if verbosity <= -1 {
+            return Ok(());
+        }
+        if days_off.is_empty() {
+            println!("no days off");
+        }
+        for off in days_off {
+            println!("{off}");
+        }
+        Ok(())
+    }
+
+    /// Warns about the budgets covering `project` that reached one of their thresholds.
+    fn warn_about_budgets(
+        &self,
+        db: &Database,
+        project: Option<&str>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        let Some(project) = project else {
+            return Ok(());
+        };
+        if verbosity <= -1 || !db.budgets.iter().any(|budget| budget.covers(project)) {
+            return Ok(());
+        }
+        let entries = self.entries_with_sessions(db)?;
+        let billing = db.billing();
+        let now = Local::now().timestamp() as u64;
+        for budget in db.budgets.iter().filter(|budget| budget.covers(project)) {
+            let usage = budget.usage(&entries, &billing, now);
+            if let Some(warning) = budget.warning(usage, &billing, DurationFormat::Compact) {
+                eprintln!("warning: {warning}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints all settings, prints the setting `key`, or sets it to `value`.
+    pub fn config(
+        &self,
+        key: Option<&str>,
+        value: Option<&str>,
+        verbosity: i8
+    ) -> Result<(), Report<FlatFileError>> {
+        let mut db_file = self.open_db()?;
+        let mut db = self.read_db(&mut db_file)?;
+        let to_flat_file_error = |e: Report<SettingsError>| e.change_context(FlatFileError::InvalidSetting);
+
+        match (key, value) {
+            (None, _) => {
+                for key in Settings::KEYS {
+                    println!("{key} = {}", db.settings.get(key).map_err(to_flat_file_error)?);
+                }
+            }
+            (Some(key), None) => {
+                println!("{}", db.settings.get(key).map_err(to_flat_file_error)?);
+            }
+            (Some(key), Some(value)) => {
+                db.settings.set(key, value).map_err(to_flat_file_error)?;
+                self.save_file(&mut db_file, &db)?;
+                if verbosity >= 1 {
+                    println!("{key} set to {}.", db.settings.get(key).map_err(to_flat_file_error)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn format_timestamp(&self, timestamp: u64, verbosity: i8) -> String {
+        let local_datetime = local_time(timestamp);
+
+        let format_str = if verbosity >= 1 { "%Y-%m-%d %H:%M:%S" } else { "%Y-%m-%d" };
+
+        local_datetime.format(format_str).to_string()
+    }
+
+    fn open_db(&self) -> Result<File, Report<FlatFileError>> {
+        let db = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .open(&self.db_dir)
+            .map_err(|e|
+                Report::new(FlatFileError::DbFileError(e)).attach_printable(
+                    "failed to open db file"
+                )
+            )?;
+
+        Ok(db)
+    }
+
+    fn open_lockfile(&self) -> Result<File, Report<FlatFileError>> {
+        let lockfile = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.lockfile)
+            .map_err(|e|
+                Report::new(FlatFileError::ReadError(e)).attach_printable("failed to open lockfile")
+            )?;
+        lockfile
+            .lock_exclusive()
+            .map_err(|e|
+                Report::new(FlatFileError::ReadError(e)).attach_printable(
+                    "failed to acquire exclusive lock"
+                )
+            )?;
+
+        Ok(lockfile)
+    }
+
+    /// Mirrors the running entry into the lockfile so that it can be read without the database.
+    fn write_lockfile_state(
+        &self,
+        lockfile: &mut File,
+        entry: &Entry
+    ) -> Result<(), Report<FlatFileError>> {
+        let json_data = serde_json
+            ::to_string(entry)
+            .map_err(|e|
+                Report::new(FlatFileError::JsonSerializeError(e)).attach_printable(
+                    "failed to serialize the running entry"
+                )
+            )?;
+        lockfile
+            .set_len(0)
+            .map_err(|e|
+                Report::new(FlatFileError::LockFileError(e)).attach_printable(
+                    "failed to truncate the lockfile"
+                )
+            )?;
+        lockfile
+            .write_all(json_data.as_bytes())
+            .map_err(|e|
+                Report::new(FlatFileError::LockFileError(e)).attach_printable(
+                    "failed to write to the lockfile"
+                )
+            )?;
+        Ok(())
+    }
+
+    /// Returns the running entry from the lockfile. The database is only consulted for
+    /// lockfiles written before the running entry was mirrored into them.
+    pub fn running_entry(&self) -> Result<Option<Entry>, Report<FlatFileError>> {
+        let buffer = match fs::read_to_string(&self.lockfile) {
+            Ok(buffer) => buffer,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(None);
+            }
+            Err(e) => {
+                return Err(
+                    Report::new(FlatFileError::LockFileError(e)).attach_printable(
+                        "failed to read the lockfile"
+                    )
+                );
+            }
+        };
+        if buffer.trim().is_empty() {
+            let mut db_file = self.open_db()?;
+            let db = self.read_db(&mut db_file)?;
+            let data = db.entries;
+            return Ok(data.into_iter().rev().find(|entry| entry.active));
+        }
+        let entry = serde_json
+            ::from_str(&buffer)
+            .map_err(|e| Report::new(FlatFileError::JsonParseError(e)))?;
+        Ok(Some(entry))
+    }
+
+    fn read_buffer(&self, db_file: &mut File) -> Result<String, Report<FlatFileError>> {
+        let mut buffer = String::new();
+        db_file.read_to_string(&mut buffer).map_err(|e| Report::new(FlatFileError::ReadError(e)))?;
+        Ok(buffer)
+    }
+
+    fn parse_db(&self, buffer: &str) -> Result<Database, Report<FlatFileError>> {
+        if buffer.trim().is_empty() {
+            return Ok(Database::default());
+        }
+        let stored: StoredDatabase = serde_json
+            ::from_str(buffer)
+            .map_err(|e| Report::new(FlatFileError::JsonParseError(e)))?;
+        Ok(stored.into())
+    }
+
+    fn read_db(&self, db_file: &mut File) -> Result<Database, Report<FlatFileError>> {
+        let buffer = self.read_buffer(db_file)?;
+        self.parse_db(&buffer)
+    }
+
+    fn save_file(&self, db_file: &mut File, data: &Database) -> Result<(), Report<FlatFileError>> {
+        db_file
+            .set_len(0)
+            .map_err(|e|
+                Report::new(FlatFileError::DbFileError(e)).attach_printable(
+                    "failed to truncate the file"
+                )
+            )?;
+        db_file
+            .seek(SeekFrom::Start(0))
+            .map_err(|e|
+                Report::new(FlatFileError::DbFileError(e)).attach_printable(
+                    "failed to find the first line"
+                )
+            )?;
+
+        let json_data = serde_json
+            ::to_string(data)
+            .map_err(|e|
+                Report::new(FlatFileError::JsonParseError(e)).attach_printable(
+                    "failed to serialize data to JSON"
+                )
+            )?;
+        db_file
+            .write_all(json_data.as_bytes())
+            .map_err(|e|
+                Report::new(FlatFileError::WriteError(e)).attach_printable(
+                    "failed to write to database"
+                )
+            )?;
+        Ok(())
+    }
+
+    pub fn is_running(&self) -> bool {
+        Path::new(&self.lockfile).exists()
+    }
+}
+
+/// Refuses times after now, which would record time that has not been worked yet.
+fn reject_future(at: DateTime<Local>) -> Result<(), Report<FlatFileError>> {
+    if at > Local::now() {
+        return Err(
+            Report::new(FlatFileError::FutureTime).attach_printable(
+                format!("{} has not happened yet", at.format("%Y-%m-%d %H:%M:%S"))
+            )
+        );
+    }
+    Ok(())
+}
+
+/// Parses the heartbeat file, one heartbeat per line.
+fn parse_heartbeats(buffer: &str) -> Result<Vec<Heartbeat>, Report<FlatFileError>> {
+    buffer
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|e| Report::new(FlatFileError::JsonParseError(e)))
+        })
+        .collect()
+}
+
+/// `details` with its project path normalized, so that it matches the project tree.
+fn normalize_project(mut details: EntryDetails) -> Result<EntryDetails, Report<FlatFileError>> {
+    if let Some(path) = details.project.as_mut() {
+        *path = project::normalize(path).map_err(|e| e.change_context(FlatFileError::InvalidProject))?;
+    }
+    Ok(details)
+}
+
+/// Replaces the entry at `position` with one entry per day it touches.
+fn split_stored_entry(entries: &mut Vec<Entry>, position: usize, day_start: NaiveTime) {
+    let mut pieces = day_boundary::split(&entries[position], day_start, entries[position].start);
+    let first_id = next_id(entries);
+    for (id, piece) in (first_id..).zip(pieces.iter_mut().skip(1)) {
+        piece.id = id;
     }
+    entries.splice(position..=position, pieces);
 }
 
 #[cfg(test)]
@@ -1 +1,3 @@
+pub mod database;
+pub mod entry;
 pub mod flatfile;
\ No newline at end of file
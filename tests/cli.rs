@@ -1,6 +1,29 @@
 use assert_cmd::Command;
+use assert_fs::TempDir;
+use chrono::{ DateTime, Duration, Local, Timelike };
 use testresult::TestResult;
 
+fn track(dir: &TempDir) -> TestResult<Command> {
+    let mut command = Command::cargo_bin("track")?;
+    command
+        .arg("--db-dir")
+        .arg(dir.path().join("db.json"))
+        .arg("--lockfile")
+        .arg(dir.path().join("lockfile"));
+    Ok(command)
+}
+
+/// An absolute time `minutes` before `base`, so that several commands agree on it.
+fn minutes_before(base: DateTime<Local>, minutes: i64) -> String {
+    (base - Duration::minutes(minutes)).format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// A day start about twelve hours away from `base`, so that the minutes before it never
+/// span two days.
+fn day_start_away_from(base: DateTime<Local>) -> String {
+    format!("{:02}:00", (base.hour() + 12) % 24)
+}
+
 #[test]
 fn status_code_is_error_if_no_command_is_specified() -> TestResult {
     Command::cargo_bin("track")?.assert().failure();
@@ -9,20 +32,1069 @@ fn status_code_is_error_if_no_command_is_specified() -> TestResult {
 
 #[test]
 fn start_command_starts_tracking_time() -> TestResult {
-    Command::cargo_bin("track")?.arg("start").assert().success();
+    let dir = TempDir::new()?;
+    track(&dir)?.arg("start").assert().success();
+    assert!(dir.path().join("lockfile").exists());
     Ok(())
 }
 
 #[test]
 fn stop_command_stops_tracking_time() -> TestResult {
-    Command::cargo_bin("track")?.arg("stop").assert().success();
-    todo!();
+    let dir = TempDir::new()?;
+    track(&dir)?.arg("start").assert().success();
+    track(&dir)?.arg("stop").assert().success();
+    assert!(!dir.path().join("lockfile").exists());
+    Ok(())
 }
 
 #[test]
 fn report_command_generates_report() -> TestResult {
-    Command::cargo_bin("track")?.arg("start").assert().success();
-    Command::cargo_bin("track")?.arg("stop").assert().success();
-    Command::cargo_bin("track")?.arg("report").assert().stdout("00:00:00\n").success();
+    let dir = TempDir::new()?;
+    track(&dir)?.arg("start").assert().success();
+    track(&dir)?.arg("stop").assert().success();
+    track(&dir)?.arg("report").assert().stdout("00:00:00\n").success();
+    Ok(())
+}
+
+#[test]
+fn start_and_stop_accept_time_expressions() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?.args(["start", "--at", "15 minutes ago"]).assert().success();
+    track(&dir)?.args(["stop", "--at", "5 minutes ago"]).assert().success();
+    Ok(())
+}
+
+#[test]
+fn times_in_the_future_are_rejected() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?.args(["start", "--at", "in 10 minutes"]).assert().failure();
+    assert!(!dir.path().join("lockfile").exists());
+    let assert = track(&dir)?.args(["start", "--at", "in 999999999 days"]).assert().code(1);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("time is out of range"), "unexpected error: {stderr}");
+    track(&dir)?.args(["start", "--at", "10 minutes ago"]).assert().success();
+    track(&dir)?.args(["stop", "--at", "in 5 minutes"]).assert().failure();
+    Ok(())
+}
+
+#[test]
+fn stop_before_start_is_rejected() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?.args(["start", "--at", "5 minutes ago"]).assert().success();
+    let assert = track(&dir)?.args(["stop", "--at", "10 minutes ago"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("the end of a time range must be after its start"));
+    Ok(())
+}
+
+#[test]
+fn add_command_records_a_finished_entry() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?
+        .args(["add", "--from", "yesterday 9am", "--to", "yesterday noon"])
+        .assert()
+        .success();
+    track(&dir)?
+        .args(["add", "--from", "yesterday noon", "--to", "yesterday 9am"])
+        .assert()
+        .failure();
+    track(&dir)?.args(["add", "--from", "whenever", "--to", "now"]).assert().failure();
+    Ok(())
+}
+
+#[test]
+fn add_command_accepts_a_duration() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?
+        .args(["add", "--from", "2 hours ago", "--duration", "1h30m"])
+        .assert()
+        .success();
+    track(&dir)?
+        .args(["report", "--duration-format", "compact"])
+        .assert()
+        .stdout("1h 30m\n")
+        .success();
+    track(&dir)?
+        .args(["report", "--duration-format", "decimal"])
+        .assert()
+        .stdout("1.50\n")
+        .success();
+    Ok(())
+}
+
+#[test]
+fn status_command_shows_the_running_timer() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?.arg("status").assert().code(3).stdout("not running\ntoday: 00:00:00\n");
+    track(&dir)?.args(["config", "day-start", &day_start_away_from(Local::now())]).assert().success();
+    track(&dir)?
+        .args(["start", "--at", "10 minutes ago", "-p", "website", "-t", "frontend", "-n", "navbar"])
+        .assert()
+        .success();
+    let assert = track(&dir)?.args(["status", "--duration-format", "compact"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("running since "), "unexpected status: {stdout}");
+    assert!(stdout.contains("(10m)\nproject: website\ntags: frontend\nnote: navbar\n"));
+    track(&dir)?
+        .args(["status", "--short", "--duration-format", "compact"])
+        .assert()
+        .stdout("running 10m | website | frontend | today 10m\n")
+        .success();
+    track(&dir)?.args(["-q", "status"]).assert().stdout("").success();
+    Ok(())
+}
+
+#[test]
+fn legacy_databases_are_still_readable() -> TestResult {
+    let dir = TempDir::new()?;
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+    std::fs::write(dir.path().join("db.json"), format!("[[{},{},false]]", now - 120, now - 60))?;
+    track(&dir)?.arg("report").assert().stdout("00:01:00\n").success();
+    Ok(())
+}
+
+#[test]
+fn prompt_command_renders_only_while_running() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?.arg("prompt").assert().stdout("").success();
+    track(&dir)?.args(["start", "--at", "2 hours ago", "-p", "api"]).assert().success();
+    // The database is not needed to render the prompt.
+    std::fs::remove_file(dir.path().join("db.json"))?;
+    track(&dir)?.arg("prompt").assert().stdout("api 2h 0m\n").success();
+    track(&dir)?
+        .args(["prompt", "--format", "<{project}> {{{elapsed:decimal}}}"])
+        .assert()
+        .stdout("<api> {2.00}\n")
+        .success();
+    track(&dir)?.args(["prompt", "--format", "{bogus}"]).assert().failure();
+    Ok(())
+}
+
+#[test]
+fn continue_command_copies_details_of_previous_entries() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?.arg("continue").assert().failure();
+    track(&dir)?
+        .args(["add", "--from", "3 hours ago", "--duration", "1h", "-p", "api", "-n", "auth"])
+        .assert()
+        .success();
+    track(&dir)?
+        .args(["add", "--from", "2 hours ago", "--duration", "1h", "-p", "web", "-t", "css"])
+        .assert()
+        .success();
+
+    track(&dir)?.arg("continue").assert().success();
+    track(&dir)?
+        .args(["prompt", "--format", "{project} {tags}"])
+        .assert()
+        .stdout("web css\n")
+        .success();
+    let assert = track(&dir)?.args(["continue", "1"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("timer is already running"));
+
+    track(&dir)?.arg("stop").assert().success();
+    track(&dir)?.args(["continue", "1"]).assert().success();
+    track(&dir)?.args(["prompt", "--format", "{project} {note}"]).assert().stdout("api auth\n");
+    track(&dir)?.arg("stop").assert().success();
+    track(&dir)?.args(["continue", "42"]).assert().failure();
+    Ok(())
+}
+
+#[test]
+fn split_and_merge_entries() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?
+        .args(["add", "--from", "3 hours ago", "--to", "1 hour ago", "-p", "web", "-t", "css"])
+        .assert()
+        .success();
+    track(&dir)?.args(["split", "1", "--at", "4 hours ago"]).assert().failure();
+    track(&dir)?.args(["split", "1", "--at", "2 hours ago"]).assert().success();
+    track(&dir)?
+        .args(["add", "--from", "50 minutes ago", "--to", "40 minutes ago", "-t", "ui", "-n", "tweak"])
+        .assert()
+        .success();
+
+    // entry 3 is 10 minutes after entry 2, which exceeds the default gap
+    track(&dir)?.args(["merge", "2", "3"]).assert().failure();
+    // entries 1 and 3 are not adjacent
+    track(&dir)?.args(["merge", "1", "3", "--max-gap", "1h"]).assert().failure();
+    track(&dir)?.args(["merge", "3", "1", "2", "--max-gap", "15m"]).assert().success();
+    track(&dir)?.args(["continue", "1"]).assert().success();
+    track(&dir)?
+        .args(["prompt", "--format", "{project}|{tags}|{note}"])
+        .assert()
+        .stdout("web|css,ui|tweak\n")
+        .success();
+    Ok(())
+}
+
+#[test]
+fn config_command_shows_and_changes_settings() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?
+        .arg("config")
+        .assert()
+        .stdout("day-boundary = virtual\nday-start = 00:00\nmax-session = off\nauto-cap = ask\nidle-timeout = 15m\nrounding = off\ndaily-target = off\nweekly-target = off\nschedule = off\nschedule-start = off\n")
+        .success();
+    track(&dir)?.args(["config", "day-start", "04:00"]).assert().success();
+    track(&dir)?.args(["config", "day-start"]).assert().stdout("04:00\n").success();
+    track(&dir)?.args(["config", "day-boundary", "sometimes"]).assert().failure();
+    track(&dir)?.args(["config", "colour", "blue"]).assert().failure();
+    Ok(())
+}
+
+#[test]
+fn entries_crossing_the_day_start_are_split_when_stored() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?.args(["config", "day-boundary", "split"]).assert().success();
+    track(&dir)?
+        .args(["add", "--from", "yesterday 22:00", "--to", "today 02:00"])
+        .assert()
+        .success();
+    let db = std::fs::read_to_string(dir.path().join("db.json"))?;
+    assert_eq!(db.matches("\"start\"").count(), 2, "unexpected database: {db}");
+    Ok(())
+}
+
+#[test]
+fn overdue_sessions_are_capped() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?.args(["config", "max-session", "1h"]).assert().success();
+    track(&dir)?.args(["start", "--at", "3 hours ago"]).assert().success();
+
+    let assert = track(&dir)?.args(["status", "--auto-cap", "warn"]).assert().success();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("longer than the maximum session length of 1h 0m"), "{stderr}");
+
+    // without a terminal, asking falls back to a warning
+    track(&dir)?.arg("status").assert().success();
+
+    track(&dir)?.args(["-q", "stop", "--auto-cap", "limit"]).assert().success();
+    track(&dir)?.arg("status").assert().code(3);
+    track(&dir)?.arg("report").assert().stdout("01:00:00\n").success();
+
+    track(&dir)?.args(["config", "auto-cap", "last-activity"]).assert().success();
+    track(&dir)?.args(["start", "--at", "2 hours ago"]).assert().success();
+    track(&dir)?.arg("status").assert().code(3);
+    Ok(())
+}
+
+#[test]
+fn overdue_sessions_stop_at_the_given_time() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?.args(["config", "max-session", "1h"]).assert().success();
+    track(&dir)?.args(["start", "--at", "4 hours ago"]).assert().success();
+    track(&dir)?.args(["stop", "--at", "2 hours ago", "--auto-cap", "limit"]).assert().success();
+    track(&dir)?.arg("report").assert().stdout("02:00:00\n").success();
+
+    // capping while running another command only reports on stderr
+    track(&dir)?.args(["start", "--at", "90 minutes ago"]).assert().success();
+    let assert = track(&dir)?.args(["report", "--auto-cap", "limit"]).assert().stdout("03:00:00\n").success();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("where the session was capped"), "{stderr}");
+    Ok(())
+}
+
+#[test]
+fn heartbeats_are_reported_as_sessions() -> TestResult {
+    let dir = TempDir::new()?;
+    let now = Local::now();
+    track(&dir)?.args(["config", "day-start", &day_start_away_from(now)]).assert().success();
+    for minutes in [60, 50, 40] {
+        track(&dir)?
+            .args(["heartbeat", "-p", "api", "-f", "src/main.rs", "--at", &minutes_before(now, minutes)])
+            .assert()
+            .success();
+    }
+    // after more than the idle timeout, a new session starts
+    for minutes in [10, 5] {
+        track(&dir)?
+            .args(["heartbeat", "-p", "api", "--at", &minutes_before(now, minutes)])
+            .assert()
+            .success();
+    }
+    assert!(dir.path().join("db.heartbeats.jsonl").exists());
+    track(&dir)?.arg("report").assert().stdout("00:25:00\n").success();
+    // sessions are not stored, so they have no id to split or merge
+    let assert = track(&dir)?.args(["-v", "report"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("session started timer at "), "unexpected report: {stdout}");
+    assert!(!stdout.contains("#0"));
+    track(&dir)?.args(["status", "--short"]).assert().stdout("idle | today 00:25:00\n").code(3);
+
+    track(&dir)?.args(["config", "idle-timeout", "1h"]).assert().success();
+    track(&dir)?.arg("report").assert().stdout("00:55:00\n").success();
+    Ok(())
+}
+
+#[test]
+fn heartbeat_projects_are_part_of_the_project_tree() -> TestResult {
+    let dir = TempDir::new()?;
+    let now = Local::now();
+    track(&dir)?.args(["config", "day-start", &day_start_away_from(now)]).assert().success();
+    for minutes in [30, 20, 10] {
+        track(&dir)?
+            .args(["heartbeat", "-p", " acme / api ", "--at", &minutes_before(now, minutes)])
+            .assert()
+            .success();
+    }
+    track(&dir)?.args(["project", "list"]).assert().stdout("acme\n  api\n").success();
+    track(&dir)?.args(["project", "move", "acme", "globex/acme"]).assert().success();
+    track(&dir)?.args(["project", "list"]).assert().stdout("globex\n  acme\n    api\n").success();
+    track(&dir)?
+        .args(["report", "--by", "project", "--depth", "1"])
+        .assert()
+        .stdout("globex 00:20:00\n00:20:00\n")
+        .success();
+    Ok(())
+}
+
+#[test]
+fn heartbeat_time_is_broken_down_by_language_and_file() -> TestResult {
+    let dir = TempDir::new()?;
+    let now = Local::now();
+    for (minutes, file) in [(60, "src/main.rs"), (48, "src/lib.rs"), (40, "README.md"), (35, "docs/a.md")] {
+        track(&dir)?
+            .args(["heartbeat", "-p", "api", "-f", file, "--at", &minutes_before(now, minutes)])
+            .assert()
+            .success();
+    }
+    track(&dir)?
+        .args(["report", "--by", "language"])
+        .assert()
+        .stdout("Rust     00:20:00\nMarkdown 00:05:00\n00:25:00\n")
+        .success();
+    track(&dir)?
+        .args(["report", "--by", "file", "--top", "1", "--duration-format", "compact"])
+        .assert()
+        .stdout("src/main.rs 12m\n(other)     13m\n25m\n")
+        .success();
+    track(&dir)?.args(["report", "--top", "1"]).assert().failure();
+    Ok(())
+}
+
+#[test]
+fn start_uses_the_nearest_directory_context() -> TestResult {
+    let dir = TempDir::new()?;
+    let nested = dir.path().join("repo").join("src");
+    std::fs::create_dir_all(&nested)?;
+    std::fs::write(
+        dir.path().join("repo").join(".track"),
+        "project = \"website\"\ntags = [\"frontend\"]\n"
+    )?;
+    track(&dir)?.current_dir(&nested).arg("start").assert().success();
+    track(&dir)?
+        .args(["prompt", "--format", "{project} {tags}"])
+        .assert()
+        .stdout("website frontend\n");
+    track(&dir)?.arg("stop").assert().success();
+    track(&dir)?.current_dir(&nested).args(["start", "-p", "api"]).assert().success();
+    track(&dir)?
+        .args(["prompt", "--format", "{project} {tags}"])
+        .assert()
+        .stdout("api frontend\n");
+    track(&dir)?.arg("stop").assert().success();
+
+    std::fs::write(dir.path().join("repo").join(".track"), "project = [")?;
+    track(&dir)?.current_dir(&nested).arg("start").assert().failure();
+    Ok(())
+}
+
+#[test]
+fn entries_record_the_git_branch_and_reports_filter_by_it() -> TestResult {
+    let dir = TempDir::new()?;
+    let repo = dir.path().join("website");
+    std::fs::create_dir_all(repo.join(".git").join("refs").join("heads"))?;
+    std::fs::create_dir_all(repo.join("src"))?;
+    let head = repo.join(".git").join("HEAD");
+    std::fs::write(&head, "ref: refs/heads/main\n")?;
+    std::fs::write(repo.join(".git/refs/heads/main"), "1111111111111111111111111111111111111111\n")?;
+    let now = Local::now();
+
+    track(&dir)?
+        .current_dir(repo.join("src"))
+        .args(["start", "--at", &minutes_before(now, 30)])
+        .assert()
+        .success();
+    let assert = track(&dir)?.arg("status").assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(stdout.contains("git: website on main\n"), "{stdout}");
+    track(&dir)?.args(["stop", "--at", &minutes_before(now, 20)]).assert().success();
+
+    std::fs::write(&head, "ref: refs/heads/feature/login\n")?;
+    track(&dir)?
+        .current_dir(&repo)
+        .args(["start", "--at", &minutes_before(now, 20)])
+        .assert()
+        .success();
+    track(&dir)?.args(["stop", "--at", &minutes_before(now, 15)]).assert().success();
+
+    let db = std::fs::read_to_string(dir.path().join("db.json"))?;
+    assert!(db.contains("\"commit\":\"1111111111111111111111111111111111111111\""), "{db}");
+    track(&dir)?
+        .args(["report", "--branch", "main"])
+        .assert()
+        .stdout("00:10:00\n")
+        .success();
+    track(&dir)?
+        .args(["report", "--branch", "feature/login"])
+        .assert()
+        .stdout("00:05:00\n")
+        .success();
+    track(&dir)?.arg("report").assert().stdout("00:15:00\n").success();
+    Ok(())
+}
+
+#[test]
+fn commits_are_reported_with_the_time_spent_on_them() -> TestResult {
+    let dir = TempDir::new()?;
+    let repo = dir.path().join("website");
+    let git_dir = repo.join(".git");
+    std::fs::create_dir_all(git_dir.join("refs").join("heads"))?;
+    std::fs::write(git_dir.join("HEAD"), "ref: refs/heads/login\n")?;
+    let now = Local::now();
+
+    track(&dir)?
+        .current_dir(&repo)
+        .args(["git", "install-hooks", "--note"])
+        .assert()
+        .success();
+    let hook = std::fs::read_to_string(git_dir.join("hooks").join("post-commit"))?;
+    assert!(hook.contains("git post-commit"), "{hook}");
+    assert!(hook.contains("git notes --ref=track"), "{hook}");
+
+    track(&dir)?
+        .current_dir(&repo)
+        .args(["start", "--at", &minutes_before(now, 50)])
+        .assert()
+        .success();
+    track(&dir)?.args(["stop", "--at", &minutes_before(now, 30)]).assert().success();
+    track(&dir)?.current_dir(&repo).args(["git", "spent"]).assert().stdout("20m\n").success();
+    track(&dir)?.current_dir(&repo).args(["git", "post-commit"]).assert().failure();
+
+    std::fs::write(git_dir.join("refs/heads/login"), "aaaaaaa111\n")?;
+    track(&dir)?.current_dir(&repo).args(["git", "post-commit"]).assert().stdout("20m\n").success();
+    // Time before the previous commit is not counted again.
+    std::fs::write(git_dir.join("refs/heads/login"), "bbbbbbb222\n")?;
+    track(&dir)?.current_dir(&repo).args(["git", "post-commit"]).assert().stdout("0m\n").success();
+
+    track(&dir)?
+        .args(["report", "--by", "commit", "--duration-format", "compact"])
+        .assert()
+        .stdout("aaaaaaa login 20m\nbbbbbbb login 0m\n20m\n")
+        .success();
+    track(&dir)?.args(["report", "--by", "file", "--branch", "login"]).assert().failure();
+    Ok(())
+}
+
+#[test]
+fn projects_form_a_hierarchy_with_rolled_up_subtotals() -> TestResult {
+    let dir = TempDir::new()?;
+    let now = Local::now();
+    for (from, to, project) in [
+        (120, 80, "acme/website/frontend"),
+        (80, 60, "acme/website"),
+        (60, 30, "acme / api"),
+        (30, 20, "globex"),
+    ] {
+        track(&dir)?
+            .args(["add", "--from", &minutes_before(now, from), "--to", &minutes_before(now, to), "-p", project])
+            .assert()
+            .success();
+    }
+    track(&dir)?.args(["project", "add", " initech / billing "]).assert().success();
+
+    track(&dir)?
+        .args(["report", "--by", "project", "--duration-format", "compact"])
+        .assert()
+        .stdout("acme         1h 30m\n  website    1h 0m\n    frontend 40m\n  api        30m\nglobex       10m\n1h 40m\n")
+        .success();
+    track(&dir)?
+        .args(["report", "--by", "project", "--depth", "1", "--duration-format", "compact"])
+        .assert()
+        .stdout("acme   1h 30m\nglobex 10m\n1h 40m\n")
+        .success();
+    track(&dir)?.args(["report", "--by", "project", "--depth", "0"]).assert().failure();
+    track(&dir)?
+        .args(["report", "--by", "project", "--collapse", "acme /website", "--duration-format", "compact"])
+        .assert()
+        .stdout("acme      1h 30m\n  website 1h 0m\n  api     30m\nglobex    10m\n1h 40m\n")
+        .success();
+
+    track(&dir)?.args(["project", "move", "acme/website", "globex/web"]).assert().success();
+    track(&dir)?.args(["project", "move", "globex", "globex/old"]).assert().failure();
+    track(&dir)?
+        .args(["project", "list"])
+        .assert()
+        .stdout("acme\n  api\nglobex\n  web\n    frontend\ninitech\n  billing\n")
+        .success();
+    Ok(())
+}
+
+#[test]
+fn clients_own_projects_and_group_reports() -> TestResult {
+    let dir = TempDir::new()?;
+    let now = Local::now();
+    track(&dir)?
+        .args(["client", "add", "Acme", "--currency", "eur", "--rate", "95", "-p", "acme"])
+        .assert()
+        .success();
+    track(&dir)?.args(["client", "add", "Acme"]).assert().failure();
+    track(&dir)?.args(["client", "add", "Globex", "--rate", "-3"]).assert().failure();
+    track(&dir)?
+        .args(["client", "edit", "Acme", "--tax-id", "DE123", "-p", "initech"])
+        .assert()
+        .success();
+    track(&dir)?.args(["client", "edit", "Nobody", "--rate", "1"]).assert().failure();
+    track(&dir)?
+        .args(["client", "list"])
+        .assert()
+        .stdout("Acme\n  currency: EUR\n  rate: 95.00\n  tax id: DE123\n  projects: acme, initech\n")
+        .success();
+
+    for (from, to, project) in [(90, 60, "acme/website"), (60, 45, "initech"), (45, 40, "globex")] {
+        track(&dir)?
+            .args(["add", "--from", &minutes_before(now, from), "--to", &minutes_before(now, to), "-p", project])
+            .assert()
+            .success();
+    }
+    track(&dir)?
+        .args(["report", "--by", "client", "--duration-format", "compact"])
+        .assert()
+        .stdout("Acme      45m\n(unknown) 5m\n50m\n")
+        .success();
+    Ok(())
+}
+
+#[test]
+fn moved_projects_keep_their_client() -> TestResult {
+    let dir = TempDir::new()?;
+    let now = Local::now();
+    track(&dir)?
+        .args(["client", "add", "Acme", "--currency", "EUR", "--rate", "100", "-p", "acme/web"])
+        .assert()
+        .success();
+    track(&dir)?
+        .args(["add", "--from", &minutes_before(now, 90), "--to", &minutes_before(now, 30), "-p", "acme/web"])
+        .assert()
+        .success();
+    track(&dir)?.args(["project", "move", "acme/web", "other/web"]).assert().success();
+    track(&dir)?
+        .args(["client", "list"])
+        .assert()
+        .stdout("Acme\n  currency: EUR\n  rate: 100.00\n  projects: other/web\n")
+        .success();
+    track(&dir)?
+        .args(["report", "--billing"])
+        .assert()
+        .stdout("Acme  01:00:00 €100.00\ntotal 01:00:00 €100.00\n")
+        .success();
+    Ok(())
+}
+
+#[test]
+fn moved_projects_keep_their_rate() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?.args(["rate", "set", "120", "--project", "acme/web"]).assert().success();
+    track(&dir)?.args(["rate", "set", "90", "--project", "globex"]).assert().success();
+    track(&dir)?.args(["project", "move", "acme/web", "other/web"]).assert().success();
+    track(&dir)?
+        .args(["rate", "list"])
+        .assert()
+        .stdout("project globex: 90.00\nproject other/web: 120.00\n")
+        .success();
+    let assert = track(&dir)?.args(["project", "move", "other/web", "globex"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("cannot move a rate onto globex, which already has one"), "{stderr}");
+    track(&dir)?
+        .args(["rate", "list"])
+        .assert()
+        .stdout("project globex: 90.00\nproject other/web: 120.00\n")
+        .success();
+    Ok(())
+}
+
+#[test]
+fn billing_reports_use_the_most_specific_rate() -> TestResult {
+    let dir = TempDir::new()?;
+    let now = Local::now();
+    track(&dir)?
+        .args(["client", "add", "Acme", "--currency", "EUR", "--rate", "80", "-p", "acme"])
+        .assert()
+        .success();
+    track(&dir)?.args(["rate", "set", "100", "--project", "acme/website"]).assert().success();
+    track(&dir)?.args(["rate", "set", "200", "--tag", "urgent"]).assert().success();
+    track(&dir)?.args(["rate", "set", "50"]).assert().failure();
+    track(&dir)?
+        .args(["rate", "list"])
+        .assert()
+        .stdout("client Acme: 80.00\nproject acme/website: 100.00\ntag urgent: 200.00\n")
+        .success();
+
+    for (from, to, args) in [
+        (180, 120, vec!["-p", "acme/api"]),
+        (120, 90, vec!["-p", "acme/website/frontend"]),
+        (90, 75, vec!["-p", "acme/website", "-t", "urgent"]),
+        (75, 60, vec!["-p", "acme/website", "--non-billable"]),
+        (60, 30, vec!["-p", "globex"]),
+    ] {
+        track(&dir)?
+            .args(["add", "--from", &minutes_before(now, from), "--to", &minutes_before(now, to)])
+            .args(args)
+            .assert()
+            .success();
+    }
+    track(&dir)?
+        .args(["report", "--billing"])
+        .assert()
+        .stdout("Acme      02:00:00 €180.00\n(unknown) 00:30:00 -\ntotal     02:30:00 €180.00\n")
+        .success();
+    track(&dir)?
+        .args(["report", "--billing", "--by", "project", "--duration-format", "decimal"])
+        .assert()
+        .stdout(
+            "acme/api              1.00 €80.00\n\
+             acme/website          0.50 €50.00\n\
+             acme/website/frontend 0.50 €50.00\n\
+             globex                0.50 -\n\
+             total                 2.50 €180.00\n"
+        )
+        .success();
+    Ok(())
+}
+
+#[test]
+fn billing_reports_show_raw_and_rounded_time() -> TestResult {
+    let dir = TempDir::new()?;
+    let now = Local::now();
+    track(&dir)?
+        .args(["client", "add", "Acme", "--currency", "EUR", "--rate", "60", "-p", "acme"])
+        .assert()
+        .success();
+    for (from, to, project) in [(60, 50, "acme/api"), (50, 30, "acme/api"), (30, 20, "globex")] {
+        track(&dir)?
+            .args(["add", "--from", &minutes_before(now, from), "--to", &minutes_before(now, to), "-p", project])
+            .assert()
+            .success();
+    }
+    track(&dir)?.args(["config", "rounding", "15m sideways"]).assert().failure();
+    track(&dir)?.args(["config", "rounding", "15m up"]).assert().success();
+    track(&dir)?
+        .args(["report", "--billing"])
+        .assert()
+        .stdout(
+            "               raw  rounded amount\n\
+             Acme      00:30:00 00:45:00 €45.00\n\
+             (unknown) 00:10:00 00:10:00 -\n\
+             total     00:40:00 00:55:00 €45.00\n"
+        )
+        .success();
+
+    track(&dir)?.args(["client", "edit", "Acme", "--rounding", "off"]).assert().success();
+    track(&dir)?
+        .args(["report", "--billing", "--duration-format", "decimal"])
+        .assert()
+        .stdout(
+            "           raw rounded amount\n\
+             Acme      0.50    0.50 €30.00\n\
+             (unknown) 0.17    0.17 -\n\
+             total     0.67    0.67 €30.00\n"
+        )
+        .success();
+    Ok(())
+}
+
+#[test]
+fn invoices_bill_unbilled_time_once() -> TestResult {
+    let dir = TempDir::new()?;
+    let now = Local::now();
+    let today = now.date_naive();
+    let period = format!("{}..{today}", today - chrono::Duration::days(1));
+    track(&dir)?
+        .args(["client", "add", "Acme", "--currency", "EUR", "--rate", "100", "--tax-rate", "19"])
+        .args(["--address", "1 Main St", "-p", "acme"])
+        .assert()
+        .success();
+    for (from, to, args) in [
+        (180, 120, vec!["-p", "acme/api"]),
+        (120, 90, vec!["-p", "acme/website"]),
+        (90, 60, vec!["-p", "acme/website", "--non-billable"]),
+        (60, 30, vec!["-p", "globex"]),
+    ] {
+        track(&dir)?
+            .args(["add", "--from", &minutes_before(now, from), "--to", &minutes_before(now, to)])
+            .args(args)
+            .assert()
+            .success();
+    }
+
+    track(&dir)?
+        .args(["invoice", "--client", "Acme", "--period", &period, "--format", "markdown", "--dry-run"])
+        .assert()
+        .stdout(
+            format!(
+                "# Invoice {year}-001\n\n\
+                 **Date:** {today}  \n\
+                 **Period:** {period}\n\n\
+                 ## Bill to\n\n\
+                 **Acme**  \n\
+                 1 Main St  \n\n\
+                 | Description | Hours | Rate | Amount |\n\
+                 | --- | ---: | ---: | ---: |\n\
+                 | acme/api | 1.00 | €100.00 | €100.00 |\n\
+                 | acme/website | 0.50 | €100.00 | €50.00 |\n\
+                 | **Subtotal** | 1.50 | | €150.00 |\n\
+                 | Tax (19%) | | | €28.50 |\n\
+                 | **Total** | | | **€178.50** |\n",
+                year = today.format("%Y")
+            )
+        )
+        .success();
+
+    let output = dir.path().join("invoice.txt");
+    track(&dir)?
+        .args(["invoice", "--client", "Acme", "--period", &period, "--number", "A-7", "--tax-rate", "0"])
+        .args(["-o", output.to_str().unwrap()])
+        .assert()
+        .stdout("")
+        .stderr(format!("invoice A-7 for Acme ({period}): 2 entries, €150.00.\n"))
+        .success();
+    let text = std::fs::read_to_string(&output)?;
+    assert!(text.starts_with("INVOICE A-7\n"));
+    assert!(text.contains("acme/website\n    0.50 h x €100.00 = €50.00\n"));
+    assert!(text.ends_with("Subtotal: €150.00\nTax (0%): €0.00\nTotal:    €150.00\n"));
+
+    track(&dir)?.args(["invoice", "--client", "Acme", "--period", &period]).assert().failure();
+    track(&dir)?
+        .args(["invoice", "--client", "Acme", "--period", &period, "--number", "A-7"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn invoiced_entries_cannot_be_merged_or_split() -> TestResult {
+    let dir = TempDir::new()?;
+    let now = Local::now();
+    let today = now.date_naive();
+    let period = format!("{}..{today}", today - chrono::Duration::days(1));
+    track(&dir)?
+        .args(["client", "add", "Acme", "--currency", "EUR", "--rate", "100", "-p", "acme"])
+        .assert()
+        .success();
+    let add = |from: i64, to: i64| -> TestResult {
+        track(&dir)?
+            .args(["add", "--from", &minutes_before(now, from), "--to", &minutes_before(now, to), "-p", "acme"])
+            .assert()
+            .success();
+        Ok(())
+    };
+    add(60, 30)?;
+    track(&dir)?
+        .args(["invoice", "--client", "Acme", "--period", &period, "--number", "A-1"])
+        .assert()
+        .stderr(format!("invoice A-1 for Acme ({period}): 1 entries, €50.00.\n"))
+        .success();
+    add(30, 15)?;
+
+    track(&dir)?.args(["merge", "1", "2"]).assert().failure();
+    track(&dir)?.args(["split", "1", "--at", &minutes_before(now, 45)]).assert().failure();
+    track(&dir)?
+        .args(["invoice", "--client", "Acme", "--period", &period, "--number", "A-2"])
+        .assert()
+        .stderr(format!("invoice A-2 for Acme ({period}): 1 entries, €25.00.\n"))
+        .success();
+    Ok(())
+}
+
+#[test]
+fn budgets_warn_at_thresholds() -> TestResult {
+    let dir = TempDir::new()?;
+    let now = Local::now();
+    track(&dir)?.args(["client", "add", "Acme", "--rate", "100", "-p", "acme"]).assert().success();
+    track(&dir)?.args(["budget", "set", "acme/website", "--time", "2h"]).assert().success();
+    track(&dir)?
+        .args(["budget", "set", "acme", "--money", "300", "--thresholds", "50,100"])
+        .assert()
+        .success();
+    track(&dir)?.args(["budget", "set", "acme", "--time", "1h", "--money", "1"]).assert().failure();
+    for (from, to, project) in [(180, 120, "acme/website/frontend"), (120, 90, "acme/api")] {
+        track(&dir)?
+            .args(["add", "--from", &minutes_before(now, from), "--to", &minutes_before(now, to), "-p", project])
+            .assert()
+            .success();
+    }
+
+    track(&dir)?
+        .args(["budget"])
+        .assert()
+        .stdout(
+            "acme/website 01:00:00 of 02:00:00 (50%) 01:00:00 left\n\
+             acme         150.00 of 300.00 (50%)     150.00 left !\n"
+        )
+        .success();
+    track(&dir)?
+        .args(["budget", "show", " acme / website ", "--duration-format", "compact"])
+        .assert()
+        .stdout(
+            format!(
+                "acme/website: 2h 0m budget\nweek      used remaining\n{} 1h 0m     1h 0m\n",
+                (now - chrono::Duration::hours(3)).format("%G-W%V")
+            )
+        )
+        .success();
+    track(&dir)?
+        .args(["budget", "show", "acme/website", "--format", "json"])
+        .assert()
+        .stdout(
+            format!(
+                "[\n  {{\n    \"project\": \"acme/website\",\n    \"week\": \"{}\",\n    \"used\": 3600,\n    \"remaining\": 3600\n  }}\n]\n",
+                (now - chrono::Duration::hours(3)).format("%G-W%V")
+            )
+        )
+        .success();
+    track(&dir)?
+        .args(["budget", "--format", "csv", "show", "acme/website"])
+        .assert()
+        .stdout(
+            format!(
+                "project,week,used,remaining\nacme/website,{},3600,3600\n",
+                (now - chrono::Duration::hours(3)).format("%G-W%V")
+            )
+        )
+        .success();
+
+    track(&dir)?
+        .args(["report"])
+        .assert()
+        .stdout("01:30:00\n")
+        .stderr("warning: acme has used 50% of its budget (150.00 of 300.00)\n")
+        .success();
+    track(&dir)?
+        .args(["start", "-p", "acme/website", "--non-billable"])
+        .assert()
+        .stderr("warning: acme has used 50% of its budget (150.00 of 300.00)\n")
+        .success();
+    let assert = track(&dir)?.args(["status"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("budget acme/website: 01:00:0"));
+    assert!(stdout.contains("budget acme: 150.00 of 300.00 (50%) !\n"));
+    track(&dir)?.args(["stop"]).assert().success();
+    track(&dir)?.args(["project", "move", "acme/website", "initech/website"]).assert().success();
+    track(&dir)?
+        .args(["budget", "show", "initech/website", "--duration-format", "compact"])
+        .assert()
+        .stdout(
+            format!(
+                "initech/website: 2h 0m budget\nweek      used remaining\n{} 1h 0m     1h 0m\n",
+                (now - chrono::Duration::hours(3)).format("%G-W%V")
+            )
+        )
+        .success();
+    track(&dir)?.args(["budget", "show", "acme/website"]).assert().failure();
+    track(&dir)?.args(["budget", "set", "globex", "--time", "1h"]).assert().success();
+    let assert = track(&dir)?.args(["project", "move", "initech/website", "globex"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("cannot move a budget onto globex, which already has one"), "{stderr}");
+    track(&dir)?.args(["client", "add", "Globex", "--currency", "USD", "-p", "acme/legacy"]).assert().success();
+    track(&dir)?.args(["budget", "set", "acme", "--money", "400"]).assert().failure();
+    track(&dir)?.args(["budget", "show", "acme"]).assert().failure();
+    track(&dir)?.args(["budget", "unset", "acme"]).assert().success();
+    track(&dir)?.args(["budget", "unset", "acme"]).assert().failure();
+    Ok(())
+}
+
+#[test]
+fn targets_show_progress_and_remaining_time() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?.args(["config", "daily-target", "8h mon-sun"]).assert().success();
+    track(&dir)?.args(["config", "weekly-target", "40h"]).assert().success();
+    track(&dir)?.args(["config", "daily-target", "8h someday"]).assert().failure();
+    track(&dir)?.args(["config", "daily-target"]).assert().stdout("8h 0m mon-sun\n").success();
+    track(&dir)?.args(["config", "day-start", &day_start_away_from(Local::now())]).assert().success();
+
+    let assert = track(&dir)?.args(["status", "--duration-format", "compact"]).assert().code(3);
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("not running\ntoday: 0m\nday:  [--------------------] 0m of 8h 0m, 8h 0m left"));
+    track(&dir)?.args(["start", "--at", "10 minutes ago"]).assert().success();
+    let assert = track(&dir)?.args(["status", "--duration-format", "compact"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("\nday:  [--------------------] 10m of 8h 0m, 7h 50m left, done at "));
+    assert!(stdout.contains(" of 40h 0m, "), "unexpected status: {stdout}");
+    Ok(())
+}
+
+#[test]
+fn balance_sums_up_overtime_against_the_schedule() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?.args(["balance"]).assert().failure();
+    let yesterday = (Local::now() - Duration::days(1)).format("%Y-%m-%d").to_string();
+    track(&dir)?.args(["config", "schedule", "8h mon-sun"]).assert().success();
+    track(&dir)?.args(["config", "schedule-start", &yesterday]).assert().success();
+    track(&dir)?.args(["add", "--from", "yesterday 9am", "--to", "yesterday noon"]).assert().success();
+    track(&dir)?.args(["add", "--from", "today 00:00", "--to", "now"]).assert().success();
+
+    let assert = track(&dir)?.args(["balance", "--duration-format", "compact"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("week     expected worked balance running\n"), "unexpected balance: {stdout}");
+    assert!(stdout.contains(" 8h 0m  3h 0m  -5h 0m  -5h 0m\n"), "unexpected balance: {stdout}");
+    assert!(stdout.ends_with("\nbalance: -5h 0m\n"));
+    let assert = track(&dir)?.args(["balance", "--by", "month", "--duration-format", "compact"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("month"), "unexpected balance: {stdout}");
+    assert!(stdout.ends_with("\nbalance: -5h 0m\n"));
+    Ok(())
+}
+
+#[test]
+fn days_off_are_not_counted_as_missing_time() -> TestResult {
+    let dir = TempDir::new()?;
+    let day = |days: i64| (Local::now() - Duration::days(days)).format("%Y-%m-%d").to_string();
+    track(&dir)?.args(["config", "schedule", "8h mon-sun"]).assert().success();
+    track(&dir)?.args(["config", "schedule-start", &day(2)]).assert().success();
+    track(&dir)?.args(["config", "daily-target", "8h mon-sun"]).assert().success();
+    track(&dir)?.args(["add", "--from", &format!("{} 09:00", day(2)), "--duration", "3h"]).assert().success();
+    track(&dir)?.args(["off", "add", "--date", "yesterday", "--kind", "vacation"]).assert().success();
+    track(&dir)?.args(["off", "add", "--date", "today", "--kind", "sick"]).assert().success();
+    track(&dir)?.args(["off", "add", "--date", "today", "--until", "yesterday"]).assert().failure();
+
+    let assert = track(&dir)?.args(["balance", "--duration-format", "compact"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains(" 1 vacation\n"), "unexpected balance: {stdout}");
+    assert!(stdout.ends_with("\nbalance: -5h 0m\n"), "unexpected balance: {stdout}");
+    let assert = track(&dir)?.args(["status"]).assert().code(3);
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("\nday:  off, sick\n"), "unexpected status: {stdout}");
+    track(&dir)?
+        .args(["report", "--from", &format!("{} 00:00", day(2)), "--group-by", "day", "--duration-format", "compact"])
+        .assert()
+        .stdout(format!("{}            3h 0m\n{} (vacation) 0m\n{} (sick)     0m\n3h 0m\n", day(2), day(1), day(0)))
+        .success();
+
+    let holidays = dir.path().join("holidays.csv");
+    std::fs::write(&holidays, format!("date,name\n{},Founders Day\n", day(-1)))?;
+    track(&dir)?
+        .arg("-v")
+        .arg("off")
+        .arg("import")
+        .arg(&holidays)
+        .assert()
+        .stdout("1 day(s) off imported.\n")
+        .success();
+    track(&dir)?
+        .args(["off", "list", "--from", "today"])
+        .assert()
+        .stdout(format!("{} sick\n{} holiday (Founders Day)\n", day(0), day(-1)))
+        .success();
+    track(&dir)?.args(["off", "remove", "--date", "today"]).assert().success();
+    track(&dir)?.args(["off", "remove", "--date", "today"]).assert().failure();
+    track(&dir)?.args(["off", "remove", "--date", "yesterday", "--until", &day(-1)]).assert().success();
+    track(&dir)?.args(["off", "list"]).assert().stdout("no days off\n").success();
+    track(&dir)?.args(["off", "remove", "--date", "yesterday", "--until", &day(-1)]).assert().failure();
+    Ok(())
+}
+
+#[test]
+fn grouped_reports_nest_with_subtotals() -> TestResult {
+    let dir = TempDir::new()?;
+    let yesterday = (Local::now() - Duration::days(1)).format("%Y-%m-%d").to_string();
+    track(&dir)?
+        .args(["add", "--from", "yesterday 9am", "--to", "yesterday 11am", "-p", "api", "-t", "code"])
+        .assert()
+        .success();
+    track(&dir)?.args(["add", "--from", "yesterday 1pm", "--to", "yesterday 2pm", "-p", "web"]).assert().success();
+
+    let report = ["report", "--from", "2 days ago", "--duration-format", "compact", "--group-by"];
+    track(&dir)?
+        .args(report)
+        .arg("day,project")
+        .assert()
+        .stdout(format!("{yesterday} 3h 0m\n  api      2h 0m\n  web      1h 0m\n3h 0m\n"))
+        .success();
+    track(&dir)?.args(report).arg("tag").assert().stdout("code      2h 0m\n(unknown) 1h 0m\n3h 0m\n").success();
+    track(&dir)?.args(report).arg("fortnight").assert().failure();
+    track(&dir)?.args(report).args(["project", "--by", "client"]).assert().failure();
+    track(&dir)?.args(["report", "--from", "now", "--to", "yesterday"]).assert().failure();
+    Ok(())
+}
+
+#[test]
+fn reports_and_lists_print_machine_readable_records() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?
+        .args(["add", "--from", "yesterday 9am", "--to", "yesterday 10am", "-p", "api", "-t", "code"])
+        .assert()
+        .success();
+
+    let report = ["report", "--from", "2 days ago", "--format"];
+    let assert = track(&dir)?.args(report).arg("ndjson").assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    let records: Vec<serde_json::Value> = stdout.lines().map(serde_json::from_str).collect::<Result<_, _>>()?;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["seconds"], 3600);
+    assert_eq!(records[0]["project"], "api");
+    assert_eq!(records[0]["tags"], serde_json::json!(["code"]));
+    assert_eq!(records[0]["note"], serde_json::Value::Null);
+    let assert = track(&dir)?.args(report).arg("json").assert().success();
+    let json: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(json, serde_json::Value::Array(records));
+
+    track(&dir)?.args(report).args(["csv", "--by", "project"]).assert().stdout("key,seconds\napi,3600\n").success();
+    track(&dir)?
+        .args(report)
+        .args(["csv", "--group-by", "project,tag"])
+        .assert()
+        .stdout("project,tag,seconds\napi,,3600\napi,code,3600\n,,3600\n")
+        .success();
+    track(&dir)?.args(report).arg("xml").assert().failure();
+
+    track(&dir)?.args(["project", "list", "--format", "json"]).assert().stdout("[\n  {\n    \"path\": \"api\"\n  }\n]\n").success();
+    track(&dir)?.args(["off", "add", "--date", "2026-12-24", "--kind", "vacation"]).assert().success();
+    track(&dir)?
+        .args(["off", "list", "--format", "csv"])
+        .assert()
+        .stdout("date,kind,name\n2026-12-24,vacation,\n")
+        .success();
+    track(&dir)?.args(["rate", "list", "--format", "ndjson"]).assert().stdout("").success();
+    Ok(())
+}
+
+#[test]
+fn reports_render_as_markdown_and_html() -> TestResult {
+    let dir = TempDir::new()?;
+    track(&dir)?
+        .args(["add", "--from", "yesterday 9am", "--to", "yesterday 11am", "-p", "acme/api"])
+        .assert()
+        .success();
+    track(&dir)?
+        .args(["add", "--from", "yesterday 1pm", "--to", "yesterday 2pm", "-p", "acme/web"])
+        .assert()
+        .success();
+
+    let report = ["report", "--from", "2 days ago", "--by", "project", "--format"];
+    let assert = track(&dir)?.args(report).arg("markdown").assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("# Time by project\n\n"));
+    assert!(
+        stdout.ends_with(
+            "| project | time |\n| :-- | --: |\n| acme | 03:00:00 |\n| &nbsp;&nbsp;api | 02:00:00 |\n\
+            | &nbsp;&nbsp;web | 01:00:00 |\n| **total** | **03:00:00** |\n"
+        )
+    );
+
+    let assert = track(&dir)?.args(report).args(["html", "--charts"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("<!DOCTYPE html>"));
+    assert!(stdout.contains("<style>"));
+    assert!(stdout.contains("<div class=\"bar\" style=\"width: 66.7%\""));
+    assert!(stdout.trim_end().ends_with("</html>"));
+
+    let assert = track(&dir)?.args(["report", "--from", "2 days ago", "--format", "markdown"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("| entry | start | stop | project | time |"));
+    assert!(stdout.ends_with("| **total** |  |  |  | **03:00:00** |\n"));
+    track(&dir)?.args(["report", "--charts"]).assert().failure();
+    track(&dir)?.args(report).args(["markdown", "--charts"]).assert().failure();
     Ok(())
 }